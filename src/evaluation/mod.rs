@@ -0,0 +1,2 @@
+pub mod recall;
+pub mod vecs;
@@ -0,0 +1,250 @@
+//! Recall@k evaluation: build a `Segment` from a dataset, run every query through both the HNSW
+//! graph and exact search, and report recall@k, QPS and latency percentiles per metric and `ef`.
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use crate::evaluation::vecs;
+use crate::segment::segment::Segment;
+use crate::utils::errors::DBError;
+use crate::utils::types::{DistanceMetric, PointId, Vector};
+use crate::vector::hnsw::{HNSWIndex, SearchParams};
+
+/// Base vectors to index plus the queries to run against them.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub name: String,
+    pub vectors: Vec<Vector>,
+    pub queries: Vec<Vector>,
+    /// Precomputed neighbors shipped with the dataset. Metrics without one fall back to exact search.
+    pub ground_truth: Option<GroundTruth>,
+}
+
+/// Per-query nearest neighbors as row indices into `Dataset::vectors`, best first.
+#[derive(Debug, Clone)]
+pub struct GroundTruth {
+    pub metric: DistanceMetric,
+    pub neighbors: Vec<Vec<usize>>,
+}
+
+impl Dataset {
+    pub fn new(name: impl Into<String>, vectors: Vec<Vector>, queries: Vec<Vector>) -> Self {
+        Self { name: name.into(), vectors, queries, ground_truth: None }
+    }
+
+    pub fn with_ground_truth(mut self, metric: DistanceMetric, neighbors: Vec<Vec<usize>>) -> Self {
+        self.ground_truth = Some(GroundTruth { metric, neighbors });
+        self
+    }
+
+    /// Load a SIFT-style dataset: base and query `.fvecs`/`.bvecs` files plus an optional
+    /// ground-truth `.ivecs` file computed under `metric`. `limit` caps the number of base
+    /// vectors; shipped ground truth is only valid for the full base set, so leave it `None`
+    /// when passing a ground-truth file.
+    pub fn from_files(
+        name: impl Into<String>,
+        base: impl AsRef<Path>,
+        queries: impl AsRef<Path>,
+        ground_truth: Option<(&Path, DistanceMetric)>,
+        limit: Option<usize>,
+    ) -> Result<Self, DBError> {
+        let mut dataset = Self::new(name, vecs::read_vectors(base, limit)?, vecs::read_vectors(queries, None)?);
+        if let Some((path, metric)) = ground_truth {
+            dataset = dataset.with_ground_truth(metric, vecs::read_ground_truth(path, None)?);
+        }
+        Ok(dataset)
+    }
+
+    pub fn dim(&self) -> usize {
+        self.vectors.first().map_or(0, |v| v.len())
+    }
+}
+
+/// Index parameters plus the grid of metrics and search-time `ef` values to sweep.
+#[derive(Debug, Clone)]
+pub struct EvalConfig {
+    pub metrics: Vec<DistanceMetric>,
+    pub ef_values: Vec<usize>,
+    pub top_k: usize,
+    pub m: usize,
+    pub ef_construction: usize,
+    pub max_level_cap: usize,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            metrics: vec![DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot],
+            ef_values: vec![16, 32, 64, 128],
+            top_k: 10,
+            m: 16,
+            ef_construction: 64,
+            max_level_cap: 16,
+        }
+    }
+}
+
+/// Results for one (metric, ef) cell of the sweep.
+#[derive(Debug, Clone)]
+pub struct EvalReport {
+    pub dataset: String,
+    pub metric: DistanceMetric,
+    pub ef: usize,
+    pub top_k: usize,
+    pub num_queries: usize,
+    /// Mean fraction of the exact top-k found by the graph search.
+    pub recall: f64,
+    /// Graph-search queries per second, measured over the whole query set.
+    pub qps: f64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?} ef={:<4} recall@{}={:.4} qps={:.1} p50={:?} p95={:?} p99={:?}",
+            self.dataset, self.metric, self.ef, self.top_k, self.recall, self.qps, self.p50, self.p95, self.p99
+        )
+    }
+}
+
+/// Insert every vector of `dataset` into a fresh segment. Returns the segment and the point ID
+/// assigned to each vector, in dataset order.
+pub fn build_segment(
+    dataset: &Dataset,
+    metric: DistanceMetric,
+    config: &EvalConfig,
+) -> Result<(Segment, Vec<PointId>), DBError> {
+    let hnsw = HNSWIndex::new(metric, config.m, config.ef_construction, config.max_level_cap, dataset.dim());
+    let mut segment = Segment::new(hnsw);
+    // Always measure the graph, never the small-segment exact fallback.
+    segment.set_full_scan_threshold(0);
+    let ids = dataset
+        .vectors
+        .iter()
+        .map(|v| segment.insert(v.clone(), None))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((segment, ids))
+}
+
+/// Exact top-k IDs for every query, used as ground truth.
+pub fn exact_neighbors(segment: &Segment, queries: &[Vector], top_k: usize) -> Result<Vec<Vec<PointId>>, DBError> {
+    queries
+        .iter()
+        .map(|q| Ok(segment.exact_search(q, top_k, None)?.into_iter().map(|sp| sp.id).collect()))
+        .collect()
+}
+
+/// Translate shipped ground truth from dataset rows into the point IDs `build_segment` assigned.
+fn ground_truth_ids(
+    ground_truth: &GroundTruth,
+    ids: &[PointId],
+    num_queries: usize,
+    top_k: usize,
+) -> Result<Vec<Vec<PointId>>, DBError> {
+    if ground_truth.neighbors.len() != num_queries {
+        return Err(DBError::SerializationError(anyhow!(
+            "ground truth has {} rows for {} queries",
+            ground_truth.neighbors.len(),
+            num_queries
+        )));
+    }
+    ground_truth
+        .neighbors
+        .iter()
+        .map(|row| {
+            row.iter()
+                .take(top_k)
+                .map(|&idx| {
+                    ids.get(idx).copied().ok_or_else(|| {
+                        DBError::SerializationError(anyhow!(
+                            "ground truth references row {} but the dataset has {} vectors",
+                            idx,
+                            ids.len()
+                        ))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Fraction of `truth` present in `found`. An empty ground truth counts as full recall.
+pub fn recall_at_k(found: &[PointId], truth: &[PointId]) -> f64 {
+    if truth.is_empty() {
+        return 1.0;
+    }
+    let truth: HashSet<_> = truth.iter().collect();
+    found.iter().filter(|id| truth.contains(id)).count() as f64 / truth.len() as f64
+}
+
+/// Nearest-rank percentile of an ascending-sorted slice.
+fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Run `queries` through the graph at the given `ef` and score them against `truth`.
+pub fn evaluate_segment(
+    segment: &Segment,
+    dataset: &str,
+    queries: &[Vector],
+    truth: &[Vec<PointId>],
+    top_k: usize,
+    ef: usize,
+) -> Result<EvalReport, DBError> {
+    let params = SearchParams::with_ef(ef);
+    let mut latencies = Vec::with_capacity(queries.len());
+    let mut recall_sum = 0.0;
+
+    let total = Instant::now();
+    for (query, expected) in queries.iter().zip(truth) {
+        let start = Instant::now();
+        let results = segment.search_with_params(query, top_k, &params)?;
+        latencies.push(start.elapsed());
+
+        let found: Vec<_> = results.iter().map(|sp| sp.id).collect();
+        recall_sum += recall_at_k(&found, expected);
+    }
+    let elapsed = total.elapsed();
+    latencies.sort_unstable();
+
+    let num_queries = queries.len();
+    Ok(EvalReport {
+        dataset: dataset.to_string(),
+        metric: segment.hnsw().metric(),
+        ef,
+        top_k,
+        num_queries,
+        recall: if num_queries == 0 { 1.0 } else { recall_sum / num_queries as f64 },
+        qps: if elapsed.is_zero() { 0.0 } else { num_queries as f64 / elapsed.as_secs_f64() },
+        p50: percentile(&latencies, 50.0),
+        p95: percentile(&latencies, 95.0),
+        p99: percentile(&latencies, 99.0),
+    })
+}
+
+/// Build one segment per metric and sweep every `ef` value, returning one report per cell.
+pub fn evaluate(dataset: &Dataset, config: &EvalConfig) -> Result<Vec<EvalReport>, DBError> {
+    let mut reports = Vec::with_capacity(config.metrics.len() * config.ef_values.len());
+    for &metric in &config.metrics {
+        let (segment, ids) = build_segment(dataset, metric, config)?;
+        let truth = match &dataset.ground_truth {
+            Some(gt) if gt.metric == metric => ground_truth_ids(gt, &ids, dataset.queries.len(), config.top_k)?,
+            _ => exact_neighbors(&segment, &dataset.queries, config.top_k)?,
+        };
+        for &ef in &config.ef_values {
+            reports.push(evaluate_segment(&segment, &dataset.name, &dataset.queries, &truth, config.top_k, ef)?);
+        }
+    }
+    Ok(reports)
+}
@@ -0,0 +1,234 @@
+//! Readers and writers for the `.fvecs` / `.ivecs` / `.bvecs` files used by the SIFT, GIST and
+//! Deep1B benchmark datasets.
+//!
+//! Every record is `dim i32 (little-endian) | dim components`, where a component is an `f32`,
+//! an `i32` or a `u8` respectively. Records are read one at a time, so loading into a segment
+//! never holds the whole file in memory.
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::segment::segment::Segment;
+use crate::utils::errors::DBError;
+use crate::utils::types::{PointId, Vector};
+
+/// Component type of a vecs file, picked from its extension by `VecsFormat::from_path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VecsFormat {
+    Fvecs,
+    Ivecs,
+    Bvecs,
+}
+
+impl VecsFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "fvecs" => Some(VecsFormat::Fvecs),
+            "ivecs" => Some(VecsFormat::Ivecs),
+            "bvecs" => Some(VecsFormat::Bvecs),
+            _ => None,
+        }
+    }
+
+    fn component_size(self) -> usize {
+        match self {
+            VecsFormat::Fvecs | VecsFormat::Ivecs => 4,
+            VecsFormat::Bvecs => 1,
+        }
+    }
+}
+
+/// Streams raw records out of a vecs file. Each item is the record's component bytes.
+struct RecordReader<R> {
+    reader: R,
+    format: VecsFormat,
+    dim: Option<usize>,
+    remaining: Option<usize>,
+    index: usize,
+}
+
+impl<R: Read> RecordReader<R> {
+    fn new(reader: R, format: VecsFormat, limit: Option<usize>) -> Self {
+        Self { reader, format, dim: None, remaining: limit, index: 0 }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Vec<u8>>, DBError> {
+        if self.remaining == Some(0) {
+            return Ok(None);
+        }
+
+        let mut dim_bytes = [0u8; 4];
+        let read = read_fully(&mut self.reader, &mut dim_bytes)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < dim_bytes.len() {
+            return Err(DBError::SerializationError(anyhow!("truncated dimension at record {}", self.index)));
+        }
+
+        let dim = i32::from_le_bytes(dim_bytes);
+        if dim <= 0 {
+            return Err(DBError::SerializationError(anyhow!("invalid dimension {} at record {}", dim, self.index)));
+        }
+        let dim = dim as usize;
+        match self.dim {
+            Some(expected) if expected != dim => return Err(DBError::VectorLengthMismatch { expected, actual: dim }),
+            _ => self.dim = Some(dim),
+        }
+
+        let mut body = vec![0u8; dim * self.format.component_size()];
+        if read_fully(&mut self.reader, &mut body)? < body.len() {
+            return Err(DBError::SerializationError(anyhow!("truncated record {}", self.index)));
+        }
+
+        self.index += 1;
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+        Ok(Some(body))
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = Result<Vec<u8>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Like `read_exact`, but returns how many bytes were read instead of failing on a short read.
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn open_records(path: &Path, format: VecsFormat, limit: Option<usize>) -> Result<RecordReader<BufReader<File>>, DBError> {
+    Ok(RecordReader::new(BufReader::new(File::open(path)?), format, limit))
+}
+
+fn decode_f32(body: &[u8]) -> Vector {
+    body.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect()
+}
+
+fn decode_i32(body: &[u8]) -> Vec<i32> {
+    body.chunks_exact(4).map(|c| i32::from_le_bytes(c.try_into().unwrap())).collect()
+}
+
+/// Stream float vectors out of an `.fvecs` or `.bvecs` file (bytes are widened to `f32`).
+/// At most `limit` records are read.
+pub fn vector_reader(
+    path: impl AsRef<Path>,
+    format: VecsFormat,
+    limit: Option<usize>,
+) -> Result<impl Iterator<Item = Result<Vector, DBError>>, DBError> {
+    let decode: fn(Vec<u8>) -> Vector = match format {
+        VecsFormat::Fvecs => |body| decode_f32(&body),
+        VecsFormat::Bvecs => |body| body.into_iter().map(f32::from).collect(),
+        VecsFormat::Ivecs => {
+            return Err(DBError::SerializationError(anyhow!("ivecs files hold integer ids, not vectors")));
+        }
+    };
+    Ok(open_records(path.as_ref(), format, limit)?.map(move |record| record.map(decode)))
+}
+
+pub fn read_fvecs(path: impl AsRef<Path>, limit: Option<usize>) -> Result<Vec<Vector>, DBError> {
+    vector_reader(path, VecsFormat::Fvecs, limit)?.collect()
+}
+
+pub fn read_bvecs(path: impl AsRef<Path>, limit: Option<usize>) -> Result<Vec<Vector>, DBError> {
+    vector_reader(path, VecsFormat::Bvecs, limit)?.collect()
+}
+
+pub fn read_ivecs(path: impl AsRef<Path>, limit: Option<usize>) -> Result<Vec<Vec<i32>>, DBError> {
+    open_records(path.as_ref(), VecsFormat::Ivecs, limit)?
+        .map(|record| record.map(|body| decode_i32(&body)))
+        .collect()
+}
+
+/// Read an `.fvecs` or `.bvecs` file, choosing the format from the extension.
+pub fn read_vectors(path: impl AsRef<Path>, limit: Option<usize>) -> Result<Vec<Vector>, DBError> {
+    let path = path.as_ref();
+    let format = VecsFormat::from_path(path)
+        .ok_or_else(|| DBError::SerializationError(anyhow!("unknown vector file extension: {}", path.display())))?;
+    vector_reader(path, format, limit)?.collect()
+}
+
+/// Read a ground-truth `.ivecs` file: for every query, the dataset row indices of its nearest
+/// neighbors, best first.
+pub fn read_ground_truth(path: impl AsRef<Path>, limit: Option<usize>) -> Result<Vec<Vec<usize>>, DBError> {
+    read_ivecs(path, limit)?
+        .into_iter()
+        .enumerate()
+        .map(|(query, row)| {
+            row.into_iter()
+                .map(|idx| {
+                    usize::try_from(idx).map_err(|_| {
+                        DBError::SerializationError(anyhow!("negative neighbor index {} for query {}", idx, query))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Insert every vector of an `.fvecs` / `.bvecs` file into `segment`, without payloads.
+/// Returns the assigned point IDs in file order, so row `i` of the file is `ids[i]`.
+pub fn insert_from_file(
+    segment: &mut Segment,
+    path: impl AsRef<Path>,
+    limit: Option<usize>,
+) -> Result<Vec<PointId>, DBError> {
+    let path = path.as_ref();
+    let format = VecsFormat::from_path(path)
+        .ok_or_else(|| DBError::SerializationError(anyhow!("unknown vector file extension: {}", path.display())))?;
+    let mut ids = Vec::new();
+    for vector in vector_reader(path, format, limit)? {
+        ids.push(segment.insert(vector?, None)?);
+    }
+    Ok(ids)
+}
+
+fn write_records<T>(
+    path: &Path,
+    rows: &[Vec<T>],
+    put: impl Fn(&mut BufWriter<File>, &T) -> io::Result<()>,
+) -> Result<(), DBError> {
+    // Checked before creating the file: the readers reject empty rows, so never write one.
+    if let Some(index) = rows.iter().position(|row| row.is_empty()) {
+        return Err(DBError::SerializationError(anyhow!("row {} is empty", index)));
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    for row in rows {
+        let dim = i32::try_from(row.len())
+            .map_err(|_| DBError::SerializationError(anyhow!("row of length {} is too long", row.len())))?;
+        writer.write_all(&dim.to_le_bytes())?;
+        for x in row {
+            put(&mut writer, x)?;
+        }
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+pub fn write_fvecs(path: impl AsRef<Path>, vectors: &[Vector]) -> Result<(), DBError> {
+    write_records(path.as_ref(), vectors, |w, x| w.write_all(&x.to_le_bytes()))
+}
+
+pub fn write_ivecs(path: impl AsRef<Path>, rows: &[Vec<i32>]) -> Result<(), DBError> {
+    write_records(path.as_ref(), rows, |w, x| w.write_all(&x.to_le_bytes()))
+}
+
+pub fn write_bvecs(path: impl AsRef<Path>, vectors: &[Vec<u8>]) -> Result<(), DBError> {
+    write_records(path.as_ref(), vectors, |w, x| w.write_all(&[*x]))
+}
@@ -2,3 +2,4 @@ pub mod utils;
 pub mod vector;
 pub mod payload_storage;
 pub mod segment;
+pub mod evaluation;
@@ -1,8 +1,12 @@
 use std::collections::{HashMap, HashSet};
+use crate::payload_storage::filters::Filter;
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
 use crate::utils::payload::{Payload, PayloadValue};
 use crate::utils::types::PointId;
 
 /// Inverted index: field_name -> field_value -> set of PointIds
+#[derive(Default)]
 pub struct PayloadIndex {
     index: HashMap<String, HashMap<PayloadValue, HashSet<PointId>>>,
 }
@@ -23,9 +27,9 @@ impl PayloadIndex {
 
             self.index
                 .entry(key.clone())
-                .or_insert_with(HashMap::new)
+                .or_default()
                 .entry(value.clone())
-                .or_insert_with(HashSet::new)
+                .or_default()
                 .insert(point_id);
         }
     }
@@ -60,6 +64,29 @@ impl PayloadIndex {
         self.index.get(key)?.get(value)
     }
 
+    /// Returns a superset of the point IDs that can satisfy `filter`, or `None` when the index
+    /// cannot narrow it down (range comparisons, negations, unindexed values).
+    /// Callers still have to evaluate the filter on every returned ID.
+    pub fn candidates(&self, filter: &Filter) -> Option<HashSet<PointId>> {
+        match filter {
+            Filter::Match { key, value } => {
+                if !Self::is_indexable(value) {
+                    return None;
+                }
+                Some(self.query_exact(key, value).cloned().unwrap_or_default())
+            }
+            Filter::And(conditions) => conditions
+                .iter()
+                .filter_map(|cond| self.candidates(cond))
+                .reduce(|acc, set| acc.intersection(&set).copied().collect()),
+            Filter::Or(conditions) => conditions.iter().try_fold(HashSet::new(), |mut acc, cond| {
+                acc.extend(self.candidates(cond)?);
+                Some(acc)
+            }),
+            Filter::Compare { .. } | Filter::Not(_) => None,
+        }
+    }
+
     fn is_indexable(value: &PayloadValue) -> bool {
         matches!(
             value,
@@ -80,4 +107,40 @@ impl PayloadIndex {
                 })
         })
     }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        let mut keys: Vec<_> = self.index.keys().collect();
+        keys.sort();
+        codec::put_u64(buf, keys.len() as u64);
+        for key in keys {
+            let value_map = &self.index[key];
+            codec::put_str(buf, key);
+            codec::put_u64(buf, value_map.len() as u64);
+            for (value, ids) in value_map {
+                let mut ids: Vec<_> = ids.iter().copied().collect();
+                ids.sort_unstable();
+                codec::put_payload_value(buf, value);
+                codec::put_u64(buf, ids.len() as u64);
+                ids.iter().for_each(|&id| codec::put_u64(buf, id));
+            }
+        }
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let mut index = HashMap::new();
+        let num_keys = dec.length_prefix(9)?;
+        for _ in 0..num_keys {
+            let key = dec.str()?;
+            let num_values = dec.length_prefix(9)?;
+            let mut value_map = HashMap::with_capacity(num_values);
+            for _ in 0..num_values {
+                let value = dec.payload_value()?;
+                let num_ids = dec.length_prefix(8)?;
+                let ids = (0..num_ids).map(|_| dec.u64()).collect::<Result<HashSet<_>, _>>()?;
+                value_map.insert(value, ids);
+            }
+            index.insert(key, value_map);
+        }
+        Ok(Self { index })
+    }
 }
@@ -0,0 +1,88 @@
+//! Translation between caller-facing `ExtendedPointId`s and the dense internal `PointId`s that
+//! graphs and storage are keyed by.
+use std::collections::HashMap;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{ExtendedPointId, PointId};
+
+/// Two-way ID map. Internal IDs are allocated densely by the segment, so the reverse direction is a
+/// plain vector indexed by internal ID. Points inserted without an external ID have no entry.
+#[derive(Debug, Clone, Default)]
+pub struct IdMapping {
+    internal: HashMap<ExtendedPointId, PointId>,
+    external: Vec<Option<ExtendedPointId>>,
+}
+
+impl IdMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn internal_id(&self, id: &ExtendedPointId) -> Option<PointId> {
+        self.internal.get(id).copied()
+    }
+
+    pub fn external_id(&self, point_id: PointId) -> Option<&ExtendedPointId> {
+        self.external.get(point_id as usize).and_then(Option::as_ref)
+    }
+
+    /// Map `id` to `point_id`, replacing whatever either side was mapped to before.
+    pub fn set(&mut self, id: ExtendedPointId, point_id: PointId) {
+        self.remove_internal(point_id);
+        if let Some(old) = self.internal.insert(id.clone(), point_id) {
+            self.external[old as usize] = None;
+        }
+        let slot = point_id as usize;
+        if slot >= self.external.len() {
+            self.external.resize(slot + 1, None);
+        }
+        self.external[slot] = Some(id);
+    }
+
+    /// Forget the external ID of `point_id`, returning it.
+    pub fn remove_internal(&mut self, point_id: PointId) -> Option<ExtendedPointId> {
+        let id = self.external.get_mut(point_id as usize)?.take()?;
+        self.internal.remove(&id);
+        Some(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.internal.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.internal.is_empty()
+    }
+
+    /// `(internal, external)` pairs in internal ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (PointId, &ExtendedPointId)> {
+        self.external.iter().enumerate().filter_map(|(i, id)| id.as_ref().map(|id| (i as PointId, id)))
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        codec::put_u64(buf, self.len() as u64);
+        for (point_id, id) in self.iter() {
+            codec::put_u64(buf, point_id);
+            codec::put_extended_id(buf, id);
+        }
+    }
+
+    /// Inverse of `write_snapshot`. Every internal ID must be below `next_id`, the segment's next
+    /// unallocated ID, which also bounds how far the reverse vector can grow.
+    pub(crate) fn read_snapshot(dec: &mut Decoder, next_id: PointId) -> Result<Self, DBError> {
+        let mut mapping = Self::new();
+        for _ in 0..dec.length_prefix(17)? {
+            let point_id = dec.u64()?;
+            let id = dec.extended_id()?;
+            if point_id >= next_id {
+                return Err(DBError::SerializationError(anyhow::anyhow!("point ID mapping for unallocated ID {}", point_id)));
+            }
+            if mapping.internal.contains_key(&id) || mapping.external_id(point_id).is_some() {
+                return Err(DBError::SerializationError(anyhow::anyhow!("duplicate point ID mapping for {}", id)));
+            }
+            mapping.set(id, point_id);
+        }
+        Ok(mapping)
+    }
+}
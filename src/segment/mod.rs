@@ -1 +1,5 @@
-pub mod segment;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod segment;
+pub mod id_mapping;
+pub mod snapshot;
+pub mod wal;
@@ -1,86 +1,593 @@
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
 
 use crate::payload_storage::filters::{Filter, evaluate_filter};
 use crate::payload_storage::stores::PayloadIndex;
+use crate::segment::id_mapping::IdMapping;
+use crate::segment::snapshot;
+use crate::segment::wal::{IndexConfig, WalRecord, WriteAheadLog};
+use crate::utils::codec::{self, Decoder};
 use crate::utils::errors::DBError;
-use crate::utils::payload::{Payload, PayloadValue};
-use crate::utils::types::{PointId, Vector};
-use crate::vector::hnsw::{HNSWIndex, ScoredPoint};
+use crate::utils::payload::{Payload, PayloadUpdate, PayloadValue};
+use crate::utils::types::{ExtendedPointId, PointId, Vector};
+use crate::vector::fusion::{Fusion, ScoreSource};
+use crate::vector::hnsw::{HNSWIndex, ScoredPoint, SearchParams};
+use crate::vector::multivector::{MultiVector, MultiVectorConfig, MultiVectorStorage};
+use crate::vector::quantization::QuantizationConfig;
+use crate::vector::sparse::{SparseIndex, SparseVector};
 
 /// A segment is the core unit that wraps vector storage, indexing, payloads, and deletion.
 pub struct Segment {
     hnsw: HNSWIndex,
+    // Additional named vectors, each with its own dimension, metric and graph. A point may have a
+    // vector in any subset of them.
+    named: BTreeMap<String, HNSWIndex>,
+    // Optional per-point sparse vectors, searched separately from the graph.
+    sparse: SparseIndex,
+    // Optional per-point multi-vectors for late-interaction re-ranking, once enabled.
+    multivectors: Option<MultiVectorStorage>,
     payload_index: PayloadIndex,
     payloads: HashMap<PointId, Payload>,
     // This set is maintained in parallel with the HNSW deletion set.
     deleted: HashSet<PointId>,
     next_id: PointId,
+    // Caller-facing IDs of the points that were upserted under one.
+    ids: IdMapping,
+    // When present, every mutation is logged here before it is applied.
+    wal: Option<WriteAheadLog>,
+    // Searches that can touch at most this many points skip the graph and scan exhaustively.
+    full_scan_threshold: usize,
 }
 
+/// Default for `Segment::set_full_scan_threshold`.
+pub const DEFAULT_FULL_SCAN_THRESHOLD: usize = 256;
+
+/// Hybrid search fetches this many times `top_k` hits from each source before fusing, so that
+/// points ranked just outside one source's top-k can still win on the other.
+const HYBRID_PREFETCH_FACTOR: usize = 4;
+
+/// `search_with_rerank` re-ranks this many times `top_k` dense candidates.
+const RERANK_PREFETCH_FACTOR: usize = 4;
+
+/// `insert_batch` logs its points in records of at most this many, keeping WAL frames small.
+const INSERT_BATCH_RECORD_LEN: usize = 1024;
+
 impl Segment {
     pub fn new(hnsw: HNSWIndex) -> Self {
         Self {
             hnsw,
+            named: BTreeMap::new(),
+            sparse: SparseIndex::new(),
+            multivectors: None,
             payload_index: PayloadIndex::new(),
             payloads: HashMap::new(),
             deleted: HashSet::new(),
             next_id: 1,
+            ids: IdMapping::new(),
+            wal: None,
+            full_scan_threshold: DEFAULT_FULL_SCAN_THRESHOLD,
+        }
+    }
+
+    /// Create an empty segment backed by a new write-ahead log at `path`.
+    pub fn create(path: impl AsRef<Path>, hnsw: HNSWIndex) -> Result<Self, DBError> {
+        let wal = WriteAheadLog::create(path, IndexConfig::of(&hnsw))?;
+        let mut segment = Self::new(hnsw);
+        segment.wal = Some(wal);
+        Ok(segment)
+    }
+
+    /// Reopen a segment from its write-ahead log, replaying every logged mutation.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DBError> {
+        let (wal, config, records) = WriteAheadLog::open(path)?;
+        let mut segment = Self::new(config.build()?);
+
+        println!("[WAL] Replaying {} records", records.len());
+        for record in records {
+            match record {
+                WalRecord::Insert { point_id, vector, payload, sparse, named, external } => {
+                    segment.apply_insert(point_id, external, vector, payload, sparse, named)?;
+                    segment.next_id = segment.next_id.max(point_id.saturating_add(1));
+                }
+                WalRecord::InsertBatch { points } => {
+                    if let Some(&(last, _, _)) = points.last() {
+                        segment.next_id = segment.next_id.max(last.saturating_add(1));
+                    }
+                    segment.apply_insert_batch(points)?;
+                }
+                WalRecord::Delete { point_id } => segment.apply_delete(point_id)?,
+                WalRecord::DeleteMany { point_ids } => {
+                    for point_id in point_ids {
+                        segment.apply_delete(point_id)?;
+                    }
+                }
+                WalRecord::Purge => segment.apply_purge()?,
+                WalRecord::Quantize(quantizer) => segment.hnsw.set_quantizer(quantizer),
+                WalRecord::AddNamedVector { name, config } => {
+                    segment.named.insert(name, config.build()?);
+                }
+                WalRecord::EnableMultiVectors(config) => segment.multivectors = Some(MultiVectorStorage::new(config)),
+                WalRecord::SetMultiVector { point_id, vector } => segment.apply_set_multivector(point_id, vector)?,
+                WalRecord::UpdatePayload { point_ids, update } => segment.apply_update_payload(&point_ids, &update)?,
+                WalRecord::FreezeVectors { path } => segment.apply_freeze_vectors(&path)?,
+            }
+        }
+
+        segment.wal = Some(wal);
+        Ok(segment)
+    }
+
+    /// Write the whole segment (graphs, vectors, named and sparse vectors, payloads, payload index,
+    /// deletions, external IDs) to a snapshot file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DBError> {
+        let mut body = Vec::new();
+        self.hnsw.write_snapshot(&mut body);
+        self.payload_index.write_snapshot(&mut body);
+
+        let mut ids: Vec<_> = self.payloads.keys().copied().collect();
+        ids.sort_unstable();
+        codec::put_u64(&mut body, ids.len() as u64);
+        for id in ids {
+            codec::put_u64(&mut body, id);
+            codec::put_payload(&mut body, &self.payloads[&id]);
+        }
+
+        let mut deleted: Vec<_> = self.deleted.iter().copied().collect();
+        deleted.sort_unstable();
+        codec::put_u64(&mut body, deleted.len() as u64);
+        deleted.iter().for_each(|&id| codec::put_u64(&mut body, id));
+
+        codec::put_u64(&mut body, self.next_id);
+
+        let mut sparse_ids: Vec<_> = self.sparse.iter().map(|(&id, _)| id).collect();
+        sparse_ids.sort_unstable();
+        codec::put_u64(&mut body, sparse_ids.len() as u64);
+        for id in sparse_ids {
+            codec::put_u64(&mut body, id);
+            codec::put_sparse_vector(&mut body, self.sparse.get(&id).unwrap());
+        }
+
+        codec::put_u64(&mut body, self.named.len() as u64);
+        for (name, hnsw) in &self.named {
+            codec::put_str(&mut body, name);
+            hnsw.write_snapshot(&mut body);
+        }
+
+        match &self.multivectors {
+            Some(storage) => {
+                codec::put_u8(&mut body, 1);
+                storage.write_snapshot(&mut body);
+            }
+            None => codec::put_u8(&mut body, 0),
+        }
+
+        self.ids.write_snapshot(&mut body);
+
+        snapshot::write_file(path.as_ref(), &body)
+    }
+
+    /// Load a segment from a snapshot written by `save`. The graph is restored as-is, not rebuilt.
+    /// The returned segment is in-memory only; it has no write-ahead log attached.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DBError> {
+        let body = snapshot::read_file(path.as_ref())?;
+        let mut dec = Decoder::new(&body);
+
+        let hnsw = HNSWIndex::read_snapshot(&mut dec)?;
+        let payload_index = PayloadIndex::read_snapshot(&mut dec)?;
+
+        let num_payloads = dec.length_prefix(16)?;
+        let mut payloads = HashMap::with_capacity(num_payloads);
+        for _ in 0..num_payloads {
+            let id = dec.u64()?;
+            payloads.insert(id, dec.payload()?);
+        }
+
+        let num_deleted = dec.length_prefix(8)?;
+        let deleted = (0..num_deleted).map(|_| dec.u64()).collect::<Result<HashSet<_>, _>>()?;
+        let next_id = dec.u64()?;
+
+        let num_sparse = dec.length_prefix(16)?;
+        let mut sparse = SparseIndex::new();
+        for _ in 0..num_sparse {
+            let id = dec.u64()?;
+            sparse.insert(id, dec.sparse_vector()?);
+        }
+
+        let num_named = dec.length_prefix(8)?;
+        let mut named = BTreeMap::new();
+        for _ in 0..num_named {
+            let name = dec.str()?;
+            named.insert(name, HNSWIndex::read_snapshot(&mut dec)?);
+        }
+
+        let multivectors = if dec.bool()? { Some(MultiVectorStorage::read_snapshot(&mut dec)?) } else { None };
+        let ids = IdMapping::read_snapshot(&mut dec, next_id)?;
+
+        if !dec.is_empty() {
+            return Err(DBError::SerializationError(anyhow::anyhow!("trailing bytes after segment snapshot")));
+        }
+
+        Ok(Self {
+            hnsw,
+            named,
+            sparse,
+            multivectors,
+            payload_index,
+            payloads,
+            deleted,
+            next_id,
+            ids,
+            wal: None,
+            full_scan_threshold: DEFAULT_FULL_SCAN_THRESHOLD,
+        })
+    }
+
+    fn log(&mut self, record: WalRecord) -> Result<(), DBError> {
+        if let Some(wal) = self.wal.as_mut() {
+            wal.append(&record)?;
         }
+        Ok(())
     }
 
     /// Insert a new vector and optional payload. Auto-generates ID.
     pub fn insert(&mut self, vector: Vector, payload: Option<Payload>) -> Result<PointId, DBError> {
-        let point_id = self.next_id;
-        self.hnsw.insert(point_id, vector.clone())?;
+        self.insert_point(self.next_id, None, vector, Vec::new(), None, payload)
+    }
+
+    /// Insert many new points at once, with auto-generated IDs returned in input order. Their graph
+    /// links are built on every available core (see `HNSWIndex::insert_batch`), which makes this
+    /// far faster than repeated `insert` calls for bulk loads. Nothing is inserted if any vector has
+    /// the wrong length.
+    pub fn insert_batch(&mut self, points: Vec<(Vector, Option<Payload>)>) -> Result<Vec<PointId>, DBError> {
+        // Validate everything `HNSWIndex::insert_batch` checks before anything reaches the log. The
+        // IDs are fresh ones counted up from `next_id`, so they cannot repeat.
+        if let Some((vector, _)) = points.iter().find(|(v, _)| v.len() != self.hnsw.dim()) {
+            return Err(DBError::VectorLengthMismatch { expected: self.hnsw.dim(), actual: vector.len() });
+        }
+        if self.hnsw.is_mmap() {
+            return Err(DBError::ReadOnly("segment vectors are frozen".into()));
+        }
+
+        let first = self.next_id;
+        let points: Vec<_> = points.into_iter().zip(first..).map(|((vector, payload), id)| (id, vector, payload)).collect();
+        for chunk in points.chunks(INSERT_BATCH_RECORD_LEN) {
+            self.log(WalRecord::InsertBatch { points: chunk.to_vec() })?;
+        }
+        let ids: Vec<PointId> = points.iter().map(|&(id, _, _)| id).collect();
+        self.apply_insert_batch(points)?;
+
+        if let Some(&last) = ids.last() {
+            self.next_id = last + 1;
+        }
+        Ok(ids)
+    }
+
+    /// Store a batch of new points: the graph in parallel first, then the payloads of the whole
+    /// batch, so that the filter-aware edges added point by point last can see all of them.
+    fn apply_insert_batch(&mut self, points: Vec<(PointId, Vector, Option<Payload>)>) -> Result<(), DBError> {
+        let mut vectors = Vec::with_capacity(points.len());
+        let mut payloads = Vec::new();
+        for (point_id, vector, payload) in points {
+            if let Some(p) = payload {
+                payloads.push((point_id, p));
+            }
+            vectors.push((point_id, vector));
+        }
+
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.hnsw.insert_batch(vectors, threads)?;
+
+        let with_payload: Vec<PointId> = payloads.iter().map(|&(id, _)| id).collect();
+        for (point_id, p) in payloads {
+            self.payload_index.insert(point_id, &p);
+            self.payloads.insert(point_id, p);
+        }
+        for point_id in with_payload {
+            let vector = self.hnsw.get_vector(&point_id).unwrap().into_owned();
+            let payload = &self.payloads[&point_id];
+            Self::add_filter_edges(&mut self.hnsw, point_id, &vector, payload, payload.0.keys(), &self.payload_index, &self.payloads)?;
+        }
+        Ok(())
+    }
+
+    /// Insert a point under a caller-chosen ID (a number, UUID or string), or replace the point
+    /// already stored under it. A replaced point keeps nothing of its old self: vector, payload,
+    /// sparse, named and multi-vectors are all dropped, and the graph, payload index and
+    /// filter-aware edges are updated to match. Upserting a deleted ID brings it back until the
+    /// next purge.
+    ///
+    /// Returns the internal ID the point is stored under, which search results and the
+    /// `PointId`-based methods use; `external_id` and `internal_id` translate between the two.
+    pub fn upsert(
+        &mut self,
+        id: impl Into<ExtendedPointId>,
+        vector: Vector,
+        payload: Option<Payload>,
+    ) -> Result<PointId, DBError> {
+        let id = id.into();
+        let point_id = self.ids.internal_id(&id).unwrap_or(self.next_id);
+        self.insert_point(point_id, Some(id), vector, Vec::new(), None, payload)
+    }
+
+    /// The internal ID of the live point upserted under `id`.
+    pub fn internal_id(&self, id: &ExtendedPointId) -> Option<PointId> {
+        self.ids.internal_id(id).filter(|point_id| !self.deleted.contains(point_id))
+    }
+
+    /// The ID a point was upserted under, or `None` for points inserted with an auto-generated ID.
+    pub fn external_id(&self, point_id: PointId) -> Option<&ExtendedPointId> {
+        self.ids.external_id(point_id)
+    }
+
+    /// Insert a new dense vector together with a sparse vector for `search_sparse`. Auto-generates ID.
+    pub fn insert_with_sparse(
+        &mut self,
+        vector: Vector,
+        sparse: SparseVector,
+        payload: Option<Payload>,
+    ) -> Result<PointId, DBError> {
+        self.insert_point(self.next_id, None, vector, Vec::new(), Some(sparse), payload)
+    }
+
+    /// Insert a new point with its default vector plus vectors for some of the segment's named
+    /// vector indexes (see `add_named_vector`). Auto-generates ID.
+    pub fn insert_named(
+        &mut self,
+        vector: Vector,
+        named: HashMap<String, Vector>,
+        payload: Option<Payload>,
+    ) -> Result<PointId, DBError> {
+        let mut named: Vec<_> = named.into_iter().collect();
+        named.sort_by(|a, b| a.0.cmp(&b.0));
+        self.insert_point(self.next_id, None, vector, named, None, payload)
+    }
+
+    fn insert_point(
+        &mut self,
+        point_id: PointId,
+        external: Option<ExtendedPointId>,
+        vector: Vector,
+        named: Vec<(String, Vector)>,
+        sparse: Option<SparseVector>,
+        payload: Option<Payload>,
+    ) -> Result<PointId, DBError> {
+        // Validate up front so that nothing un-replayable ever reaches the log.
+        if vector.len() != self.hnsw.dim() {
+            return Err(DBError::VectorLengthMismatch {
+                expected: self.hnsw.dim(),
+                actual: vector.len(),
+            });
+        }
+        if self.hnsw.is_mmap() {
+            return Err(DBError::ReadOnly("segment vectors are frozen".into()));
+        }
+        for (name, v) in &named {
+            let index = self.named_index(name)?;
+            if v.len() != index.dim() {
+                return Err(DBError::VectorLengthMismatch { expected: index.dim(), actual: v.len() });
+            }
+            if index.is_mmap() {
+                return Err(DBError::ReadOnly(format!("named vector '{}' is frozen", name)));
+            }
+        }
+
+        self.log(WalRecord::Insert {
+            point_id,
+            vector: vector.clone(),
+            payload: payload.clone(),
+            sparse: sparse.clone(),
+            named: named.clone(),
+            external: external.clone(),
+        })?;
+        self.apply_insert(point_id, external, vector, payload, sparse, named)?;
 
+        self.next_id = self.next_id.max(point_id.saturating_add(1));
+        Ok(point_id)
+    }
+
+    fn apply_insert(
+        &mut self,
+        point_id: PointId,
+        external: Option<ExtendedPointId>,
+        vector: Vector,
+        payload: Option<Payload>,
+        sparse: Option<SparseVector>,
+        named: Vec<(String, Vector)>,
+    ) -> Result<(), DBError> {
+        if self.hnsw.contains(&point_id) {
+            self.clear_point(point_id, &named);
+        }
+
+        if let Some(sparse) = sparse {
+            self.sparse.insert(point_id, sparse);
+        }
         if let Some(p) = payload {
             self.payload_index.insert(point_id, &p);
-            self.payloads.insert(point_id, p.clone());
-
-            let filter_keys: Vec<String> = p.0
-                .iter()
-                .filter(|(_, v)| matches!(v, PayloadValue::Int(_) | PayloadValue::Float(_) | PayloadValue::Str(_) | PayloadValue::Bool(_)))
-                .map(|(k, _)| k.clone())
-                .collect();
-
-            if !filter_keys.is_empty() {
-                self.hnsw.build_filter_aware_edges(
-                    point_id,
-                    &vector,
-                    &p,
-                    &self.payload_index,
-                    &self.payloads,
-                    &filter_keys,
-                )?;
+            self.payloads.insert(point_id, p);
+        }
+
+        let payload = self.payloads.get(&point_id);
+        Self::link(&mut self.hnsw, point_id, vector, payload, &self.payload_index, &self.payloads)?;
+        for (name, v) in named {
+            let index = self
+                .named
+                .get_mut(&name)
+                .ok_or_else(|| DBError::InvalidConfig(format!("no named vector '{}'", name)))?;
+            Self::link(index, point_id, v, payload, &self.payload_index, &self.payloads)?;
+        }
+        if let Some(id) = external {
+            self.ids.set(id, point_id);
+        }
+
+        Ok(())
+    }
+
+    /// Drop everything stored for a point that is about to be replaced, except the vectors its
+    /// replacement overwrites anyway: the default one and those named in `named`.
+    fn clear_point(&mut self, point_id: PointId, named: &[(String, Vector)]) {
+        if let Some(p) = self.payloads.remove(&point_id) {
+            // Deleted points were already taken out of the payload index.
+            if !self.deleted.contains(&point_id) {
+                self.payload_index.remove(point_id, &p);
+            }
+        }
+        self.deleted.remove(&point_id);
+        self.sparse.remove(point_id);
+        if let Some(storage) = self.multivectors.as_mut() {
+            storage.remove(point_id);
+        }
+        for (name, index) in self.named.iter_mut() {
+            if index.contains(&point_id) && !named.iter().any(|(n, _)| n == name) {
+                index.mark_deleted(point_id);
             }
+        }
+    }
 
+    /// Insert `vector` into `index` and, if the point's payload has filterable fields, give it
+    /// filter-aware edges there as well.
+    fn link(
+        index: &mut HNSWIndex,
+        point_id: PointId,
+        vector: Vector,
+        payload: Option<&Payload>,
+        payload_index: &PayloadIndex,
+        payloads: &HashMap<PointId, Payload>,
+    ) -> Result<(), DBError> {
+        index.insert(point_id, vector.clone())?;
+
+        if let Some(p) = payload {
+            Self::add_filter_edges(index, point_id, &vector, p, p.0.keys(), payload_index, payloads)?;
         }
 
-        self.next_id += 1;
-        Ok(point_id)
+        Ok(())
+    }
+
+    /// Build filter-aware edges in `index` for those of `keys` that hold a filterable scalar in
+    /// `payload`.
+    fn add_filter_edges<'a>(
+        index: &mut HNSWIndex,
+        point_id: PointId,
+        vector: &[f32],
+        payload: &Payload,
+        keys: impl Iterator<Item = &'a String>,
+        payload_index: &PayloadIndex,
+        payloads: &HashMap<PointId, Payload>,
+    ) -> Result<(), DBError> {
+        let filter_keys: Vec<String> = keys
+            .filter(|k| {
+                matches!(
+                    payload.get(k),
+                    Some(PayloadValue::Int(_) | PayloadValue::Float(_) | PayloadValue::Str(_) | PayloadValue::Bool(_))
+                )
+            })
+            .cloned()
+            .collect();
+
+        if !filter_keys.is_empty() {
+            index.build_filter_aware_edges(point_id, vector, payload, payload_index, payloads, &filter_keys)?;
+        }
+        Ok(())
+    }
+
+    /// Add an empty named vector index. Points inserted from now on may carry a vector for it via
+    /// `insert_named`; searches pick it with `search_named`.
+    pub fn add_named_vector(&mut self, name: &str, hnsw: HNSWIndex) -> Result<(), DBError> {
+        if self.named.contains_key(name) {
+            return Err(DBError::InvalidConfig(format!("named vector '{}' already exists", name)));
+        }
+        if !hnsw.is_empty() {
+            return Err(DBError::InvalidConfig(format!("index for named vector '{}' must be empty", name)));
+        }
+        self.log(WalRecord::AddNamedVector { name: name.to_string(), config: IndexConfig::of(&hnsw) })?;
+        self.named.insert(name.to_string(), hnsw);
+        Ok(())
+    }
+
+    fn named_index(&self, name: &str) -> Result<&HNSWIndex, DBError> {
+        self.named
+            .get(name)
+            .ok_or_else(|| DBError::InvalidConfig(format!("no named vector '{}'", name)))
+    }
+
+    /// The index behind a named vector.
+    pub fn named_vector(&self, name: &str) -> Option<&HNSWIndex> {
+        self.named.get(name)
+    }
+
+    /// Names of the segment's named vectors, in order.
+    pub fn named_vector_names(&self) -> impl Iterator<Item = &str> {
+        self.named.keys().map(String::as_str)
+    }
+
+    /// Get a point's vector for `name`, if it has one and is not deleted.
+    pub fn get_named_vector(&self, point_id: PointId, name: &str) -> Option<Cow<'_, [f32]>> {
+        if self.deleted.contains(&point_id) {
+            return None;
+        }
+        let index = self.named.get(name)?;
+        if index.is_deleted(&point_id) {
+            return None;
+        }
+        index.get_vector(&point_id)
     }
 
     /// Get the vector for a given point ID, if it exists and is not deleted.
-    pub fn get_vector(&self, point_id: PointId) -> Option<&Vector> {
+    pub fn get_vector(&self, point_id: PointId) -> Option<Cow<'_, [f32]>> {
         if self.deleted.contains(&point_id) {
             return None;
         }
         self.hnsw.get_vector(&point_id)
     }
 
+    /// Get the sparse vector for a given point ID, if it has one and is not deleted.
+    pub fn get_sparse_vector(&self, point_id: PointId) -> Option<&SparseVector> {
+        self.sparse.get(&point_id)
+    }
+
     pub fn delete(&mut self, point_id: PointId) -> Result<(), DBError> {
         // If the point is already marked as deleted OR is no longer in the index,
         // treat it as already deleted.
         if self.deleted.contains(&point_id) || !self.hnsw.contains(&point_id) {
             return Ok(());
         }
-    
+
+        self.log(WalRecord::Delete { point_id })?;
+        self.apply_delete(point_id)
+    }
+
+    /// Delete every live point whose payload satisfies `filter`, logged as a single record. Points
+    /// without a payload never match. Returns how many points were deleted.
+    pub fn delete_where(&mut self, filter: &Filter) -> Result<usize, DBError> {
+        let point_ids = self.points_matching(filter)?;
+        if point_ids.is_empty() {
+            return Ok(0);
+        }
+        self.log(WalRecord::DeleteMany { point_ids: point_ids.clone() })?;
+        for &point_id in &point_ids {
+            self.apply_delete(point_id)?;
+        }
+        Ok(point_ids.len())
+    }
+
+    fn apply_delete(&mut self, point_id: PointId) -> Result<(), DBError> {
+        if self.deleted.contains(&point_id) || !self.hnsw.contains(&point_id) {
+            return Ok(());
+        }
+
         if let Some(p) = self.payloads.get(&point_id) {
             self.payload_index.remove(point_id, p);
         }
     
         self.deleted.insert(point_id);
         self.hnsw.mark_deleted(point_id);
+        for index in self.named.values_mut().filter(|index| index.contains(&point_id)) {
+            index.mark_deleted(point_id);
+        }
+        self.sparse.remove(point_id);
+        if let Some(storage) = self.multivectors.as_mut() {
+            storage.remove(point_id);
+        }
     
         let deleted_count = self.deleted.len();
         let total_count = self.hnsw.len();
@@ -88,10 +595,12 @@ impl Segment {
         const MIN_DELETIONS_BEFORE_PURGE: usize = 100;
         const MAX_DELETION_RATIO: f32 = 0.25;
 
+        // Frozen vectors cannot be rebuilt, so their deletions stay lazy.
         if deleted_count >= MIN_DELETIONS_BEFORE_PURGE &&
-        (deleted_count as f32 / total_count as f32) >= MAX_DELETION_RATIO {
+        (deleted_count as f32 / total_count as f32) >= MAX_DELETION_RATIO && !self.is_frozen() {
             println!("[DELETE] Triggering purge: {}/{} ({:.2}%) deleted", deleted_count, total_count, 100.0 * deleted_count as f32 / total_count as f32);
-            self.purge()?;
+            // Not logged separately: replaying the delete re-triggers the same purge.
+            self.apply_purge()?;
         }
 
     
@@ -101,13 +610,26 @@ impl Segment {
 
 
     pub fn search(&self, query: &Vector, top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
+        self.search_with_params(query, top_k, &SearchParams::default())
+    }
+
+    pub fn search_with_params(
+        &self,
+        query: &Vector,
+        top_k: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
         let total_non_deleted = self.hnsw.len() - self.deleted.len();
         if total_non_deleted == 0 {
             return Err(DBError::SearchError("No active points available to search.".into()));
         }
 
+        if params.exact || total_non_deleted <= self.full_scan_threshold {
+            return self.exact_search_with_params(&self.hnsw, query, top_k, None, None, params);
+        }
+
         // HNSWIndex now internally skips deleted points.
-        let candidates = self.hnsw.search(query, top_k * 2)?;
+        let candidates = self.hnsw.search_with_params(query, top_k * 2, params)?;
         // (The following filter is kept as extra safety.)
         let filtered = candidates
             .into_iter()
@@ -124,17 +646,59 @@ impl Segment {
         top_k: usize,
         filter: Option<&Filter>,
     ) -> Result<Vec<ScoredPoint>, DBError> {
-        let total_non_deleted = self.hnsw.len() - self.deleted.len();
+        self.search_with_filter_and_params(query, top_k, filter, &SearchParams::default())
+    }
+
+    pub fn search_with_filter_and_params(
+        &self,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.search_index(&self.hnsw, query, top_k, filter, params)
+    }
+
+    /// Search the named vector `name` instead of the default one, with the same filtering and
+    /// parameters as `search_with_filter_and_params`.
+    pub fn search_named(
+        &self,
+        name: &str,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.search_index(self.named_index(name)?, query, top_k, filter, params)
+    }
+
+    fn search_index(
+        &self,
+        index: &HNSWIndex,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let total_non_deleted = index.live_len();
         if total_non_deleted == 0 {
             return Err(DBError::SearchError("No active points available to search.".into()));
         }
+
+        // Small segments and very selective filters are cheaper, and exact, to answer by scanning.
+        let candidates = filter.and_then(|f| self.payload_index.candidates(f));
+        let scan_size = candidates.as_ref().map_or(total_non_deleted, |c| c.len().min(total_non_deleted));
+        if params.exact || scan_size <= self.full_scan_threshold {
+            return self.exact_search_with_params(index, query, top_k, filter, candidates, params);
+        }
     
-        let results = self.hnsw.in_place_filtered_search(
+        let results = index.in_place_filtered_search_with_params(
             query,
             top_k * 2,
             &self.payloads,
             &self.payload_index,
             filter,
+            params,
         )?;
     
         let filtered: Vec<_> = results
@@ -148,11 +712,281 @@ impl Segment {
     
     
 
+    /// Exhaustive search over every live point matching `filter`. Returns the ground-truth top-k,
+    /// scored exactly like the graph searches.
+    pub fn exact_search(
+        &self,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        if self.hnsw.len() == self.deleted.len() {
+            return Err(DBError::SearchError("No active points available to search.".into()));
+        }
+        let candidates = filter.and_then(|f| self.payload_index.candidates(f));
+        self.exact_search_with_params(&self.hnsw, query, top_k, filter, candidates, &SearchParams::default())
+    }
+
+    fn exact_search_with_params(
+        &self,
+        index: &HNSWIndex,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        candidates: Option<HashSet<PointId>>,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let results = match candidates {
+            Some(ids) => index.exact_search_among(query, top_k, ids, &self.payloads, filter)?,
+            None => index.exact_search(query, top_k, &self.payloads, filter)?,
+        };
+        Ok(index.apply_score_threshold(results, params))
+    }
+
+    /// Exact top-k by dot product against the points' sparse vectors, restricted to points matching
+    /// `filter`. Points without a sparse vector, or sharing no dimension with `query`, are never
+    /// returned, so fewer than `top_k` results is normal.
+    pub fn search_sparse(
+        &self,
+        query: &SparseVector,
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let candidates = filter.and_then(|f| self.payload_index.candidates(f));
+        Ok(self.sparse.search(query, top_k, |id| {
+            !self.deleted.contains(&id)
+                && candidates.as_ref().is_none_or(|c| c.contains(&id))
+                && filter.is_none_or(|f| {
+                    self.payloads.get(&id).is_some_and(|p| evaluate_filter(f, p).unwrap_or(false))
+                })
+        }))
+    }
+
+    /// Hybrid search: dense search for `dense` and sparse search for `sparse` over the points
+    /// matching `filter`, fused into one ranking. Each result lists its rank and raw score in the
+    /// sources it was found by in `ScoredPoint::sources`.
+    pub fn hybrid_search(
+        &self,
+        dense: &Vector,
+        sparse: &SparseVector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        fusion: &Fusion,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.hybrid_search_with_params(dense, sparse, top_k, filter, fusion, &SearchParams::default())
+    }
+
+    /// `hybrid_search` with `params` applied to the dense side.
+    pub fn hybrid_search_with_params(
+        &self,
+        dense: &Vector,
+        sparse: &SparseVector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        fusion: &Fusion,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let prefetch = top_k * HYBRID_PREFETCH_FACTOR;
+        let dense_hits = self.search_with_filter_and_params(dense, prefetch, filter, params)?;
+        let sparse_hits = self.search_sparse(sparse, prefetch, filter)?;
+        Ok(fusion.fuse(&[(ScoreSource::Dense, dense_hits), (ScoreSource::Sparse, sparse_hits)], top_k))
+    }
+
+    /// Start storing multi-vectors (one variable-length matrix of token vectors per point) under
+    /// `config`, for `rerank` and `search_with_rerank`.
+    pub fn enable_multivectors(&mut self, config: MultiVectorConfig) -> Result<(), DBError> {
+        if self.multivectors.is_some() {
+            return Err(DBError::InvalidConfig("multi-vectors are already enabled".into()));
+        }
+        if config.dim == 0 {
+            return Err(DBError::InvalidConfig("multi-vector dimension must be positive".into()));
+        }
+        self.log(WalRecord::EnableMultiVectors(config))?;
+        self.multivectors = Some(MultiVectorStorage::new(config));
+        Ok(())
+    }
+
+    /// Attach a multi-vector to an existing point, replacing any it had.
+    pub fn set_multivector(&mut self, point_id: PointId, vector: MultiVector) -> Result<(), DBError> {
+        let Some(storage) = self.multivectors.as_ref() else {
+            return Err(DBError::InvalidConfig("multi-vectors are not enabled".into()));
+        };
+        if vector.dim() != storage.config().dim {
+            return Err(DBError::VectorLengthMismatch { expected: storage.config().dim, actual: vector.dim() });
+        }
+        if self.deleted.contains(&point_id) || !self.hnsw.contains(&point_id) {
+            return Err(DBError::NotFound(point_id));
+        }
+        self.log(WalRecord::SetMultiVector { point_id, vector: vector.clone() })?;
+        self.apply_set_multivector(point_id, vector)
+    }
+
+    fn apply_set_multivector(&mut self, point_id: PointId, vector: MultiVector) -> Result<(), DBError> {
+        match self.multivectors.as_mut() {
+            Some(storage) => storage.insert(point_id, vector),
+            None => Err(DBError::InvalidConfig("multi-vectors are not enabled".into())),
+        }
+    }
+
+    /// A point's multi-vector, if it has one. Cosine tokens come back normalized.
+    pub fn get_multivector(&self, point_id: PointId) -> Option<&MultiVector> {
+        self.multivectors.as_ref()?.get(&point_id)
+    }
+
+    pub fn multivectors(&self) -> Option<&MultiVectorStorage> {
+        self.multivectors.as_ref()
+    }
+
+    /// Re-rank `candidates` from any search by comparing `query` with their multi-vectors (MaxSim
+    /// for ColBERT-style models). See `MultiVectorStorage::rescore`.
+    pub fn rerank(&self, query: &MultiVector, candidates: Vec<ScoredPoint>, top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
+        match &self.multivectors {
+            Some(storage) => storage.rescore(query, candidates, top_k),
+            None => Err(DBError::InvalidConfig("multi-vectors are not enabled".into())),
+        }
+    }
+
+    /// Two-stage search: the graph finds dense candidates for `query` among points matching
+    /// `filter`, then they are re-ranked against `tokens` with the multi-vector comparator.
+    pub fn search_with_rerank(
+        &self,
+        query: &Vector,
+        tokens: &MultiVector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let candidates = self.search_with_filter_and_params(query, top_k * RERANK_PREFETCH_FACTOR, filter, params)?;
+        self.rerank(tokens, candidates, top_k)
+    }
+
+    /// Searches that can touch at most `threshold` points are answered by `exact_search` instead of
+    /// the graph. Set to 0 to always use the graph.
+    pub fn set_full_scan_threshold(&mut self, threshold: usize) {
+        self.full_scan_threshold = threshold;
+    }
+
+    pub fn full_scan_threshold(&self) -> usize {
+        self.full_scan_threshold
+    }
+
     /// Internal unfiltered search (used for diagnostics or filtered versions).
     pub fn search_unfiltered(&self, query: &Vector, top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
         self.hnsw.search(query, top_k)
     }
 
+    /// Add the keys of `payload` to a point's payload, replacing the values of keys it already has.
+    pub fn set_payload(&mut self, point_id: PointId, payload: Payload) -> Result<(), DBError> {
+        self.update_payload(vec![point_id], PayloadUpdate::Set(payload))
+    }
+
+    /// Replace a point's whole payload.
+    pub fn overwrite_payload(&mut self, point_id: PointId, payload: Payload) -> Result<(), DBError> {
+        self.update_payload(vec![point_id], PayloadUpdate::Overwrite(payload))
+    }
+
+    /// Remove `keys` from a point's payload. Keys it doesn't have are ignored.
+    pub fn delete_payload_keys(&mut self, point_id: PointId, keys: &[&str]) -> Result<(), DBError> {
+        let keys = keys.iter().map(|k| k.to_string()).collect();
+        self.update_payload(vec![point_id], PayloadUpdate::DeleteKeys(keys))
+    }
+
+    /// Remove a point's whole payload.
+    pub fn clear_payload(&mut self, point_id: PointId) -> Result<(), DBError> {
+        self.update_payload(vec![point_id], PayloadUpdate::Clear)
+    }
+
+    /// Apply `update` to every live point whose payload satisfies `filter`, logged as a single
+    /// record. Points without a payload never match. Returns how many points were updated.
+    pub fn update_payload_where(&mut self, filter: &Filter, update: PayloadUpdate) -> Result<usize, DBError> {
+        let point_ids = self.points_matching(filter)?;
+        if point_ids.is_empty() {
+            return Ok(0);
+        }
+        let count = point_ids.len();
+        self.update_payload(point_ids, update)?;
+        Ok(count)
+    }
+
+    /// Live points whose payload satisfies `filter`, in ID order. The payload index narrows the
+    /// scan when the filter allows it; otherwise every stored payload is checked.
+    fn points_matching(&self, filter: &Filter) -> Result<Vec<PointId>, DBError> {
+        let candidates: Vec<PointId> = match self.payload_index.candidates(filter) {
+            Some(ids) => ids.into_iter().collect(),
+            None => self.payloads.keys().copied().collect(),
+        };
+        let mut matching = Vec::new();
+        for id in candidates {
+            if self.deleted.contains(&id) {
+                continue;
+            }
+            let Some(p) = self.payloads.get(&id) else { continue };
+            if evaluate_filter(filter, p)? {
+                matching.push(id);
+            }
+        }
+        matching.sort_unstable();
+        Ok(matching)
+    }
+
+    fn update_payload(&mut self, point_ids: Vec<PointId>, update: PayloadUpdate) -> Result<(), DBError> {
+        if let Some(&id) = point_ids.iter().find(|id| !self.hnsw.contains(id) || self.deleted.contains(id)) {
+            return Err(DBError::NotFound(id));
+        }
+        self.log(WalRecord::UpdatePayload { point_ids: point_ids.clone(), update: update.clone() })?;
+        self.apply_update_payload(&point_ids, &update)
+    }
+
+    /// Apply `update` to the payload of each live point in `point_ids`, keeping the payload index
+    /// in step. Vectors are left alone; only the filter-aware edges of keys whose value changed or
+    /// went away are refreshed, in every index that holds the point: edges to neighbors that share
+    /// an old value the point no longer has are dropped, and edges for the new values are built.
+    fn apply_update_payload(&mut self, point_ids: &[PointId], update: &PayloadUpdate) -> Result<(), DBError> {
+        for &point_id in point_ids {
+            if !self.hnsw.contains(&point_id) || self.deleted.contains(&point_id) {
+                continue;
+            }
+            let old = self.payloads.remove(&point_id).unwrap_or_default();
+            let mut new = old.clone();
+            update.apply(&mut new);
+
+            self.payload_index.remove(point_id, &old);
+            self.payload_index.insert(point_id, &new);
+            let affected: BTreeSet<String> =
+                old.0.keys().chain(new.0.keys()).filter(|k| old.get(k) != new.get(k)).cloned().collect();
+            if !new.0.is_empty() {
+                self.payloads.insert(point_id, new);
+            }
+            if affected.is_empty() {
+                continue;
+            }
+
+            let empty = Payload::default();
+            let payload = self.payloads.get(&point_id).unwrap_or(&empty);
+            for index in std::iter::once(&mut self.hnsw).chain(self.named.values_mut()) {
+                if index.is_deleted(&point_id) {
+                    continue;
+                }
+                let stale: HashSet<PointId> = index
+                    .layer_neighbors(0, point_id)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|n| {
+                        let Some(theirs) = self.payloads.get(n) else { return false };
+                        affected.iter().any(|k| old.get(k).is_some_and(|v| theirs.get(k) == Some(v)))
+                    })
+                    .collect();
+                index.drop_edges(point_id, &stale)?;
+
+                let Some(vector) = index.get_vector(&point_id).map(|v| v.into_owned()) else { continue };
+                let changed = affected.iter().filter(|k| payload.get(k).is_some());
+                Self::add_filter_edges(index, point_id, &vector, payload, changed, &self.payload_index, &self.payloads)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Get payload metadata for a point.
     pub fn get_payload(&self, point_id: PointId) -> Option<&Payload> {
         self.payloads.get(&point_id)
@@ -163,59 +997,63 @@ impl Segment {
         self.deleted.contains(&point_id)
     }
 
+    /// Drop deleted points for good by rebuilding the indexes from the live ones. Fails with
+    /// `DBError::ReadOnly` once any vectors are frozen, since the rebuild would move them back
+    /// into memory.
     pub fn purge(&mut self) -> Result<(), DBError> {
-        let mut new_hnsw = HNSWIndex::new(
-            self.hnsw.metric(),
-            self.hnsw.m(),
-            self.hnsw.ef(),
-            self.hnsw.max_level_cap(),
-            self.hnsw.dim(),
-        );
-    
+        if self.is_frozen() {
+            return Err(DBError::ReadOnly("segment vectors are frozen".into()));
+        }
+        self.log(WalRecord::Purge)?;
+        self.apply_purge()
+    }
+
+    fn apply_purge(&mut self) -> Result<(), DBError> {
         let mut new_payload_index = PayloadIndex::new();
         let mut new_payloads = HashMap::new();
-    
-        for (&id, vector) in self.hnsw.iter_vectors() {
-            if self.deleted.contains(&id) {
-                continue;
-            }
-    
-            // Reinsert into HNSW
-            new_hnsw.insert(id, vector.clone())?;
-    
-            if let Some(p) = self.payloads.get(&id) {
-                // Reinsert into payload structures
+        for (&id, p) in &self.payloads {
+            if !self.deleted.contains(&id) {
                 new_payload_index.insert(id, p);
                 new_payloads.insert(id, p.clone());
-    
-                // Rebuild filter-aware edges
-                let filter_keys: Vec<String> = p.0
-                    .iter()
-                    .filter(|(_, v)| matches!(v, PayloadValue::Int(_) | PayloadValue::Float(_) | PayloadValue::Str(_) | PayloadValue::Bool(_)))
-                    .map(|(k, _)| k.clone())
-                    .collect();
-    
-                new_hnsw.build_filter_aware_edges(
-                    id,
-                    vector,
-                    p,
-                    &new_payload_index,
-                    &new_payloads,
-                    &filter_keys,
-                )?;
             }
         }
-    
+
+        let new_hnsw = self.rebuild_index(&self.hnsw, &new_payload_index, &new_payloads)?;
+        let mut new_named = BTreeMap::new();
+        for (name, index) in &self.named {
+            new_named.insert(name.clone(), self.rebuild_index(index, &new_payload_index, &new_payloads)?);
+        }
+
         // Swap in the rebuilt structures
         self.hnsw = new_hnsw;
+        self.named = new_named;
         self.payload_index = new_payload_index;
         self.payloads = new_payloads;
-    
+
+        // Purged points are gone for good; upserting their IDs again creates new points.
+        for &id in &self.deleted {
+            self.ids.remove_internal(id);
+        }
         self.deleted.clear();
-    
+
         Ok(())
     }
-     
+
+    /// A fresh graph with the live points of `index` reinserted, filter-aware edges included.
+    fn rebuild_index(
+        &self,
+        index: &HNSWIndex,
+        payload_index: &PayloadIndex,
+        payloads: &HashMap<PointId, Payload>,
+    ) -> Result<HNSWIndex, DBError> {
+        let mut new_index = index.empty_like();
+        for (&id, vector) in index.iter_vectors() {
+            if !self.deleted.contains(&id) && !index.is_deleted(&id) {
+                Self::link(&mut new_index, id, vector.into_owned(), payloads.get(&id), payload_index, payloads)?;
+            }
+        }
+        Ok(new_index)
+    }
 
     /// Vector search with logical payload filtering
     pub fn post_filter(
@@ -223,19 +1061,29 @@ impl Segment {
         query: &Vector,
         top_k: usize,
         filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.post_filter_with_params(query, top_k, filter, &SearchParams::default())
+    }
+
+    pub fn post_filter_with_params(
+        &self,
+        query: &Vector,
+        top_k: usize,
+        filter: Option<&Filter>,
+        params: &SearchParams,
     ) -> Result<Vec<ScoredPoint>, DBError> {
         let total_non_deleted = self.hnsw.len() - self.deleted.len();
         if total_non_deleted == 0 {
             return Err(DBError::SearchError("No active points available to search.".into()));
         }
 
-        let candidates = self.hnsw.search(query, top_k * 4)?;
+        let candidates = self.hnsw.search_with_params(query, top_k * 4, params)?;
 
         let filtered = candidates
             .into_iter()
             .filter(|sp| {
                 !self.deleted.contains(&sp.id)
-                    && filter.map_or(true, |f| {
+                    && filter.is_none_or(|f| {
                         self.payloads
                             .get(&sp.id)
                             .map(|p| evaluate_filter(f, p).unwrap_or(false))
@@ -248,6 +1096,45 @@ impl Segment {
         Ok(filtered)
     }
 
+    /// Train a quantizer on the live vectors and traverse the graph with their codes from then on.
+    /// The originals stay on the heap until `freeze_vectors`; see `HNSWIndex::quantize`.
+    pub fn quantize(&mut self, config: &QuantizationConfig) -> Result<(), DBError> {
+        // The trained quantizer is logged rather than the config: training is randomized, and
+        // replay must encode with the very same codebooks.
+        let quantizer = self.hnsw.train_quantizer(config)?;
+        self.log(WalRecord::Quantize(quantizer.clone()))?;
+        self.hnsw.set_quantizer(quantizer);
+        Ok(())
+    }
+
+    /// Move the segment's vectors into read-only memory-mapped files: the default vectors to `path`,
+    /// and each named vector's to `path` with `.<name>` appended. Searches keep working; inserts are
+    /// rejected from then on. The freeze is logged, so reopening the segment from its WAL rewrites
+    /// the same files and serves from them again.
+    pub fn freeze_vectors(&mut self, path: impl AsRef<Path>) -> Result<(), DBError> {
+        if self.is_frozen() {
+            return Err(DBError::ReadOnly("segment vectors are frozen".into()));
+        }
+        let path = path.as_ref().to_path_buf();
+        self.log(WalRecord::FreezeVectors { path: path.clone() })?;
+        self.apply_freeze_vectors(&path)
+    }
+
+    fn apply_freeze_vectors(&mut self, path: &Path) -> Result<(), DBError> {
+        self.hnsw.freeze_vectors(path)?;
+        for (name, index) in &mut self.named {
+            let mut named_path = path.as_os_str().to_owned();
+            named_path.push(format!(".{}", name));
+            index.freeze_vectors(named_path)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the default or any named vectors are served from a memory-mapped file.
+    fn is_frozen(&self) -> bool {
+        self.hnsw.is_mmap() || self.named.values().any(HNSWIndex::is_mmap)
+    }
+
     /// Immutable reference to underlying HNSW index
     pub fn hnsw(&self) -> &HNSWIndex {
         &self.hnsw
@@ -261,4 +1148,8 @@ impl Segment {
     pub fn payload_index(&self) -> &PayloadIndex {
         &self.payload_index
     }
+
+    pub fn sparse_index(&self) -> &SparseIndex {
+        &self.sparse
+    }
 }
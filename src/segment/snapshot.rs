@@ -0,0 +1,72 @@
+//! Versioned, checksummed snapshot files.
+//!
+//! File layout:
+//!   magic (8) | version u32 | body_len u64 | crc32(body) u32 | body
+//!
+//! The body itself is produced by `Segment::save`.
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"VDBSNAP\0";
+pub const SNAPSHOT_VERSION: u32 = 8;
+const HEADER_LEN: usize = 8 + 4 + 8 + 4;
+
+/// Write `body` to `path` behind a snapshot header. The file is written to a
+/// sibling temp file first and renamed into place, so a crash never leaves a half-written snapshot.
+pub fn write_file(path: &Path, body: &[u8]) -> Result<(), DBError> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(SNAPSHOT_MAGIC);
+    codec::put_u32(&mut header, SNAPSHOT_VERSION);
+    codec::put_u64(&mut header, body.len() as u64);
+    codec::put_u32(&mut header, crc32fast::hash(body));
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
+    let mut file = File::create(tmp_path)?;
+    file.write_all(&header)?;
+    file.write_all(body)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Read a snapshot file and return its verified body.
+pub fn read_file(path: &Path) -> Result<Vec<u8>, DBError> {
+    let mut data = fs::read(path)?;
+
+    let mut dec = Decoder::new(&data);
+    let magic = dec.bytes(SNAPSHOT_MAGIC.len())?;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(DBError::SerializationError(anyhow!("not a snapshot file (bad magic)")));
+    }
+    let version = dec.u32()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(DBError::SerializationError(anyhow!(
+            "unsupported snapshot version {} (expected {})",
+            version,
+            SNAPSHOT_VERSION
+        )));
+    }
+    let body_len = dec.u64()? as usize;
+    let crc = dec.u32()?;
+    let body = dec.bytes(body_len)?;
+    if !dec.is_empty() {
+        return Err(DBError::SerializationError(anyhow!("trailing bytes after snapshot body")));
+    }
+    if crc32fast::hash(body) != crc {
+        return Err(DBError::SerializationError(anyhow!("snapshot checksum mismatch")));
+    }
+
+    data.drain(..HEADER_LEN);
+    Ok(data)
+}
@@ -0,0 +1,370 @@
+//! Append-only, checksummed write-ahead log for segment mutations.
+//!
+//! File layout:
+//!   header: magic (8) | version u32 | index config | crc32 u32
+//!   frames: body_len u32 | crc32(body) u32 | body
+//!
+//! A frame cut short by a crash is dropped on open; a frame whose checksum
+//! doesn't match is reported as `DBError::WALCorrupt`.
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::payload::{Payload, PayloadUpdate};
+use crate::utils::types::{DistanceMetric, ExtendedPointId, PointId, Vector, VectorElementType};
+use crate::vector::hnsw::HNSWIndex;
+use crate::vector::quantization::Quantizer;
+use crate::vector::multivector::{MultiVector, MultiVectorConfig};
+use crate::vector::sparse::SparseVector;
+
+const WAL_MAGIC: &[u8; 8] = b"VDBWAL\0\0";
+const WAL_VERSION: u32 = 1;
+const FRAME_HEADER_LEN: usize = 8;
+
+/// HNSW construction parameters, stored in the WAL header so a segment can be reopened without them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexConfig {
+    pub metric: DistanceMetric,
+    pub m: usize,
+    pub ef: usize,
+    pub max_level_cap: usize,
+    pub dim: usize,
+    pub element_type: VectorElementType,
+}
+
+impl IndexConfig {
+    /// The construction parameters of `hnsw`.
+    pub fn of(hnsw: &HNSWIndex) -> Self {
+        Self {
+            metric: hnsw.metric(),
+            m: hnsw.m(),
+            ef: hnsw.ef(),
+            max_level_cap: hnsw.max_level_cap(),
+            dim: hnsw.dim(),
+            element_type: hnsw.element_type(),
+        }
+    }
+
+    /// An empty index built with these parameters.
+    pub fn build(&self) -> Result<HNSWIndex, DBError> {
+        let mut hnsw = HNSWIndex::new(self.metric, self.m, self.ef, self.max_level_cap, self.dim);
+        hnsw.set_element_type(self.element_type)?;
+        Ok(hnsw)
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_metric(buf, self.metric);
+        codec::put_u64(buf, self.m as u64);
+        codec::put_u64(buf, self.ef as u64);
+        codec::put_u64(buf, self.max_level_cap as u64);
+        codec::put_u64(buf, self.dim as u64);
+        codec::put_element_type(buf, self.element_type);
+    }
+
+    fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        let metric = dec.metric()?;
+        let m = dec.u64()? as usize;
+        let ef = dec.u64()? as usize;
+        let max_level_cap = dec.u64()? as usize;
+        let dim = dec.u64()? as usize;
+        let element_type = dec.element_type()?;
+        Ok(Self { metric, m, ef, max_level_cap, dim, element_type })
+    }
+}
+
+/// A single logged mutation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalRecord {
+    Insert {
+        point_id: PointId,
+        vector: Vector,
+        payload: Option<Payload>,
+        sparse: Option<SparseVector>,
+        /// Vectors for the segment's named vector indexes, by name.
+        named: Vec<(String, Vector)>,
+        /// The caller's ID for the point, if it was given one.
+        external: Option<ExtendedPointId>,
+    },
+    Delete {
+        point_id: PointId,
+    },
+    Purge,
+    /// Encode every vector present at this point of the log with a trained quantizer.
+    Quantize(Quantizer),
+    /// Add an empty named vector index.
+    AddNamedVector {
+        name: String,
+        config: IndexConfig,
+    },
+    /// Start storing multi-vectors under this config.
+    EnableMultiVectors(MultiVectorConfig),
+    /// Attach a multi-vector to an existing point, replacing any it had.
+    SetMultiVector {
+        point_id: PointId,
+        vector: MultiVector,
+    },
+    /// Delete several points at once.
+    DeleteMany {
+        point_ids: Vec<PointId>,
+    },
+    /// Insert several new points, each with a default vector and an optional payload.
+    InsertBatch {
+        points: Vec<(PointId, Vector, Option<Payload>)>,
+    },
+    /// Change the payloads of these points without touching their vectors.
+    UpdatePayload {
+        point_ids: Vec<PointId>,
+        update: PayloadUpdate,
+    },
+    /// Move every index's vectors into read-only memory-mapped files; see `Segment::freeze_vectors`.
+    FreezeVectors {
+        path: PathBuf,
+    },
+}
+
+impl WalRecord {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            WalRecord::Insert { point_id, vector, payload, sparse, named, external } => {
+                codec::put_u8(&mut buf, 0);
+                codec::put_u64(&mut buf, *point_id);
+                codec::put_vector(&mut buf, vector);
+                match payload {
+                    Some(p) => {
+                        codec::put_u8(&mut buf, 1);
+                        codec::put_payload(&mut buf, p);
+                    }
+                    None => codec::put_u8(&mut buf, 0),
+                }
+                match sparse {
+                    Some(v) => {
+                        codec::put_u8(&mut buf, 1);
+                        codec::put_sparse_vector(&mut buf, v);
+                    }
+                    None => codec::put_u8(&mut buf, 0),
+                }
+                codec::put_u64(&mut buf, named.len() as u64);
+                for (name, vector) in named {
+                    codec::put_str(&mut buf, name);
+                    codec::put_vector(&mut buf, vector);
+                }
+                match external {
+                    Some(id) => {
+                        codec::put_u8(&mut buf, 1);
+                        codec::put_extended_id(&mut buf, id);
+                    }
+                    None => codec::put_u8(&mut buf, 0),
+                }
+            }
+            WalRecord::Delete { point_id } => {
+                codec::put_u8(&mut buf, 1);
+                codec::put_u64(&mut buf, *point_id);
+            }
+            WalRecord::Purge => codec::put_u8(&mut buf, 2),
+            WalRecord::Quantize(quantizer) => {
+                codec::put_u8(&mut buf, 3);
+                quantizer.write_snapshot(&mut buf);
+            }
+            WalRecord::AddNamedVector { name, config } => {
+                codec::put_u8(&mut buf, 4);
+                codec::put_str(&mut buf, name);
+                config.write(&mut buf);
+            }
+            WalRecord::EnableMultiVectors(config) => {
+                codec::put_u8(&mut buf, 5);
+                config.write(&mut buf);
+            }
+            WalRecord::SetMultiVector { point_id, vector } => {
+                codec::put_u8(&mut buf, 6);
+                codec::put_u64(&mut buf, *point_id);
+                vector.write(&mut buf);
+            }
+            WalRecord::UpdatePayload { point_ids, update } => {
+                codec::put_u8(&mut buf, 7);
+                codec::put_u64(&mut buf, point_ids.len() as u64);
+                point_ids.iter().for_each(|&id| codec::put_u64(&mut buf, id));
+                update.write(&mut buf);
+            }
+            WalRecord::DeleteMany { point_ids } => {
+                codec::put_u8(&mut buf, 8);
+                codec::put_u64(&mut buf, point_ids.len() as u64);
+                point_ids.iter().for_each(|&id| codec::put_u64(&mut buf, id));
+            }
+            WalRecord::InsertBatch { points } => {
+                codec::put_u8(&mut buf, 9);
+                codec::put_u64(&mut buf, points.len() as u64);
+                for (point_id, vector, payload) in points {
+                    codec::put_u64(&mut buf, *point_id);
+                    codec::put_vector(&mut buf, vector);
+                    match payload {
+                        Some(p) => {
+                            codec::put_u8(&mut buf, 1);
+                            codec::put_payload(&mut buf, p);
+                        }
+                        None => codec::put_u8(&mut buf, 0),
+                    }
+                }
+            }
+            WalRecord::FreezeVectors { path } => {
+                codec::put_u8(&mut buf, 10);
+                codec::put_str(&mut buf, &path.to_string_lossy());
+            }
+        }
+        buf
+    }
+
+    fn decode(body: &[u8]) -> Result<Self, DBError> {
+        Self::decode_body(body).map_err(|e| match e {
+            DBError::WALCorrupt(_) => e,
+            other => DBError::WALCorrupt(format!("malformed record: {}", other)),
+        })
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, DBError> {
+        let mut dec = Decoder::new(body);
+        let record = match dec.u8()? {
+            0 => {
+                let point_id = dec.u64()?;
+                let vector = dec.vector()?;
+                let payload = if dec.bool()? { Some(dec.payload()?) } else { None };
+                let sparse = if dec.bool()? { Some(dec.sparse_vector()?) } else { None };
+                let mut named = Vec::new();
+                for _ in 0..dec.length_prefix(16)? {
+                    named.push((dec.str()?, dec.vector()?));
+                }
+                let external = if dec.bool()? { Some(dec.extended_id()?) } else { None };
+                WalRecord::Insert { point_id, vector, payload, sparse, named, external }
+            }
+            1 => WalRecord::Delete { point_id: dec.u64()? },
+            2 => WalRecord::Purge,
+            3 => WalRecord::Quantize(Quantizer::read_snapshot(&mut dec)?),
+            4 => WalRecord::AddNamedVector { name: dec.str()?, config: IndexConfig::read(&mut dec)? },
+            5 => WalRecord::EnableMultiVectors(MultiVectorConfig::read(&mut dec)?),
+            6 => WalRecord::SetMultiVector { point_id: dec.u64()?, vector: MultiVector::read(&mut dec)? },
+            7 => {
+                let point_ids = (0..dec.length_prefix(8)?).map(|_| dec.u64()).collect::<Result<_, _>>()?;
+                WalRecord::UpdatePayload { point_ids, update: PayloadUpdate::read(&mut dec)? }
+            }
+            8 => WalRecord::DeleteMany { point_ids: (0..dec.length_prefix(8)?).map(|_| dec.u64()).collect::<Result<_, _>>()? },
+            9 => {
+                let mut points = Vec::new();
+                for _ in 0..dec.length_prefix(17)? {
+                    let point_id = dec.u64()?;
+                    let vector = dec.vector()?;
+                    let payload = if dec.bool()? { Some(dec.payload()?) } else { None };
+                    points.push((point_id, vector, payload));
+                }
+                WalRecord::InsertBatch { points }
+            }
+            10 => WalRecord::FreezeVectors { path: PathBuf::from(dec.str()?) },
+            other => return Err(DBError::WALCorrupt(format!("unknown record tag {}", other))),
+        };
+        if !dec.is_empty() {
+            return Err(DBError::WALCorrupt("trailing bytes after record".into()));
+        }
+        Ok(record)
+    }
+}
+
+pub struct WriteAheadLog {
+    file: File,
+    path: PathBuf,
+}
+
+impl WriteAheadLog {
+    /// Create a new, empty log. Fails if a file already exists at `path`.
+    pub fn create(path: impl AsRef<Path>, config: IndexConfig) -> Result<Self, DBError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(WAL_MAGIC);
+        codec::put_u32(&mut header, WAL_VERSION);
+        config.write(&mut header);
+        let crc = crc32fast::hash(&header);
+        codec::put_u32(&mut header, crc);
+
+        file.write_all(&header)?;
+        file.sync_all()?;
+        Ok(Self { file, path })
+    }
+
+    /// Open an existing log and read back its config and every intact record.
+    /// A torn trailing frame is truncated away so later appends start on a frame boundary.
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, IndexConfig, Vec<WalRecord>), DBError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut dec = Decoder::new(&data);
+        let config = Self::read_header(&data, &mut dec)?;
+
+        let mut records = Vec::new();
+        let mut valid_len = dec.position();
+        while !dec.is_empty() {
+            let Ok(frame_header) = dec.bytes(FRAME_HEADER_LEN) else { break };
+            let body_len = u32::from_le_bytes(frame_header[0..4].try_into().unwrap()) as usize;
+            let crc = u32::from_le_bytes(frame_header[4..8].try_into().unwrap());
+            let Ok(body) = dec.bytes(body_len) else { break };
+
+            if crc32fast::hash(body) != crc {
+                return Err(DBError::WALCorrupt(format!("checksum mismatch in frame at offset {}", valid_len)));
+            }
+            records.push(WalRecord::decode(body)?);
+            valid_len = dec.position();
+        }
+
+        if valid_len < data.len() {
+            println!("[WAL] Dropping {} bytes of torn trailing frame", data.len() - valid_len);
+            file.set_len(valid_len as u64)?;
+            file.sync_all()?;
+        }
+
+        // Reopen in append mode so writes always land at the end of the log.
+        let file = OpenOptions::new().append(true).open(&path)?;
+        Ok((Self { file, path }, config, records))
+    }
+
+    fn read_header(data: &[u8], dec: &mut Decoder) -> Result<IndexConfig, DBError> {
+        let corrupt = |_| DBError::WALCorrupt("truncated header".into());
+
+        let magic = dec.bytes(WAL_MAGIC.len()).map_err(corrupt)?;
+        if magic != WAL_MAGIC {
+            return Err(DBError::WALCorrupt("bad magic".into()));
+        }
+        let version = dec.u32().map_err(corrupt)?;
+        if version != WAL_VERSION {
+            return Err(DBError::WALCorrupt(format!("unsupported version {}", version)));
+        }
+        let config = IndexConfig::read(dec).map_err(corrupt)?;
+
+        let header_len = dec.position();
+        let crc = dec.u32().map_err(corrupt)?;
+        if crc != crc32fast::hash(&data[..header_len]) {
+            return Err(DBError::WALCorrupt("header checksum mismatch".into()));
+        }
+
+        Ok(config)
+    }
+
+    /// Append a record and flush it to stable storage.
+    pub fn append(&mut self, record: &WalRecord) -> Result<(), DBError> {
+        let body = record.encode();
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+        codec::put_u32(&mut frame, body.len() as u32);
+        codec::put_u32(&mut frame, crc32fast::hash(&body));
+        frame.extend_from_slice(&body);
+
+        self.file.write_all(&frame)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,317 @@
+//! Little-endian binary encoding shared by the on-disk formats (WAL, snapshots).
+use anyhow::anyhow;
+use ordered_float::OrderedFloat;
+
+use crate::utils::errors::DBError;
+use crate::utils::payload::{Payload, PayloadValue};
+use crate::utils::types::{DistanceMetric, ExtendedPointId, Vector, VectorElementType};
+use crate::vector::sparse::SparseVector;
+
+pub fn put_u8(buf: &mut Vec<u8>, v: u8) {
+    buf.push(v);
+}
+
+pub fn put_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub fn put_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub fn put_i64(buf: &mut Vec<u8>, v: i64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub fn put_f32(buf: &mut Vec<u8>, v: f32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub fn put_f64(buf: &mut Vec<u8>, v: f64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub fn put_str(buf: &mut Vec<u8>, s: &str) {
+    put_u64(buf, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+pub fn put_vector(buf: &mut Vec<u8>, v: &[f32]) {
+    put_u64(buf, v.len() as u64);
+    for &x in v {
+        put_f32(buf, x);
+    }
+}
+
+pub fn put_sparse_vector(buf: &mut Vec<u8>, v: &SparseVector) {
+    put_u64(buf, v.len() as u64);
+    for (index, value) in v.iter() {
+        put_u32(buf, index);
+        put_f32(buf, value);
+    }
+}
+
+pub fn put_extended_id(buf: &mut Vec<u8>, id: &ExtendedPointId) {
+    match id {
+        ExtendedPointId::Num(n) => {
+            put_u8(buf, 0);
+            put_u64(buf, *n);
+        }
+        ExtendedPointId::Uuid(u) => {
+            put_u8(buf, 1);
+            buf.extend_from_slice(u.as_bytes());
+        }
+        ExtendedPointId::Str(s) => {
+            put_u8(buf, 2);
+            put_str(buf, s);
+        }
+    }
+}
+
+pub fn put_metric(buf: &mut Vec<u8>, metric: DistanceMetric) {
+    put_u8(buf, match metric {
+        DistanceMetric::Cosine => 0,
+        DistanceMetric::Dot => 1,
+        DistanceMetric::Euclidean => 2,
+        DistanceMetric::Manhattan => 3,
+        DistanceMetric::Chebyshev => 4,
+        DistanceMetric::Hamming => 5,
+        DistanceMetric::Jaccard => 6,
+    });
+}
+
+pub fn put_element_type(buf: &mut Vec<u8>, element_type: VectorElementType) {
+    put_u8(buf, match element_type {
+        VectorElementType::Float32 => 0,
+        VectorElementType::Float16 => 1,
+        VectorElementType::BFloat16 => 2,
+        VectorElementType::Uint8 => 3,
+    });
+}
+
+pub fn put_payload_value(buf: &mut Vec<u8>, value: &PayloadValue) {
+    match value {
+        PayloadValue::Int(i) => {
+            put_u8(buf, 0);
+            put_i64(buf, *i);
+        }
+        PayloadValue::Float(f) => {
+            put_u8(buf, 1);
+            put_f64(buf, f.0);
+        }
+        PayloadValue::Str(s) => {
+            put_u8(buf, 2);
+            put_str(buf, s);
+        }
+        PayloadValue::Bool(b) => {
+            put_u8(buf, 3);
+            put_u8(buf, *b as u8);
+        }
+        PayloadValue::ListInt(l) => {
+            put_u8(buf, 4);
+            put_u64(buf, l.len() as u64);
+            l.iter().for_each(|i| put_i64(buf, *i));
+        }
+        PayloadValue::ListFloat(l) => {
+            put_u8(buf, 5);
+            put_u64(buf, l.len() as u64);
+            l.iter().for_each(|f| put_f64(buf, f.0));
+        }
+        PayloadValue::ListStr(l) => {
+            put_u8(buf, 6);
+            put_u64(buf, l.len() as u64);
+            l.iter().for_each(|s| put_str(buf, s));
+        }
+        PayloadValue::ListBool(l) => {
+            put_u8(buf, 7);
+            put_u64(buf, l.len() as u64);
+            l.iter().for_each(|b| put_u8(buf, *b as u8));
+        }
+    }
+}
+
+pub fn put_payload(buf: &mut Vec<u8>, payload: &Payload) {
+    // Sort keys so that identical payloads always encode to identical bytes.
+    let mut entries: Vec<_> = payload.0.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    put_u64(buf, entries.len() as u64);
+    for (key, value) in entries {
+        put_str(buf, key);
+        put_payload_value(buf, value);
+    }
+}
+
+/// Cursor over an encoded buffer. Every read fails with `SerializationError` on truncated input.
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], DBError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.buf.len()).ok_or_else(|| {
+            DBError::SerializationError(anyhow!(
+                "unexpected end of input: wanted {} bytes at offset {}, have {}",
+                len,
+                self.pos,
+                self.buf.len() - self.pos
+            ))
+        })?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DBError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DBError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DBError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DBError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, DBError> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, DBError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, DBError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, DBError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(DBError::SerializationError(anyhow!("invalid bool byte {}", other))),
+        }
+    }
+
+    /// Reads a length prefix and sanity-checks it against the remaining input.
+    pub fn length_prefix(&mut self, elem_size: usize) -> Result<usize, DBError> {
+        let len = self.u64()? as usize;
+        let remaining = self.buf.len() - self.pos;
+        if len.saturating_mul(elem_size.max(1)) > remaining {
+            return Err(DBError::SerializationError(anyhow!(
+                "length {} exceeds remaining input ({} bytes)",
+                len,
+                remaining
+            )));
+        }
+        Ok(len)
+    }
+
+    pub fn str(&mut self) -> Result<String, DBError> {
+        let len = self.length_prefix(1)?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| DBError::SerializationError(e.into()))
+    }
+
+    pub fn vector(&mut self) -> Result<Vector, DBError> {
+        let len = self.length_prefix(4)?;
+        (0..len).map(|_| self.f32()).collect()
+    }
+
+    pub fn sparse_vector(&mut self) -> Result<SparseVector, DBError> {
+        let len = self.length_prefix(8)?;
+        let (mut indices, mut values) = (Vec::with_capacity(len), Vec::with_capacity(len));
+        for _ in 0..len {
+            indices.push(self.u32()?);
+            values.push(self.f32()?);
+        }
+        SparseVector::new(indices, values).map_err(|e| DBError::SerializationError(anyhow!("invalid sparse vector: {}", e)))
+    }
+
+    pub fn extended_id(&mut self) -> Result<ExtendedPointId, DBError> {
+        match self.u8()? {
+            0 => Ok(ExtendedPointId::Num(self.u64()?)),
+            1 => Ok(ExtendedPointId::Uuid(uuid::Uuid::from_slice(self.bytes(16)?).unwrap())),
+            2 => Ok(ExtendedPointId::Str(self.str()?)),
+            other => Err(DBError::SerializationError(anyhow!("unknown point ID tag {}", other))),
+        }
+    }
+
+    pub fn metric(&mut self) -> Result<DistanceMetric, DBError> {
+        match self.u8()? {
+            0 => Ok(DistanceMetric::Cosine),
+            1 => Ok(DistanceMetric::Dot),
+            2 => Ok(DistanceMetric::Euclidean),
+            3 => Ok(DistanceMetric::Manhattan),
+            4 => Ok(DistanceMetric::Chebyshev),
+            5 => Ok(DistanceMetric::Hamming),
+            6 => Ok(DistanceMetric::Jaccard),
+            other => Err(DBError::SerializationError(anyhow!("unknown distance metric tag {}", other))),
+        }
+    }
+
+    pub fn element_type(&mut self) -> Result<VectorElementType, DBError> {
+        match self.u8()? {
+            0 => Ok(VectorElementType::Float32),
+            1 => Ok(VectorElementType::Float16),
+            2 => Ok(VectorElementType::BFloat16),
+            3 => Ok(VectorElementType::Uint8),
+            other => Err(DBError::SerializationError(anyhow!("unknown vector element type tag {}", other))),
+        }
+    }
+
+    pub fn payload_value(&mut self) -> Result<PayloadValue, DBError> {
+        Ok(match self.u8()? {
+            0 => PayloadValue::Int(self.i64()?),
+            1 => PayloadValue::Float(OrderedFloat(self.f64()?)),
+            2 => PayloadValue::Str(self.str()?),
+            3 => PayloadValue::Bool(self.bool()?),
+            4 => {
+                let len = self.length_prefix(8)?;
+                PayloadValue::ListInt((0..len).map(|_| self.i64()).collect::<Result<_, _>>()?)
+            }
+            5 => {
+                let len = self.length_prefix(8)?;
+                PayloadValue::ListFloat((0..len).map(|_| self.f64().map(OrderedFloat)).collect::<Result<_, _>>()?)
+            }
+            6 => {
+                let len = self.length_prefix(8)?;
+                PayloadValue::ListStr((0..len).map(|_| self.str()).collect::<Result<_, _>>()?)
+            }
+            7 => {
+                let len = self.length_prefix(1)?;
+                PayloadValue::ListBool((0..len).map(|_| self.bool()).collect::<Result<_, _>>()?)
+            }
+            other => return Err(DBError::SerializationError(anyhow!("unknown payload value tag {}", other))),
+        })
+    }
+
+    pub fn payload(&mut self) -> Result<Payload, DBError> {
+        let len = self.length_prefix(9)?;
+        let mut payload = Payload::default();
+        for _ in 0..len {
+            let key = self.str()?;
+            let value = self.payload_value()?;
+            payload.set(&key, value);
+        }
+        Ok(payload)
+    }
+}
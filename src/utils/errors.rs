@@ -30,4 +30,13 @@ pub enum DBError {
 
     #[error("Search failed: {0}")]
     SearchError(String),
+
+    #[error("Read-only storage: {0}")]
+    ReadOnly(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Quantization error: {0}")]
+    QuantizationError(String),
 }
@@ -1,3 +1,4 @@
 pub mod types;
 pub mod payload;
 pub mod errors;
+pub mod codec;
@@ -1,5 +1,6 @@
 //! Payload implementation with setter and comparison support
 use std::collections::HashMap;
+use crate::utils::codec::{self, Decoder};
 use crate::utils::errors::DBError;
 use ordered_float::OrderedFloat;
 
@@ -17,7 +18,7 @@ pub enum PayloadValue {
     ListBool(Vec<bool>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Payload(pub HashMap<String, PayloadValue>);
 
 
@@ -128,6 +129,68 @@ impl Payload {
     }
 }
 
+/// A change to a point's payload that leaves its vectors alone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadUpdate {
+    /// Add these keys, replacing the values of keys already present; other keys are kept.
+    Set(Payload),
+    /// Replace the whole payload.
+    Overwrite(Payload),
+    /// Remove these keys; missing ones are ignored.
+    DeleteKeys(Vec<String>),
+    /// Remove every key.
+    Clear,
+}
+
+impl PayloadUpdate {
+    /// Apply the update to `payload` in place.
+    pub fn apply(&self, payload: &mut Payload) {
+        match self {
+            PayloadUpdate::Set(patch) => {
+                for (key, value) in &patch.0 {
+                    payload.0.insert(key.clone(), value.clone());
+                }
+            }
+            PayloadUpdate::Overwrite(new) => *payload = new.clone(),
+            PayloadUpdate::DeleteKeys(keys) => {
+                for key in keys {
+                    payload.0.remove(key);
+                }
+            }
+            PayloadUpdate::Clear => payload.0.clear(),
+        }
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            PayloadUpdate::Set(patch) => {
+                codec::put_u8(buf, 0);
+                codec::put_payload(buf, patch);
+            }
+            PayloadUpdate::Overwrite(new) => {
+                codec::put_u8(buf, 1);
+                codec::put_payload(buf, new);
+            }
+            PayloadUpdate::DeleteKeys(keys) => {
+                codec::put_u8(buf, 2);
+                codec::put_u64(buf, keys.len() as u64);
+                keys.iter().for_each(|key| codec::put_str(buf, key));
+            }
+            PayloadUpdate::Clear => codec::put_u8(buf, 3),
+        }
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        Ok(match dec.u8()? {
+            0 => PayloadUpdate::Set(dec.payload()?),
+            1 => PayloadUpdate::Overwrite(dec.payload()?),
+            2 => PayloadUpdate::DeleteKeys((0..dec.length_prefix(8)?).map(|_| dec.str()).collect::<Result<_, _>>()?),
+            3 => PayloadUpdate::Clear,
+            other => return Err(DBError::SerializationError(anyhow::anyhow!("unknown payload update {}", other))),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ScalarComparisonOp {
     Eq,
@@ -251,9 +314,3 @@ impl PayloadValue {
     
     
 }
-
-impl Default for Payload {
-    fn default() -> Self {
-        Payload(HashMap::new())
-    }
-}
\ No newline at end of file
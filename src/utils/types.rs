@@ -1,10 +1,55 @@
 #![allow(dead_code)]
 
+use std::fmt;
 
+use uuid::Uuid;
 
 /// The unique identifier for a point in the vector database.
 pub type PointId = u64;
 
+/// A point ID as callers know it. Segments translate each one to a dense internal `PointId`, which
+/// is what graphs, storage and search results use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExtendedPointId {
+    Num(u64),
+    Uuid(Uuid),
+    Str(String),
+}
+
+impl From<u64> for ExtendedPointId {
+    fn from(id: u64) -> Self {
+        ExtendedPointId::Num(id)
+    }
+}
+
+impl From<Uuid> for ExtendedPointId {
+    fn from(id: Uuid) -> Self {
+        ExtendedPointId::Uuid(id)
+    }
+}
+
+impl From<String> for ExtendedPointId {
+    fn from(id: String) -> Self {
+        ExtendedPointId::Str(id)
+    }
+}
+
+impl From<&str> for ExtendedPointId {
+    fn from(id: &str) -> Self {
+        ExtendedPointId::Str(id.to_string())
+    }
+}
+
+impl fmt::Display for ExtendedPointId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtendedPointId::Num(id) => write!(f, "{}", id),
+            ExtendedPointId::Uuid(id) => write!(f, "{}", id),
+            ExtendedPointId::Str(id) => write!(f, "{:?}", id),
+        }
+    }
+}
+
 /// The vector representation of a point.
 pub type Vector = Vec<f32>;
 
@@ -21,11 +66,55 @@ pub type ShardId = u32;
 pub type CollectionName = String;
 
 /// Describes the type of distance metric used for similarity search.
+///
+/// Every returned `ScoredPoint` carries two scores:
+/// - `raw_score` is the metric's natural value, exactly what `vector::metric::score` returns.
+/// - `sort_key` orders results: smaller is always better. It equals `raw_score` for every
+///   distance metric and is `-raw_score` for `Dot`, where larger similarities are better.
+///
+/// Inside the index `Euclidean` is ranked by squared distance; it is converted back to the plain
+/// distance before results are returned, so neither score ever exposes the squared value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DistanceMetric {
+    /// `1 - cos(a, b)`, in `[0, 2]`. Vectors are normalized on insert and on query.
     Cosine,
+    /// Dot product similarity; the only metric where a larger `raw_score` is better.
     Dot,
+    /// L2 distance.
     Euclidean,
+    /// L1 distance: sum of absolute component differences.
+    Manhattan,
+    /// L-infinity distance: largest absolute component difference.
+    Chebyshev,
+    /// Number of positions whose set bit differs; any non-zero component counts as a set bit.
+    Hamming,
+    /// `1 - |A ∩ B| / |A ∪ B|` over the sets of non-zero positions. Two empty sets are identical.
+    Jaccard,
 }
 
+/// Element type an index stores its vectors as. Vectors always enter and leave the API as `f32`;
+/// they are converted on insert and scored without widening the stored copy first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VectorElementType {
+    /// Full precision, 4 bytes per component.
+    #[default]
+    Float32,
+    /// IEEE 754 half precision, 2 bytes per component.
+    Float16,
+    /// bfloat16: the upper half of an f32, 2 bytes per component. Same range as f32, less precision.
+    BFloat16,
+    /// Integers in `0..=255`, 1 byte per component. Components are rounded and clamped on insert,
+    /// so it suits data that already is 8-bit, and cannot hold normalized Cosine vectors.
+    Uint8,
+}
 
+impl VectorElementType {
+    /// Bytes per stored component.
+    pub fn size(self) -> usize {
+        match self {
+            VectorElementType::Float32 => 4,
+            VectorElementType::Float16 | VectorElementType::BFloat16 => 2,
+            VectorElementType::Uint8 => 1,
+        }
+    }
+}
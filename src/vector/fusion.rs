@@ -0,0 +1,89 @@
+//! Fusion of ranked result lists that different retrievals (dense graph search, sparse search)
+//! produced over the same points, for hybrid search.
+use std::collections::HashMap;
+
+use crate::utils::types::{PointId, Score};
+use crate::vector::hnsw::ScoredPoint;
+
+/// `k` used by `Fusion::default()`, the value from the original RRF paper.
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// The retrieval a result list came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScoreSource {
+    Dense,
+    Sparse,
+}
+
+/// Where a fused point stood in one of the lists it was fused from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceScore {
+    pub source: ScoreSource,
+    /// Position in that list, 0 for the best hit.
+    pub rank: usize,
+    /// The `raw_score` the point had in that list, in that retrieval's own units.
+    pub raw_score: Score,
+}
+
+/// How to merge per-source rankings into one. Fused results carry the fused score as `raw_score`,
+/// higher being better, and `-raw_score` as `sort_key`, like `DistanceMetric::Dot`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fusion {
+    /// Reciprocal Rank Fusion: each list adds `1 / (k + rank)`, with ranks starting at 1. Only ranks
+    /// matter, so sources with incomparable scores mix without tuning.
+    Rrf { k: f32 },
+    /// Weighted sum of scores min-max normalized to `[0, 1]` within each list, the best hit of a list
+    /// scoring 1. A point missing from a list gets nothing from it.
+    Linear { dense_weight: f32, sparse_weight: f32 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf { k: DEFAULT_RRF_K }
+    }
+}
+
+impl Fusion {
+    /// Fuse best-first result lists into the best `top_k` points, best first.
+    pub fn fuse(&self, lists: &[(ScoreSource, Vec<ScoredPoint>)], top_k: usize) -> Vec<ScoredPoint> {
+        let mut fused: HashMap<PointId, (Score, Vec<SourceScore>)> = HashMap::new();
+        for (source, list) in lists {
+            let contributions = self.contributions(*source, list);
+            for (rank, (point, contribution)) in list.iter().zip(contributions).enumerate() {
+                let entry = fused.entry(point.id).or_insert_with(|| (0.0, Vec::new()));
+                entry.0 += contribution;
+                entry.1.push(SourceScore { source: *source, rank, raw_score: point.raw_score });
+            }
+        }
+
+        let mut results: Vec<ScoredPoint> = fused
+            .into_iter()
+            .map(|(id, (score, sources))| ScoredPoint { id, raw_score: score, sort_key: -score, sources })
+            .collect();
+        results.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap().then(a.id.cmp(&b.id)));
+        results.truncate(top_k);
+        results
+    }
+
+    /// What each entry of `list` adds to its point's fused score.
+    fn contributions(&self, source: ScoreSource, list: &[ScoredPoint]) -> Vec<Score> {
+        match *self {
+            Fusion::Rrf { k } => (0..list.len()).map(|rank| 1.0 / (k + rank as f32 + 1.0)).collect(),
+            Fusion::Linear { dense_weight, sparse_weight } => {
+                let weight = match source {
+                    ScoreSource::Dense => dense_weight,
+                    ScoreSource::Sparse => sparse_weight,
+                };
+                // `sort_key` orders every metric the same way, so normalize its negation.
+                let best = list.iter().map(|p| -p.sort_key).fold(Score::NEG_INFINITY, Score::max);
+                let worst = list.iter().map(|p| -p.sort_key).fold(Score::INFINITY, Score::min);
+                list.iter()
+                    .map(|p| {
+                        let normalized = if best > worst { (-p.sort_key - worst) / (best - worst) } else { 1.0 };
+                        weight * normalized
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -1,18 +1,30 @@
+use std::borrow::Cow;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
 use rand::seq::IteratorRandom;
 use rand::Rng;
-use crate::utils::types::{PointId, Vector, DistanceMetric, Score};
-use crate::vector::metric::score;
+use crate::utils::types::{PointId, Vector, DistanceMetric, Score, VectorElementType};
+use crate::vector::metric::{internal_score_ref, kernels, to_user_score};
+use crate::vector::storage::{MmapVectors, StoredVector, VectorRef, VectorStorage};
 use crate::utils::errors::DBError;
+use crate::utils::codec::{self, Decoder};
 use crate::payload_storage::stores::PayloadIndex;
 use crate::utils::payload::Payload;
 use crate::payload_storage::filters::{Filter, evaluate_filter};
+use crate::vector::fusion::SourceScore;
+use crate::vector::quantization::{PreparedQuery, QuantizationConfig, QuantizationSearchParams, QuantizedVectors, Quantizer};
 
+mod parallel;
+
+/// A search hit. See `DistanceMetric` for what `raw_score` and `sort_key` hold per metric.
 #[derive(Clone, Debug)]
 pub struct ScoredPoint {
     pub id: PointId,
     pub raw_score: Score,
     pub sort_key: Score,
+    /// For fused results, the rank and score the point had in each retrieval it came from. Empty for
+    /// plain searches.
+    pub sources: Vec<SourceScore>,
 }
 
 // This ordering is used for the candidate queue (we want the candidate with the lowest score to be popped first).
@@ -26,14 +38,47 @@ impl Eq for ScoredPoint {}
 
 impl PartialOrd for ScoredPoint {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        // Invert the ordering so that lower scores (better) are considered "greater" for the BinaryHeap.
-        other.sort_key.partial_cmp(&self.sort_key)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for ScoredPoint {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        // Invert the ordering so that lower scores (better) are considered "greater" for the BinaryHeap.
+        other.sort_key.partial_cmp(&self.sort_key).unwrap()
+    }
+}
+
+/// Per-query knobs accepted by every search entry point. `SearchParams::default()` reproduces the
+/// behaviour of the plain `search` calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchParams {
+    /// Size of the dynamic candidate list at layer 0. `None` uses the `ef` the index was built with;
+    /// either way it is raised to at least `top_k`.
+    pub hnsw_ef: Option<usize>,
+    /// Scan every stored vector instead of walking the graph.
+    pub exact: bool,
+    /// Drop results worse than this raw score: a distance above it (Cosine, Euclidean) or a
+    /// similarity below it (Dot).
+    pub score_threshold: Option<Score>,
+    /// How to use the index's quantized vectors, if it has any. `None` means the defaults:
+    /// traverse on the codes and rescore `top_k` candidates with the original vectors.
+    pub quantization: Option<QuantizationSearchParams>,
+}
+
+impl SearchParams {
+    pub fn with_ef(ef: usize) -> Self {
+        Self { hnsw_ef: Some(ef), ..Self::default() }
+    }
+
+    pub fn exact() -> Self {
+        Self { exact: true, ..Self::default() }
+    }
+
+    /// Traverse on the quantized vectors and rescore `ceil(top_k * oversampling)` candidates.
+    pub fn with_oversampling(oversampling: f32) -> Self {
+        let quantization = QuantizationSearchParams { oversampling, ..QuantizationSearchParams::default() };
+        Self { quantization: Some(quantization), ..Self::default() }
     }
 }
 
@@ -45,20 +90,20 @@ impl Eq for ResultPoint {}
 
 impl PartialOrd for ResultPoint {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        // Normal ordering: lower score is better, so when used in a max-heap the worst (largest score) will be at the top.
-        self.0.sort_key.partial_cmp(&other.0.sort_key)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for ResultPoint {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Normal ordering: lower score is better, so when used in a max-heap the worst (largest score) will be at the top.
         self.0.sort_key.partial_cmp(&other.0.sort_key).unwrap()
     }
 }
 
 pub struct HNSWIndex {
     layers: HashMap<usize, HashMap<PointId, Vec<PointId>>>,
-    vectors: HashMap<PointId, Vector>,
+    vectors: VectorStorage,
     levels: HashMap<PointId, usize>,
     entry_point: Option<PointId>,
     metric: DistanceMetric,
@@ -68,8 +113,14 @@ pub struct HNSWIndex {
     level_scale: f64,
     current_max_level: usize,
     dim: usize,
+    element_type: VectorElementType,
     // NEW: Maintain a set of deleted point IDs for lazy deletion
     deleted: HashSet<PointId>,
+    // Neighbor selection options (HNSW paper, Algorithm 4).
+    extend_candidates: bool,
+    keep_pruned_connections: bool,
+    // Compressed copies of `vectors` used for traversal once `quantize` has been called.
+    quantized: Option<QuantizedVectors>,
 }
 
 
@@ -79,7 +130,7 @@ impl HNSWIndex {
         println!("Creating new HNSWIndex with dim {}, M {}, ef {}, max_level_cap {}", dim, m, ef, max_level_cap);
         Self {
             layers: HashMap::new(),
-            vectors: HashMap::new(),
+            vectors: VectorStorage::InMemory(HashMap::new()),
             levels: HashMap::new(),
             entry_point: None,
             metric,
@@ -89,20 +140,28 @@ impl HNSWIndex {
             level_scale,
             current_max_level: 0,
             dim,
+            element_type: VectorElementType::Float32,
             deleted: HashSet::new(),
+            extend_candidates: false,
+            keep_pruned_connections: false,
+            quantized: None,
         }
     }
 
     fn assign_random_level(&self) -> usize {
         let r: f64 = rand::rng().random_range(0.0..1.0);
         let l = (-r.ln() * self.level_scale).floor() as usize;
-        let level = l.min(self.max_level_cap);
-        level
+        l.min(self.max_level_cap)
     }
 
     pub fn normalize_score(&self, raw: f32) -> f32 {
         match self.metric {
-            DistanceMetric::Cosine | DistanceMetric::Euclidean => raw,
+            DistanceMetric::Cosine
+            | DistanceMetric::Euclidean
+            | DistanceMetric::Manhattan
+            | DistanceMetric::Chebyshev
+            | DistanceMetric::Hamming
+            | DistanceMetric::Jaccard => raw,
             DistanceMetric::Dot => -raw,  // So we can use a min-heap
         }
     }
@@ -112,16 +171,17 @@ impl HNSWIndex {
         self.deleted.insert(point_id);
         // If the deleted point was the entry point, try to choose a new one.
         if Some(point_id) == self.entry_point {
-            self.entry_point = self.vectors.keys().find(|&&id| !self.deleted.contains(&id)).cloned();
+            self.entry_point = self.vectors.ids().find(|&&id| !self.deleted.contains(&id)).cloned();
         }
     }
 
+    /// Insert a point, or replace the vector of an existing one. A replaced point is unlinked from
+    /// the graph and linked again from scratch, and is no longer deleted if it was.
     pub fn insert(&mut self, point_id: PointId, vector: Vector) -> Result<(), DBError> {
         //println!("\n[INSERT] Attempting to insert point: {}", point_id);
-    
-        if self.vectors.contains_key(&point_id) {
-            println!("[INSERT] Point {} already exists. Skipping.", point_id);
-            return Ok(());
+
+        if let VectorStorage::Mmap(mmap) = &self.vectors {
+            return Err(DBError::ReadOnly(format!("vectors are memory-mapped from {}", mmap.path().display())));
         }
     
         if vector.len() != self.dim {
@@ -131,20 +191,28 @@ impl HNSWIndex {
                 actual: vector.len(),
             });
         }
+
+        if self.vectors.contains(&point_id) {
+            println!("[INSERT] Point {} already exists. Replacing.", point_id);
+            self.unlink(point_id);
+            self.deleted.remove(&point_id);
+        }
     
         let level = self.assign_random_level();
         //println!("[INSERT] Assigned random level {} to point {}", level, point_id);
     
         let vec = self.maybe_normalize(&vector);
-        self.vectors.insert(point_id, vec);
+        if let Some(quantized) = self.quantized.as_mut() {
+            quantized.insert(point_id, &vec);
+        }
+        self.vectors.insert(point_id, StoredVector::encode(vec, self.element_type))?;
+        // Link from the stored copy, which may have been rounded to a narrower element type.
+        let query = self.vector(&point_id).to_f32().into_owned();
         self.levels.insert(point_id, level);
     
-        // Initialize self-links
+        // Every level the point lives on gets an (initially empty) adjacency list.
         for l in 0..=level {
-            self.layers.entry(l).or_default()
-                .entry(point_id).or_insert_with(Vec::new)
-                .push(point_id);
-            //println!("[INSERT] Initialized self-link at level {}", l);
+            self.layers.entry(l).or_default().entry(point_id).or_default();
         }
     
         if self.entry_point.is_none() {
@@ -158,7 +226,7 @@ impl HNSWIndex {
         let mut current_entry = if let Some(ep) = self.entry_point {
             if self.deleted.contains(&ep) {
                 // Find a non-deleted entry in the index.
-                self.vectors.keys().find(|&&id| !self.deleted.contains(&id)).cloned().unwrap_or(ep)
+                self.vectors.ids().find(|&&id| !self.deleted.contains(&id)).cloned().unwrap_or(ep)
             } else {
                 ep
             }
@@ -168,29 +236,19 @@ impl HNSWIndex {
         
         for l in ((level + 1)..=self.current_max_level).rev() {
             //println!("[INSERT] Greedy search for entry at level {} starting from {}", l, current_entry);
-            current_entry = self.greedy_search_layer_unfiltered(&self.vectors[&point_id], current_entry, l);
+            current_entry = self.greedy_search_layer_unfiltered(&query, current_entry, l);
             //println!("[INSERT] Entry point after greedy search at level {}: {}", l, current_entry);
         }
     
         for l in (0..=level).rev() {
             //println!("[INSERT] Performing search layer at level {}...", l);
-            let use_norm = self.metric == DistanceMetric::Cosine || self.metric == DistanceMetric::Dot;
-            let candidates = self.search_layer_unfiltered(&self.vectors[&point_id], current_entry, l, self.ef, use_norm)?;
-            let neighbors: Vec<PointId> = candidates.iter().take(self.m).map(|sp| sp.id).collect();
+            let candidates = self.search_layer_unfiltered(&query, current_entry, l, self.ef)?;
+            let neighbors = self.select_neighbors_heuristic(point_id, &candidates, self.m, l, self.extend_candidates);
             //println!("[INSERT] Found neighbors at level {} for {}: {:?}", l, point_id, neighbors);
     
-            let layer = self.layers.get_mut(&l).unwrap();
-            let mut linked = neighbors.clone();
-            if !linked.contains(&point_id) {
-                linked.push(point_id);
-            }
-            layer.insert(point_id, linked.clone());
-    
+            self.layers.get_mut(&l).unwrap().insert(point_id, neighbors.clone());
             for &n in &neighbors {
-                let e = layer.entry(n).or_default();
-                if !e.contains(&point_id) {
-                    e.push(point_id);
-                }
+                self.add_back_link(l, n, point_id);
             }
     
             if let Some(&best) = neighbors.first() {
@@ -207,10 +265,99 @@ impl HNSWIndex {
         Ok(())
     }
     
+    /// Insert many points at once, linking them into the graph from `threads` worker threads. Each
+    /// point is linked exactly as `insert` would, so the graph is as good as a serial build; see
+    /// `parallel` for the locking. Existing IDs are replaced as by `insert`. Fails without inserting
+    /// anything if a vector has the wrong length or an ID appears twice.
+    pub fn insert_batch(&mut self, points: Vec<(PointId, Vector)>, threads: usize) -> Result<(), DBError> {
+        if let VectorStorage::Mmap(mmap) = &self.vectors {
+            return Err(DBError::ReadOnly(format!("vectors are memory-mapped from {}", mmap.path().display())));
+        }
+        let mut seen = HashSet::with_capacity(points.len());
+        for (id, vector) in &points {
+            if vector.len() != self.dim {
+                return Err(DBError::VectorLengthMismatch { expected: self.dim, actual: vector.len() });
+            }
+            if !seen.insert(*id) {
+                return Err(DBError::InvalidConfig(format!("point {} appears twice in the batch", id)));
+            }
+        }
+
+        // Storing vectors and drawing levels is cheap and stays serial; only linking is parallel.
+        let mut ids = Vec::with_capacity(points.len());
+        for (point_id, vector) in points {
+            if self.vectors.contains(&point_id) {
+                println!("[INSERT] Point {} already exists. Replacing.", point_id);
+                self.unlink(point_id);
+                self.deleted.remove(&point_id);
+            }
+            let vec = self.maybe_normalize(&vector);
+            if let Some(quantized) = self.quantized.as_mut() {
+                quantized.insert(point_id, &vec);
+            }
+            self.vectors.insert(point_id, StoredVector::encode(vec, self.element_type))?;
+            self.levels.insert(point_id, self.assign_random_level());
+            ids.push(point_id);
+        }
+
+        let layers = std::mem::take(&mut self.layers);
+        let graph = parallel::ConcurrentGraph::new(self, layers, &ids);
+        graph.insert_all(&ids, threads);
+        let (layers, entry_point, current_max_level) = graph.into_parts();
+        self.layers = layers;
+        self.entry_point = entry_point;
+        self.current_max_level = current_max_level;
+        Ok(())
+    }
+
+    /// Take a point out of the graph, leaving its vector in storage. Nodes that linked to it are
+    /// offered its former neighbors instead, so the graph stays navigable around the gap. Links are
+    /// mostly two-way, so those nodes are looked for among the point's neighbors and theirs rather
+    /// than across the whole layer; a one-way link from further away is left to point at the node,
+    /// which callers link again right away.
+    fn unlink(&mut self, point_id: PointId) {
+        let level = self.levels.remove(&point_id).unwrap_or(0);
+        for l in 0..=level {
+            let Some(layer) = self.layers.get_mut(&l) else { continue };
+            let mut former = layer.remove(&point_id).unwrap_or_default();
+            let mut nearby: HashSet<PointId> = former.iter().copied().collect();
+            for n in &former {
+                nearby.extend(layer.get(n).into_iter().flatten().copied());
+            }
+            nearby.remove(&point_id);
+            let orphaned: Vec<PointId> = nearby
+                .into_iter()
+                .filter_map(|id| {
+                    let list = layer.get_mut(&id)?;
+                    let before = list.len();
+                    list.retain(|&n| n != point_id);
+                    (list.len() < before).then_some(id)
+                })
+                .collect();
+            former.retain(|n| !self.deleted.contains(n));
+            for node in orphaned {
+                for &n in &former {
+                    self.add_back_link(l, node, n);
+                }
+            }
+        }
+
+        if self.entry_point == Some(point_id) {
+            // Prefer the highest live node; deleted ones still route searches if nothing else is left.
+            let top = self
+                .levels
+                .iter()
+                .max_by_key(|&(id, &lvl)| (!self.deleted.contains(id), lvl))
+                .map(|(&id, &lvl)| (id, lvl));
+            self.entry_point = top.map(|(id, _)| id);
+            self.current_max_level = top.map_or(0, |(_, lvl)| lvl);
+        }
+    }
+
     pub fn build_filter_aware_edges(
         &mut self,
         point_id: PointId,
-        vector: &Vector,
+        vector: &[f32],
         payload: &Payload,
         payload_index: &PayloadIndex,
         payloads: &HashMap<PointId, Payload>,
@@ -219,7 +366,7 @@ impl HNSWIndex {
         let query_vector = if self.metric == DistanceMetric::Cosine {
             self.maybe_normalize(vector)
         } else {
-            vector.clone()
+            vector.to_vec()
         };
     
         let mut extra_neighbors = HashSet::new();
@@ -232,17 +379,17 @@ impl HNSWIndex {
                     let mut rng = rand::rng();
                     let sample = id_set
                         .iter()
-                        .filter(|&&id| id != point_id && self.get_vector(&id).is_some())
+                        .filter(|&&id| id != point_id && self.live_vector(&id).is_some())
                         .copied()
                         .choose_multiple(&mut rng, 100); // sample limit
     
                     let mut scored: Vec<_> = sample
                         .into_iter()
                         .filter_map(|id| {
-                            self.get_vector(&id).map(|vec| {
-                                let raw = score(&query_vector, vec, self.metric);
+                            self.live_vector(&id).map(|vec| {
+                                let raw = self.distance(&query_vector, vec);
                                 let sort_key = self.normalize_score(raw);
-                                ScoredPoint { id, raw_score: raw, sort_key }
+                                ScoredPoint { id, raw_score: raw, sort_key, sources: Vec::new() }
                             })
                         })
                         .collect();
@@ -270,16 +417,17 @@ impl HNSWIndex {
                     for l in (1..=self.current_max_level()).rev() {
                         entry = self.greedy_search_layer_unfiltered(&query_vector, entry, l);
                     }
-                    self.search_layer_unfiltered(&query_vector, entry, 0, self.ef(), self.metric == DistanceMetric::Cosine || self.metric == DistanceMetric::Dot)?
+                    self.search_layer_unfiltered(&query_vector, entry, 0, self.ef())?
                 } else {
-                    self.iter_vectors()
+                    self.vectors.iter()
                         .filter_map(|(&id, vec)| {
                             if id != point_id && !self.deleted.contains(&id) {
-                                let raw = score(&query_vector, vec, self.metric);
+                                let raw = self.distance(&query_vector, vec);
                                 Some(ScoredPoint {
                                     id,
                                     raw_score: raw,
                                     sort_key: self.normalize_score(raw),
+                                    sources: Vec::new(),
                                 })
                             } else {
                                 None
@@ -301,7 +449,7 @@ impl HNSWIndex {
                     .filter(|sp| {
                         payloads.get(&sp.id)
                             .and_then(|p| p.get(key))
-                            .map_or(false, |v| v == value)
+                            .is_some_and(|v| v == value)
                     })
                     .take(m)
                     .map(|sp| sp.id)
@@ -315,8 +463,6 @@ impl HNSWIndex {
             }
         }
     
-        extra_neighbors.insert(point_id); // self-loop
-    
         for neighbor_id in extra_neighbors {
             self.add_bidirectional_edge(0, point_id, neighbor_id);
         }
@@ -325,13 +471,219 @@ impl HNSWIndex {
     }
     
 
+    /// Neighbor selection heuristic (HNSW paper, Algorithm 4).
+    ///
+    /// Candidates are visited nearest-first and one is kept only if it is closer to `point_id` than to
+    /// every neighbor already kept, which spreads links across directions instead of packing them into
+    /// one cluster. With `extend_candidates`, the candidates' own neighbors at `level` are considered
+    /// too; with `keep_pruned_connections`, discarded candidates fill any slots left under `m`.
+    /// Deleted points are never selected.
+    fn select_neighbors_heuristic(
+        &self,
+        point_id: PointId,
+        candidates: &[ScoredPoint],
+        m: usize,
+        level: usize,
+        extend_candidates: bool,
+    ) -> Vec<PointId> {
+        let base = &self.vector(&point_id).to_f32();
+        let distance = |a: &[f32], b: VectorRef| self.normalize_score(self.distance(a, b));
+
+        let mut working: Vec<(PointId, f32)> = candidates
+            .iter()
+            .filter(|sp| sp.id != point_id && !self.deleted.contains(&sp.id))
+            .map(|sp| (sp.id, distance(base, self.vector(&sp.id))))
+            .collect();
+
+        if extend_candidates {
+            let mut seen: HashSet<PointId> = working.iter().map(|&(id, _)| id).collect();
+            seen.insert(point_id);
+            for sp in candidates {
+                for &adj in self.layer_neighbors(level, sp.id).into_iter().flatten() {
+                    if !self.deleted.contains(&adj) && seen.insert(adj) {
+                        working.push((adj, distance(base, self.vector(&adj))));
+                    }
+                }
+            }
+        }
+
+        self.select_diverse(working, m)
+    }
+
+    /// The diversity pass of `select_neighbors_heuristic` over `(id, sort_key)` pairs measured from
+    /// the point being linked.
+    fn select_diverse(&self, mut working: Vec<(PointId, f32)>, m: usize) -> Vec<PointId> {
+        let distance = |a: &[f32], b: VectorRef| self.normalize_score(self.distance(a, b));
+        working.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let mut selected: Vec<PointId> = Vec::with_capacity(m);
+        let mut discarded = Vec::new();
+        for (id, dist) in working {
+            if selected.len() >= m {
+                break;
+            }
+            let candidate = &self.vector(&id).to_f32();
+            let diverse = selected.iter().all(|r| dist < distance(candidate, self.vector(r)));
+            if diverse {
+                selected.push(id);
+            } else {
+                discarded.push(id);
+            }
+        }
+
+        if self.keep_pruned_connections {
+            let room = m.saturating_sub(selected.len());
+            selected.extend(discarded.into_iter().take(room));
+        }
+
+        selected
+    }
+
+    /// Also consider the candidates' own neighbors during neighbor selection.
+    pub fn set_extend_candidates(&mut self, enabled: bool) {
+        self.extend_candidates = enabled;
+    }
+
+    /// Fill leftover neighbor slots with candidates the heuristic pruned.
+    pub fn set_keep_pruned_connections(&mut self, enabled: bool) {
+        self.keep_pruned_connections = enabled;
+    }
+
+    /// A new, empty index with the same configuration as this one.
+    pub fn empty_like(&self) -> Self {
+        let mut index = Self::new(self.metric, self.m, self.ef, self.max_level_cap, self.dim);
+        index.extend_candidates = self.extend_candidates;
+        index.keep_pruned_connections = self.keep_pruned_connections;
+        index.element_type = self.element_type;
+        index.quantized = self.quantized.as_ref().map(QuantizedVectors::empty_like);
+        index
+    }
+
+    /// Train a quantizer on the live vectors and encode every stored point with it. Later inserts
+    /// are encoded with the same quantizer; call again to retrain after the data has drifted.
+    ///
+    /// The full-precision vectors are kept alongside the codes, for rescoring and for `get_vector`,
+    /// so quantizing adds to memory rather than replacing it. To get them off the heap, call
+    /// `freeze_vectors` afterwards: searches then read the codes from memory and touch the mapped
+    /// originals only to rescore.
+    pub fn quantize(&mut self, config: &QuantizationConfig) -> Result<(), DBError> {
+        let quantizer = self.train_quantizer(config)?;
+        self.set_quantizer(quantizer);
+        Ok(())
+    }
+
+    /// Train a quantizer on the live vectors without applying it. Training can be randomized
+    /// (product quantization seeds k-means++), so callers that must reproduce the result keep the
+    /// returned quantizer rather than the config.
+    pub fn train_quantizer(&self, config: &QuantizationConfig) -> Result<Quantizer, DBError> {
+        let live: Vec<Cow<[f32]>> = self
+            .vectors
+            .iter()
+            .filter(|(id, _)| !self.deleted.contains(id))
+            .map(|(_, vec)| vec.to_f32())
+            .collect();
+        let refs: Vec<&[f32]> = live.iter().map(|v| v.as_ref()).collect();
+        Quantizer::train(config, self.metric, self.dim, &refs)
+    }
+
+    /// Encode every stored point with an already trained `quantizer` and search with it from then on.
+    pub fn set_quantizer(&mut self, quantizer: Quantizer) {
+        self.quantized = Some(self.encode_all(quantizer));
+    }
+
+    fn encode_all(&self, quantizer: Quantizer) -> QuantizedVectors {
+        let mut quantized = QuantizedVectors::new(quantizer);
+        for (&id, vec) in self.vectors.iter() {
+            quantized.insert(id, &vec.to_f32());
+        }
+        quantized
+    }
+
+    /// The quantized vectors searches traverse with, if `quantize` has been called.
+    pub fn quantization(&self) -> Option<&QuantizedVectors> {
+        self.quantized.as_ref()
+    }
+
+    /// Drop the quantized vectors and go back to searching on the originals only.
+    pub fn clear_quantization(&mut self) {
+        self.quantized = None;
+    }
+
+    /// Remove the layer-0 edges between `point_id` and each node of `stale`, in both directions,
+    /// then link the point to its nearest diverse neighbors again so that it stays reachable. An
+    /// edge to a stale node comes back only if that node is one of those proximity neighbors.
+    pub fn drop_edges(&mut self, point_id: PointId, stale: &HashSet<PointId>) -> Result<(), DBError> {
+        if stale.is_empty() {
+            return Ok(());
+        }
+        let Some(layer) = self.layers.get_mut(&0) else { return Ok(()) };
+        if let Some(list) = layer.get_mut(&point_id) {
+            list.retain(|n| !stale.contains(n));
+        }
+        for id in stale {
+            if let Some(list) = layer.get_mut(id) {
+                list.retain(|&n| n != point_id);
+            }
+        }
+
+        let Some(mut entry) = self.entry_point else { return Ok(()) };
+        let query = self.vector(&point_id).to_f32().into_owned();
+        for l in (1..=self.current_max_level).rev() {
+            entry = self.greedy_search_layer_unfiltered(&query, entry, l);
+        }
+        let candidates: Vec<ScoredPoint> =
+            self.search_layer_unfiltered(&query, entry, 0, self.ef)?.into_iter().filter(|sp| sp.id != point_id).collect();
+        for n in self.select_neighbors_heuristic(point_id, &candidates, self.m, 0, self.extend_candidates) {
+            self.add_bidirectional_edge(0, point_id, n);
+        }
+        Ok(())
+    }
+
     pub fn add_bidirectional_edge(&mut self, level: usize, a: PointId, b: PointId) {
-        self.layers.entry(level).or_default().entry(a).or_default().push(b);
-        self.layers.entry(level).or_default().entry(b).or_default().push(a);
+        self.add_back_link(level, a, b);
+        self.add_back_link(level, b, a);
     }
 
-    pub fn greedy_search_layer_unfiltered(&self, query: &Vector, entry: PointId, level: usize) -> PointId {
-        //println!("[GREEDY] Start at level {}, from entry {}", level, entry);
+    /// Maximum number of neighbors a node keeps at `level`: `M0 = 2 * M` on layer 0, `M` above it.
+    pub fn max_degree(&self, level: usize) -> usize {
+        if level == 0 { 2 * self.m } else { self.m }
+    }
+
+    /// Link `node -> neighbor` at `level`, ignoring self-links and duplicates. If that pushes `node`
+    /// past its max degree, its neighbor list is re-pruned with the selection heuristic.
+    fn add_back_link(&mut self, level: usize, node: PointId, neighbor: PointId) {
+        if node == neighbor {
+            return;
+        }
+        let max_degree = self.max_degree(level);
+        let list = self.layers.entry(level).or_default().entry(node).or_default();
+        if list.contains(&neighbor) {
+            return;
+        }
+        list.push(neighbor);
+
+        if list.len() > max_degree {
+            let base = &self.vector(&node).to_f32();
+            let mut candidates: Vec<ScoredPoint> = self.layers[&level][&node]
+                .iter()
+                .map(|&id| {
+                    let raw = self.distance(base, self.vector(&id));
+                    ScoredPoint { id, raw_score: raw, sort_key: self.normalize_score(raw), sources: Vec::new() }
+                })
+                .collect();
+            candidates.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap());
+
+            let kept = self.select_neighbors_heuristic(node, &candidates, max_degree, level, false);
+            self.layers.get_mut(&level).unwrap().insert(node, kept);
+        }
+    }
+
+    /// `query` must already have been passed through `maybe_normalize`.
+    pub fn greedy_search_layer_unfiltered(&self, query: &[f32], entry: PointId, level: usize) -> PointId {
+        self.greedy_search_layer_by(&|id| self.distance(query, self.vector(&id)), entry, level)
+    }
+
+    fn greedy_search_layer_by(&self, dist: &impl Fn(PointId) -> f32, entry: PointId, level: usize) -> PointId {
         let mut current = entry;
         let mut changed = true;
         let mut steps = 0;
@@ -345,10 +697,8 @@ impl HNSWIndex {
                         continue;
                     }
     
-                    let d_current = score(query, &self.vectors[&current], self.metric);
-                    let d_new = score(query, &self.vectors[&neighbor], self.metric);
-                    let s_current = self.normalize_score(d_current);
-                    let s_new = self.normalize_score(d_new);
+                    let s_current = self.normalize_score(dist(current));
+                    let s_new = self.normalize_score(dist(neighbor));
     
                     if s_new < s_current {
                         current = neighbor;
@@ -363,13 +713,24 @@ impl HNSWIndex {
             println!("[GREEDY] WARNING: Reached max steps at level {}, current = {}", level, current);
         }
     
-        //println!("[GREEDY] Finished at point {} at level {}", current, level);
         current
     }
         
+    /// `query` must already have been passed through `maybe_normalize`.
     pub fn greedy_search_layer_with_filter(
         &self,
-        query: &Vector,
+        query: &[f32],
+        entry: PointId,
+        level: usize,
+        payloads: &HashMap<PointId, Payload>,
+        filter: Option<&Filter>,
+    ) -> Result<PointId, DBError> {
+        self.greedy_search_layer_with_filter_by(&|id| self.distance(query, self.vector(&id)), entry, level, payloads, filter)
+    }
+
+    fn greedy_search_layer_with_filter_by(
+        &self,
+        dist: &impl Fn(PointId) -> f32,
         entry: PointId,
         level: usize,
         payloads: &HashMap<PointId, Payload>,
@@ -394,17 +755,8 @@ impl HNSWIndex {
                         }
                     }
 
-                    let d_current = score(query, self.get_vector(&current).unwrap(), self.metric);
-                    let d_new = score(query, self.get_vector(&neighbor).unwrap(), self.metric);
-
-                    let s_current = match self.metric {
-                        DistanceMetric::Dot => -d_current,
-                        _ => d_current,
-                    };
-                    let s_new = match self.metric {
-                        DistanceMetric::Dot => -d_new,
-                        _ => d_new,
-                    };
+                    let s_current = self.normalize_score(dist(current));
+                    let s_new = self.normalize_score(dist(neighbor));
 
                     if s_new < s_current {
                         current = neighbor;
@@ -419,11 +771,10 @@ impl HNSWIndex {
     
     fn search_layer_unfiltered(
         &self,
-        query: &Vector,
+        query: &[f32],
         entry: PointId,
         level: usize,
         ef: usize,
-        normalize: bool,
     ) -> Result<Vec<ScoredPoint>, DBError> {
         if query.len() != self.dim {
             return Err(DBError::VectorLengthMismatch {
@@ -431,37 +782,34 @@ impl HNSWIndex {
                 actual: query.len(),
             });
         }
-    
+        Ok(self.search_layer_by(&|id| self.distance(query, self.vector(&id)), entry, level, ef))
+    }
+
+    /// Beam search over one layer, scoring points with `dist`. Returns up to `ef` points, best first.
+    fn search_layer_by(&self, dist: &impl Fn(PointId) -> f32, entry: PointId, level: usize, ef: usize) -> Vec<ScoredPoint> {
         let mut visited = HashSet::new();
         let mut candidate_queue = BinaryHeap::new();
         let mut result_set = BinaryHeap::new();
     
         // If the entry is deleted, skip it by choosing a non-deleted vector (if possible)
         let start_entry = if self.deleted.contains(&entry) {
-            self.vectors.keys().find(|&&id| !self.deleted.contains(&id)).cloned().unwrap_or(entry)
+            self.vectors.ids().find(|&&id| !self.deleted.contains(&id)).cloned().unwrap_or(entry)
         } else {
             entry
         };
     
-        let entry_distance = score(query, &self.vectors[&start_entry], self.metric);
-        let entry_score = if normalize {
-            self.normalize_score(entry_distance)
-        } else {
-            entry_distance
-        };
-    
+        let entry_distance = dist(start_entry);
         let initial = ScoredPoint {
             id: start_entry,
             raw_score: entry_distance,
-            sort_key: entry_score,
+            sort_key: self.normalize_score(entry_distance),
+            sources: Vec::new(),
         };
     
         candidate_queue.push(initial.clone());
         result_set.push(ResultPoint(initial.clone()));
         visited.insert(start_entry);
     
-        //println!("[search_layer_unfiltered] Initial score at entry {}: {:.4}",start_entry, entry_score);
-    
         let mut worst_score = result_set.peek().unwrap().0.sort_key;
     
         while let Some(current) = candidate_queue.peek() {
@@ -476,18 +824,15 @@ impl HNSWIndex {
                         continue;
                     }
     
-                    let raw = score(query, &self.vectors[&neighbor], self.metric);
-                    let score_val = if normalize {
-                        self.normalize_score(raw)
-                    } else {
-                        raw
-                    };
+                    let raw = dist(neighbor);
+                    let score_val = self.normalize_score(raw);
     
                     if result_set.len() < ef || score_val < worst_score {
                         let sp = ScoredPoint {
                             id: neighbor,
                             raw_score: raw,
                             sort_key: score_val,
+                            sources: Vec::new(),
                         };
                         candidate_queue.push(sp.clone());
                         result_set.push(ResultPoint(sp));
@@ -502,15 +847,19 @@ impl HNSWIndex {
             }
         }
     
-        let mut results: Vec<ScoredPoint> = result_set.into_iter().map(|rp| rp.0).collect();
-        results.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap());
-    
-        //println!("[search_layer_unfiltered] Done. Returning top {} results: {:?}",results.len(),results.iter().map(|sp| sp.id).collect::<Vec<_>>());
-    
-        Ok(results)
+        result_set.into_sorted_vec().into_iter().map(|rp| rp.0).collect()
     }
        
-    pub fn search(&self, query: &Vector, top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
+    pub fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
+        self.search_with_params(query, top_k, &SearchParams::default())
+    }
+
+    pub fn search_with_params(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
         println!("Searching top_k = {}", top_k);
         if self.entry_point.is_none() {
             println!("No entry point. Returning empty result.");
@@ -522,39 +871,168 @@ impl HNSWIndex {
                 actual: query.len(),
             });
         }
-        
-        let (normalize_query, normalize_score_flag) = match self.metric {
-            DistanceMetric::Cosine => (true, true),
-            DistanceMetric::Dot => (false, true), // invert score but don’t normalize vec
-            DistanceMetric::Euclidean => (false, false),
-        };
-        
-        let query_for_greedy = if normalize_query {
-            self.maybe_normalize(query)
-        } else {
-            query.clone()
-        };
-                
-        let mut current = self.entry_point.unwrap();
-        for l in (1..=self.current_max_level).rev() {
-            current = self.greedy_search_layer_unfiltered(&query_for_greedy, current, l);
+        if params.exact {
+            let points = self.vectors.iter().map(|(&id, vec)| (id, vec));
+            return Ok(self.apply_score_threshold(self.brute_force_search(query, top_k, points), params));
         }
-        
-        let final_query = if normalize_query {
-            self.maybe_normalize(query)
-        } else {
-            query.clone()
+
+        let query = &self.maybe_normalize(query);
+        let ef = params.hnsw_ef.unwrap_or(self.ef).max(top_k);
+        let mut results = match self.active_quantization(params) {
+            Some((quantized, qp)) => {
+                let prepared = quantized.quantizer().prepare(query);
+                let dist = |id: PointId| self.quantized_distance(quantized, &prepared, query, id);
+                let wanted = qp.candidates(top_k);
+                let candidates = self.graph_search(&dist, ef.max(wanted));
+                if qp.rescore { self.rescore(query, candidates, wanted) } else { candidates }
+            }
+            None => self.graph_search(&|id| self.distance(query, self.vector(&id)), ef),
         };
-        
-        let mut results = self.search_layer_unfiltered(&final_query, current, 0, self.ef, normalize_score_flag)?;
-        results.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap());
         results.truncate(top_k);
+        let results = self.apply_score_threshold(self.finalize_scores(results), params);
         println!("Search complete. Returning {} results", results.len());
         Ok(results)
     }
+
+    /// Greedy descent from the entry point through the upper layers, then a beam search of width
+    /// `ef` on layer 0. Requires a non-empty index.
+    fn graph_search(&self, dist: &impl Fn(PointId) -> f32, ef: usize) -> Vec<ScoredPoint> {
+        let mut current = self.entry_point.unwrap();
+        for l in (1..=self.current_max_level).rev() {
+            current = self.greedy_search_layer_by(dist, current, l);
+        }
+        self.search_layer_by(dist, current, 0, ef)
+    }
+
+    /// Quantized vectors to traverse with, unless the query opted out or none were built.
+    fn active_quantization(&self, params: &SearchParams) -> Option<(&QuantizedVectors, QuantizationSearchParams)> {
+        let qp = params.quantization.unwrap_or_default();
+        if qp.ignore {
+            return None;
+        }
+        self.quantized.as_ref().map(|q| (q, qp))
+    }
+
+    /// Approximate internal score of `id` from its code; points without one are scored exactly.
+    fn quantized_distance(&self, quantized: &QuantizedVectors, prepared: &PreparedQuery, query: &[f32], id: PointId) -> f32 {
+        match quantized.get(&id) {
+            Some(code) => quantized.quantizer().score(prepared, code),
+            None => self.distance(query, self.vector(&id)),
+        }
+    }
+
+    /// Recompute exact scores for the best `keep` candidates and re-rank them.
+    fn rescore(&self, query: &[f32], mut candidates: Vec<ScoredPoint>, keep: usize) -> Vec<ScoredPoint> {
+        candidates.truncate(keep);
+        for sp in &mut candidates {
+            sp.raw_score = self.distance(query, self.vector(&sp.id));
+            sp.sort_key = self.normalize_score(sp.raw_score);
+        }
+        candidates.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap());
+        candidates
+    }
+
+    /// Exhaustively score every live point that passes `filter` and return the true top-k, best
+    /// first, with the same `ScoredPoint` semantics as the graph searches.
+    pub fn exact_search(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        payloads: &HashMap<PointId, Payload>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.check_query_dim(query)?;
+        let points = self
+            .vectors
+            .iter()
+            .filter(|(id, _)| Self::passes_filter(**id, payloads, filter))
+            .map(|(&id, vec)| (id, vec));
+        Ok(self.brute_force_search(query, top_k, points))
+    }
+
+    /// Like `exact_search`, but only scores the given `candidates`, e.g. the IDs a `PayloadIndex`
+    /// lookup narrowed the filter down to. Unknown IDs are ignored.
+    pub fn exact_search_among(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        candidates: impl IntoIterator<Item = PointId>,
+        payloads: &HashMap<PointId, Payload>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.check_query_dim(query)?;
+        let points = candidates
+            .into_iter()
+            .filter(|&id| Self::passes_filter(id, payloads, filter))
+            .filter_map(|id| self.vectors.get(&id).map(|vec| (id, vec)));
+        Ok(self.brute_force_search(query, top_k, points))
+    }
+
+    fn check_query_dim(&self, query: &[f32]) -> Result<(), DBError> {
+        if query.len() != self.dim {
+            return Err(DBError::VectorLengthMismatch {
+                expected: self.dim,
+                actual: query.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn passes_filter(id: PointId, payloads: &HashMap<PointId, Payload>, filter: Option<&Filter>) -> bool {
+        match filter {
+            Some(f) => payloads.get(&id).is_some_and(|p| evaluate_filter(f, p).unwrap_or(false)),
+            None => true,
+        }
+    }
+
+    /// Score `points` against `query`, skipping deleted ones, and keep the best `top_k`, best first.
+    fn brute_force_search<'a>(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        points: impl Iterator<Item = (PointId, VectorRef<'a>)>,
+    ) -> Vec<ScoredPoint> {
+        let query = self.maybe_normalize(query);
+        let mut heap: BinaryHeap<ResultPoint> = BinaryHeap::with_capacity(top_k + 1);
+        for (id, vec) in points {
+            if self.deleted.contains(&id) {
+                continue;
+            }
+            let raw = self.distance(&query, vec);
+            let sort_key = self.normalize_score(raw);
+            if heap.len() < top_k {
+                heap.push(ResultPoint(ScoredPoint { id, raw_score: raw, sort_key, sources: Vec::new() }));
+            } else if heap.peek().is_some_and(|worst| sort_key < worst.0.sort_key) {
+                heap.pop();
+                heap.push(ResultPoint(ScoredPoint { id, raw_score: raw, sort_key, sources: Vec::new() }));
+            }
+        }
+        self.finalize_scores(heap.into_sorted_vec().into_iter().map(|rp| rp.0).collect())
+    }
+
+    /// Convert internal scores on results about to be returned into the user-facing ones
+    /// described on `ScoredPoint`. Only Euclidean differs: it is ranked by squared distance.
+    fn finalize_scores(&self, mut results: Vec<ScoredPoint>) -> Vec<ScoredPoint> {
+        if self.metric == DistanceMetric::Euclidean {
+            for sp in &mut results {
+                sp.raw_score = to_user_score(sp.raw_score, self.metric);
+                sp.sort_key = self.normalize_score(sp.raw_score);
+            }
+        }
+        results
+    }
+
+    pub(crate) fn apply_score_threshold(&self, mut results: Vec<ScoredPoint>, params: &SearchParams) -> Vec<ScoredPoint> {
+        if let Some(threshold) = params.score_threshold {
+            let limit = self.normalize_score(threshold);
+            results.retain(|sp| sp.sort_key <= limit);
+        }
+        results
+    }
              
 
 
+    #[allow(clippy::only_used_in_recursion)]
     pub fn find_entry_point_matching_filter(
         &self,
         filter: &Filter,
@@ -566,7 +1044,7 @@ impl HNSWIndex {
                 payload_index
                     .query_exact(key, value)?
                     .iter()
-                    .find(|&&id| !self.deleted.contains(&id) && self.get_vector(&id).is_some())
+                    .find(|&&id| !self.deleted.contains(&id) && self.vectors.contains(&id))
                     .copied()
             }
             Filter::And(conds) | Filter::Or(conds) => {
@@ -584,11 +1062,23 @@ impl HNSWIndex {
 
     pub fn in_place_filtered_search(
         &self,
-        query: &Vector,
+        query: &[f32],
+        top_k: usize,
+        payloads: &HashMap<PointId, Payload>,
+        payload_index: &PayloadIndex,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        self.in_place_filtered_search_with_params(query, top_k, payloads, payload_index, filter, &SearchParams::default())
+    }
+
+    pub fn in_place_filtered_search_with_params(
+        &self,
+        query: &[f32],
         top_k: usize,
         payloads: &HashMap<PointId, Payload>,
         payload_index: &PayloadIndex,
         filter: Option<&Filter>,
+        params: &SearchParams,
     ) -> Result<Vec<ScoredPoint>, DBError> {
         if query.len() != self.dim {
             return Err(DBError::VectorLengthMismatch {
@@ -597,7 +1087,13 @@ impl HNSWIndex {
             });
         }
 
-        let mut entry = match self.get_entry_point() {
+        if params.exact {
+            return Ok(self.apply_score_threshold(self.exact_search(query, top_k, payloads, filter)?, params));
+        }
+        let ef = params.hnsw_ef.unwrap_or(self.ef).max(top_k);
+        let query = &self.maybe_normalize(query);
+
+        let entry = match self.get_entry_point() {
             Some(id) => {
                 if let Some(f) = filter {
                     if let Some(payload) = payloads.get(&id) {
@@ -625,28 +1121,56 @@ impl HNSWIndex {
             }
         };
 
+        let mut res = match self.active_quantization(params) {
+            Some((quantized, qp)) => {
+                let prepared = quantized.quantizer().prepare(query);
+                let dist = |id: PointId| self.quantized_distance(quantized, &prepared, query, id);
+                let wanted = qp.candidates(top_k);
+                let candidates = self.filtered_graph_search(&dist, entry, ef.max(wanted), payloads, filter)?;
+                if qp.rescore { self.rescore(query, candidates, wanted) } else { candidates }
+            }
+            None => {
+                let dist = |id: PointId| self.distance(query, self.vector(&id));
+                self.filtered_graph_search(&dist, entry, ef, payloads, filter)?
+            }
+        };
+        res.truncate(top_k);
+        Ok(self.apply_score_threshold(self.finalize_scores(res), params))
+    }
+
+    /// Filtered beam search: descend the upper layers towards points passing `filter`, then
+    /// explore layer 0 through every neighbor while only admitting matches into the results.
+    fn filtered_graph_search(
+        &self,
+        dist: &impl Fn(PointId) -> f32,
+        mut entry: PointId,
+        ef: usize,
+        payloads: &HashMap<PointId, Payload>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredPoint>, DBError> {
+        let passes = |id: PointId| Self::passes_filter(id, payloads, filter);
         for level in (1..=self.current_max_level()).rev() {
-            entry = self.greedy_search_layer_with_filter(query, entry, level, payloads, filter)?;
+            entry = self.greedy_search_layer_with_filter_by(dist, entry, level, payloads, filter)?;
         }
 
         let mut visited = HashSet::new();
         let mut candidate_queue = BinaryHeap::new();
         let mut result_set = BinaryHeap::new();
 
-        let dist = score(query, self.get_vector(&entry).unwrap(), self.metric);
-        let sort_key = match self.metric {
-            DistanceMetric::Dot => -dist,
-            _ => dist,
-        };
-        let first = ScoredPoint { id: entry, raw_score: dist, sort_key };
+        let raw = dist(entry);
+        let sort_key = self.normalize_score(raw);
+        let first = ScoredPoint { id: entry, raw_score: raw, sort_key, sources: Vec::new() };
         candidate_queue.push(first.clone());
-        result_set.push(first);
+        if passes(entry) {
+            result_set.push(ResultPoint(first));
+        }
         visited.insert(entry);
 
         let mut worst_score = sort_key;
 
         while let Some(current) = candidate_queue.pop() {
-            if current.sort_key > worst_score {
+            // Only stop once the result set is full; until then, keep exploring for matches.
+            if result_set.len() >= ef && current.sort_key > worst_score {
                 break;
             }
 
@@ -656,54 +1180,99 @@ impl HNSWIndex {
                         continue;
                     }
 
-                    let d = score(query, self.get_vector(&neighbor).unwrap(), self.metric);
-                    let sort_key = match self.metric {
-                        DistanceMetric::Dot => -d,
-                        _ => d,
-                    };
-                    let sp = ScoredPoint { id: neighbor, raw_score: d, sort_key };
+                    let d = dist(neighbor);
+                    let sort_key = self.normalize_score(d);
+                    let sp = ScoredPoint { id: neighbor, raw_score: d, sort_key, sources: Vec::new() };
 
                     candidate_queue.push(sp.clone());
 
-                    let passes = match filter {
-                        Some(f) => payloads.get(&neighbor).map_or(false, |p| evaluate_filter(f, p).unwrap_or(false)),
-                        None => true,
-                    };
-
-                    if passes {
-                        result_set.push(sp);
-                        if result_set.len() > self.ef {
+                    if passes(neighbor) {
+                        result_set.push(ResultPoint(sp));
+                        if result_set.len() > ef {
                             result_set.pop();
                         }
                         if let Some(rp) = result_set.peek() {
-                            worst_score = rp.sort_key;
+                            worst_score = rp.0.sort_key;
                         }
                     }
                 }
             }
         }
 
-        let mut res: Vec<ScoredPoint> = result_set.into_sorted_vec();
-        res.truncate(top_k);
-        Ok(res)
+        Ok(result_set.into_sorted_vec().into_iter().map(|rp| rp.0).collect())
     }
     
     pub fn contains(&self, point_id: &PointId) -> bool {
-        self.vectors.contains_key(point_id)
+        self.vectors.contains(point_id)
     }
 
     pub fn len(&self) -> usize {
         self.vectors.len()
     }
 
+    pub fn is_deleted(&self, point_id: &PointId) -> bool {
+        self.deleted.contains(point_id)
+    }
+
+    /// Number of stored points not marked deleted.
+    pub fn live_len(&self) -> usize {
+        self.vectors.len() - self.deleted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
     pub fn layer_neighbors(&self, level: usize, point_id: PointId) -> Option<&Vec<PointId>> {
         self.layers.get(&level)?.get(&point_id)
     }
 
-    pub fn iter_vectors(&self) -> impl Iterator<Item = (&PointId, &Vector)> {
+    /// Every stored vector, including deleted ones not yet purged, widened to f32.
+    pub fn iter_vectors(&self) -> impl Iterator<Item = (&PointId, Cow<'_, [f32]>)> {
+        self.vectors.iter().map(|(id, vec)| (id, vec.to_f32()))
+    }
+
+    /// Like `iter_vectors`, but in the storage element type.
+    pub fn iter_stored_vectors(&self) -> impl Iterator<Item = (&PointId, VectorRef<'_>)> {
         self.vectors.iter()
     }
 
+    // Stored vector for a point known to be in the index.
+    fn vector(&self, point_id: &PointId) -> VectorRef<'_> {
+        self.vectors.get(point_id).expect("point is missing from vector storage")
+    }
+
+    fn live_vector(&self, point_id: &PointId) -> Option<VectorRef<'_>> {
+        if self.deleted.contains(point_id) {
+            None
+        } else {
+            self.vectors.get(point_id)
+        }
+    }
+
+    /// Whether vectors are served from a read-only memory-mapped file.
+    pub fn is_mmap(&self) -> bool {
+        self.vectors.is_mmap()
+    }
+
+    /// Bytes of stored vectors held on the heap, not counting quantized codes; zero once frozen.
+    pub fn heap_vector_bytes(&self) -> usize {
+        self.vectors.heap_bytes()
+    }
+
+    /// Write all stored vectors to a contiguous file at `path` and switch to serving them from a
+    /// read-only memory map of that file. Further inserts fail with `DBError::ReadOnly`, and so
+    /// does freezing again: rewriting the file would pull it out from under the live mapping.
+    pub fn freeze_vectors(&mut self, path: impl AsRef<Path>) -> Result<(), DBError> {
+        if let VectorStorage::Mmap(mmap) = &self.vectors {
+            return Err(DBError::ReadOnly(format!("vectors are already memory-mapped from {}", mmap.path().display())));
+        }
+        let path = path.as_ref();
+        MmapVectors::write(path, self.element_type, self.dim, self.vectors.iter())?;
+        self.vectors = VectorStorage::Mmap(MmapVectors::open(path)?);
+        Ok(())
+    }
+
     pub fn metric(&self) -> DistanceMetric {
         self.metric
     }
@@ -724,13 +1293,26 @@ impl HNSWIndex {
         self.dim
     }
 
-    pub fn get_vector(&self, point_id: &PointId) -> Option<&Vector> {
-        // Optionally, one might return None for deleted points.
-        if self.deleted.contains(point_id) {
-            None
-        } else {
-            self.vectors.get(point_id)
+    /// The stored vector widened to f32, or `None` if the point is unknown or deleted.
+    pub fn get_vector(&self, point_id: &PointId) -> Option<Cow<'_, [f32]>> {
+        self.live_vector(point_id).map(VectorRef::to_f32)
+    }
+
+    pub fn element_type(&self) -> VectorElementType {
+        self.element_type
+    }
+
+    /// Choose the element type vectors are stored as. Only possible while the index is empty, and
+    /// `Uint8` cannot be combined with Cosine, whose normalized vectors would all round to zero.
+    pub fn set_element_type(&mut self, element_type: VectorElementType) -> Result<(), DBError> {
+        if !self.vectors.is_empty() {
+            return Err(DBError::InvalidConfig("element type can only be changed on an empty index".into()));
         }
+        if element_type == VectorElementType::Uint8 && self.metric == DistanceMetric::Cosine {
+            return Err(DBError::InvalidConfig("Uint8 storage cannot hold normalized Cosine vectors".into()));
+        }
+        self.element_type = element_type;
+        Ok(())
     }
 
     pub fn get_entry_point(&self) -> Option<u64> {
@@ -749,17 +1331,184 @@ impl HNSWIndex {
         self.current_max_level = level;
     }
 
-    pub fn maybe_normalize(&self, vec: &Vector) -> Vector {
+    /// Serialize the full graph state (config, layers, vectors, levels, entry point, deletions,
+    /// quantizer).
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        codec::put_metric(buf, self.metric);
+        codec::put_u64(buf, self.m as u64);
+        codec::put_u64(buf, self.ef as u64);
+        codec::put_u64(buf, self.max_level_cap as u64);
+        codec::put_u64(buf, self.dim as u64);
+        codec::put_u64(buf, self.current_max_level as u64);
+        codec::put_u8(buf, self.extend_candidates as u8);
+        codec::put_u8(buf, self.keep_pruned_connections as u8);
+        codec::put_element_type(buf, self.element_type);
+        match self.entry_point {
+            Some(ep) => {
+                codec::put_u8(buf, 1);
+                codec::put_u64(buf, ep);
+            }
+            None => codec::put_u8(buf, 0),
+        }
+
+        let mut ids: Vec<_> = self.levels.keys().copied().collect();
+        ids.sort_unstable();
+        codec::put_u64(buf, ids.len() as u64);
+        for id in &ids {
+            codec::put_u64(buf, *id);
+            codec::put_u64(buf, self.levels[id] as u64);
+        }
+
+        // Memory-mapped vectors are referenced by path rather than copied into the snapshot.
+        match &self.vectors {
+            VectorStorage::InMemory(_) => {
+                codec::put_u8(buf, 0);
+                for id in &ids {
+                    self.vector(id).write(buf);
+                }
+            }
+            VectorStorage::Mmap(mmap) => {
+                codec::put_u8(buf, 1);
+                codec::put_str(buf, &mmap.path().to_string_lossy());
+            }
+        }
+
+        let mut levels: Vec<_> = self.layers.keys().copied().collect();
+        levels.sort_unstable();
+        codec::put_u64(buf, levels.len() as u64);
+        for level in levels {
+            let layer = &self.layers[&level];
+            let mut nodes: Vec<_> = layer.keys().copied().collect();
+            nodes.sort_unstable();
+            codec::put_u64(buf, level as u64);
+            codec::put_u64(buf, nodes.len() as u64);
+            for node in nodes {
+                let neighbors = &layer[&node];
+                codec::put_u64(buf, node);
+                codec::put_u64(buf, neighbors.len() as u64);
+                neighbors.iter().for_each(|&n| codec::put_u64(buf, n));
+            }
+        }
+
+        let mut deleted: Vec<_> = self.deleted.iter().copied().collect();
+        deleted.sort_unstable();
+        codec::put_u64(buf, deleted.len() as u64);
+        deleted.iter().for_each(|&id| codec::put_u64(buf, id));
+
+        // Only the trained quantizer is stored; codes are re-encoded from the vectors on load.
+        match &self.quantized {
+            Some(quantized) => {
+                codec::put_u8(buf, 1);
+                quantized.quantizer().write_snapshot(buf);
+            }
+            None => codec::put_u8(buf, 0),
+        }
+    }
+
+    /// Inverse of `write_snapshot`.
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let metric = dec.metric()?;
+        let m = dec.u64()? as usize;
+        let ef = dec.u64()? as usize;
+        let max_level_cap = dec.u64()? as usize;
+        let dim = dec.u64()? as usize;
+        let mut index = Self::new(metric, m, ef, max_level_cap, dim);
+        index.current_max_level = dec.u64()? as usize;
+        index.extend_candidates = dec.bool()?;
+        index.keep_pruned_connections = dec.bool()?;
+        index.element_type = dec.element_type()?;
+        index.entry_point = if dec.bool()? { Some(dec.u64()?) } else { None };
+
+        let num_points = dec.length_prefix(16)?;
+        let mut ids = Vec::with_capacity(num_points);
+        for _ in 0..num_points {
+            let id = dec.u64()?;
+            index.levels.insert(id, dec.u64()? as usize);
+            ids.push(id);
+        }
+
+        match dec.u8()? {
+            0 => {
+                let mut vectors = HashMap::with_capacity(num_points);
+                for id in ids {
+                    let vector = StoredVector::read(dec, index.element_type)?;
+                    if vector.as_ref().len() != dim {
+                        return Err(DBError::VectorLengthMismatch { expected: dim, actual: vector.as_ref().len() });
+                    }
+                    vectors.insert(id, vector);
+                }
+                index.vectors = VectorStorage::InMemory(vectors);
+            }
+            1 => {
+                let mmap = MmapVectors::open(dec.str()?)?;
+                if mmap.dim() != dim {
+                    return Err(DBError::VectorLengthMismatch { expected: dim, actual: mmap.dim() });
+                }
+                if mmap.element_type() != index.element_type {
+                    return Err(DBError::SerializationError(anyhow::anyhow!(
+                        "{} holds {:?} vectors, expected {:?}",
+                        mmap.path().display(),
+                        mmap.element_type(),
+                        index.element_type
+                    )));
+                }
+                if let Some(missing) = ids.iter().find(|id| mmap.get(id).is_none()) {
+                    return Err(DBError::SerializationError(anyhow::anyhow!(
+                        "point {} is missing from {}",
+                        missing,
+                        mmap.path().display()
+                    )));
+                }
+                index.vectors = VectorStorage::Mmap(mmap);
+            }
+            other => {
+                return Err(DBError::SerializationError(anyhow::anyhow!("unknown vector storage tag {}", other)));
+            }
+        }
+
+        let num_layers = dec.length_prefix(16)?;
+        for _ in 0..num_layers {
+            let level = dec.u64()? as usize;
+            let num_nodes = dec.length_prefix(16)?;
+            let layer = index.layers.entry(level).or_default();
+            for _ in 0..num_nodes {
+                let node = dec.u64()?;
+                let degree = dec.length_prefix(8)?;
+                let neighbors = (0..degree).map(|_| dec.u64()).collect::<Result<Vec<_>, _>>()?;
+                layer.insert(node, neighbors);
+            }
+        }
+
+        let num_deleted = dec.length_prefix(8)?;
+        for _ in 0..num_deleted {
+            index.deleted.insert(dec.u64()?);
+        }
+
+        if dec.bool()? {
+            let quantizer = Quantizer::read_snapshot(dec)?;
+            index.quantized = Some(index.encode_all(quantizer));
+        }
+
+        Ok(index)
+    }
+
+    /// Internal score between a query and a stored vector that have both been through
+    /// `maybe_normalize`.
+    fn distance(&self, a: &[f32], b: VectorRef) -> f32 {
+        internal_score_ref(a, b, self.metric)
+    }
+
+    pub fn maybe_normalize(&self, vec: &[f32]) -> Vector {
         match self.metric {
             DistanceMetric::Cosine => {
-                let norm = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let norm = kernels().dot(vec, vec).sqrt();
                 if norm == 0.0 {
-                    vec.clone()
+                    vec.to_vec()
                 } else {
                     vec.iter().map(|x| x / norm).collect()
                 }
             }
-            _ => vec.clone(),
+            _ => vec.to_vec(),
         }
     }
 }
@@ -0,0 +1,241 @@
+//! Multi-threaded graph construction for `HNSWIndex::insert_batch`.
+//!
+//! Workers insert points concurrently, following the same steps as the serial `insert`. The graph
+//! as it stood before the batch stays where it is and is only read. A node's lists are copied out
+//! of it the first time a worker links to the node; those copies, and the lists of the batch's own
+//! points, sit in a fixed number of locked shards. A worker holds at most one shard lock at a time
+//! and every change to a list is merged into it under that lock, so concurrent links to the same
+//! node are never lost. The entry point has a lock of its own; a worker inserting a point above the
+//! current top level keeps it for the whole insertion so that no two workers promote at once.
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ordered_float::OrderedFloat;
+
+use crate::utils::types::PointId;
+use crate::vector::hnsw::HNSWIndex;
+
+type Layers = HashMap<usize, HashMap<PointId, Vec<PointId>>>;
+type Shard = HashMap<PointId, Vec<Vec<PointId>>>;
+
+const SHARDS: usize = 256;
+
+pub(super) struct ConcurrentGraph<'a> {
+    index: &'a HNSWIndex,
+    // Adjacency lists from before the batch, for nodes no worker has linked to yet.
+    base: Layers,
+    // Per touched node, its neighbor list on each level it lives on.
+    shards: Vec<Mutex<Shard>>,
+    // Entry point and top level of the graph.
+    entry: Mutex<(Option<PointId>, usize)>,
+}
+
+impl<'a> ConcurrentGraph<'a> {
+    /// Wrap `layers` for a batch of `points`, which must already be in `index.levels` and have no
+    /// lists in `layers` yet. Each gets an empty list on each of its levels.
+    pub(super) fn new(index: &'a HNSWIndex, layers: Layers, points: &[PointId]) -> Self {
+        let mut shards: Vec<Shard> = (0..SHARDS).map(|_| Shard::new()).collect();
+        for &id in points {
+            shards[Self::shard(id)].insert(id, vec![Vec::new(); index.levels[&id] + 1]);
+        }
+        let shards = shards.into_iter().map(Mutex::new).collect();
+        let entry = Mutex::new((index.entry_point, index.current_max_level));
+        Self { index, base: layers, shards, entry }
+    }
+
+    /// Link every point of `points` into the graph from `threads` workers.
+    pub(super) fn insert_all(&self, points: &[PointId], threads: usize) {
+        let next = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    while let Some(&id) = points.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        self.insert(id);
+                    }
+                });
+            }
+        });
+    }
+
+    /// The adjacency lists by level, the entry point and the top level.
+    pub(super) fn into_parts(self) -> (Layers, Option<PointId>, usize) {
+        let mut layers = self.base;
+        for shard in self.shards {
+            for (id, lists) in shard.into_inner().unwrap() {
+                for (l, list) in lists.into_iter().enumerate() {
+                    layers.entry(l).or_default().insert(id, list);
+                }
+            }
+        }
+        let (entry, max_level) = self.entry.into_inner().unwrap();
+        (layers, entry, max_level)
+    }
+
+    fn shard(id: PointId) -> usize {
+        id as usize % SHARDS
+    }
+
+    fn neighbors(&self, id: PointId, level: usize) -> Vec<PointId> {
+        let shard = self.shards[Self::shard(id)].lock().unwrap();
+        match shard.get(&id) {
+            Some(lists) => lists.get(level).cloned().unwrap_or_default(),
+            None => self.base.get(&level).and_then(|layer| layer.get(&id)).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Merge `new` into `node`'s list at `level` under the node's shard lock, ignoring self-links
+    /// and duplicates, and re-prune the list once it outgrows the max degree, as
+    /// `HNSWIndex::add_back_link` does.
+    fn link(&self, level: usize, node: PointId, new: &[PointId]) {
+        let index = self.index;
+        let max_degree = index.max_degree(level);
+        let mut shard = self.shards[Self::shard(node)].lock().unwrap();
+        let lists = shard.entry(node).or_insert_with(|| {
+            let level = index.levels.get(&node).copied().unwrap_or(0);
+            (0..=level)
+                .map(|l| self.base.get(&l).and_then(|layer| layer.get(&node)).cloned().unwrap_or_default())
+                .collect()
+        });
+        let Some(list) = lists.get_mut(level) else { return };
+        for &n in new {
+            if n != node && !list.contains(&n) {
+                list.push(n);
+            }
+        }
+
+        if list.len() > max_degree {
+            let base = &index.vector(&node).to_f32();
+            let working = list
+                .iter()
+                .filter(|id| !index.deleted.contains(id))
+                .map(|&id| (id, index.normalize_score(index.distance(base, index.vector(&id)))))
+                .collect();
+            *list = index.select_diverse(working, max_degree);
+        }
+    }
+
+    fn insert(&self, point_id: PointId) {
+        let index = self.index;
+        let level = index.levels[&point_id];
+        let query = index.vector(&point_id).to_f32().into_owned();
+        let dist = |id: PointId| index.normalize_score(index.distance(&query, index.vector(&id)));
+
+        let mut entry_guard = self.entry.lock().unwrap();
+        let (Some(mut current), max_level) = *entry_guard else {
+            *entry_guard = (Some(point_id), level);
+            return;
+        };
+        let promote = if level > max_level {
+            Some(entry_guard)
+        } else {
+            drop(entry_guard);
+            None
+        };
+
+        for l in ((level + 1)..=max_level).rev() {
+            current = self.greedy_search_layer(&dist, current, l);
+        }
+
+        for l in (0..=level.min(max_level)).rev() {
+            let candidates = self.search_layer(&dist, current, l, index.ef);
+            let neighbors = self.select_neighbors(point_id, &dist, &candidates, l);
+
+            // Merged rather than assigned: other workers may already have linked to this point.
+            self.link(l, point_id, &neighbors);
+            for &n in &neighbors {
+                self.link(l, n, &[point_id]);
+            }
+
+            if let Some(&best) = neighbors.first() {
+                current = best;
+            }
+        }
+
+        if let Some(mut guard) = promote {
+            *guard = (Some(point_id), level);
+        }
+    }
+
+    /// Move to the closest neighbor until none is closer. Deleted nodes are walked through.
+    fn greedy_search_layer(&self, dist: &impl Fn(PointId) -> f32, entry: PointId, level: usize) -> PointId {
+        let mut current = entry;
+        let mut current_dist = dist(current);
+        loop {
+            let mut changed = false;
+            for neighbor in self.neighbors(current, level) {
+                let d = dist(neighbor);
+                if d < current_dist {
+                    current = neighbor;
+                    current_dist = d;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return current;
+            }
+        }
+    }
+
+    /// Beam search over one layer. Returns up to `ef` live `(id, sort_key)` pairs, best first.
+    /// Deleted nodes are traversed but never returned, and neither is an entry point left below
+    /// `level` by `mark_deleted`.
+    fn search_layer(&self, dist: &impl Fn(PointId) -> f32, entry: PointId, level: usize, ef: usize) -> Vec<(PointId, f32)> {
+        let mut visited = HashSet::from([entry]);
+        // Min-heap of nodes to expand and max-heap of the best `ef` found so far.
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+
+        let d = OrderedFloat(dist(entry));
+        candidates.push((std::cmp::Reverse(d), entry));
+        if self.is_result(entry, level) {
+            results.push((d, entry));
+        }
+
+        while let Some((std::cmp::Reverse(d), current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|&(worst, _)| d > worst) {
+                break;
+            }
+            for neighbor in self.neighbors(current, level) {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let d = OrderedFloat(dist(neighbor));
+                if results.len() < ef || results.peek().is_some_and(|&(worst, _)| d < worst) {
+                    candidates.push((std::cmp::Reverse(d), neighbor));
+                    if self.is_result(neighbor, level) {
+                        results.push((d, neighbor));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec().into_iter().map(|(d, id)| (id, d.0)).collect()
+    }
+
+    fn is_result(&self, id: PointId, level: usize) -> bool {
+        !self.index.deleted.contains(&id) && self.index.levels.get(&id).is_some_and(|&l| l >= level)
+    }
+
+    /// `select_neighbors_heuristic` over this graph's locked lists.
+    fn select_neighbors(&self, point_id: PointId, dist: &impl Fn(PointId) -> f32, candidates: &[(PointId, f32)], level: usize) -> Vec<PointId> {
+        let mut working: Vec<(PointId, f32)> = candidates.iter().copied().filter(|&(id, _)| id != point_id).collect();
+
+        if self.index.extend_candidates {
+            let mut seen: HashSet<PointId> = working.iter().map(|&(id, _)| id).collect();
+            seen.insert(point_id);
+            for &(id, _) in candidates {
+                for adj in self.neighbors(id, level) {
+                    if !self.index.deleted.contains(&adj) && seen.insert(adj) {
+                        working.push((adj, dist(adj)));
+                    }
+                }
+            }
+        }
+
+        self.index.select_diverse(working, self.index.m)
+    }
+}
@@ -1,35 +1,296 @@
-use crate::utils::types::{Vector, DistanceMetric};
+use std::sync::OnceLock;
+
+use half::{bf16, f16};
+
+use crate::utils::types::DistanceMetric;
+use crate::vector::simd::{self, CosineParts};
+use crate::vector::storage::VectorRef;
 
 /// Main distance dispatcher
-pub fn score(a: &Vector, b: &Vector, metric: DistanceMetric) -> f32 {
+pub fn score(a: &[f32], b: &[f32], metric: DistanceMetric) -> f32 {
     assert_eq!(a.len(), b.len(), "Vectors must be the same length");
 
+    let kernels = kernels();
     match metric {
-        DistanceMetric::Cosine => cosine_distance(a, b),
-        DistanceMetric::Dot => dot_product_similarity(a, b),
-        DistanceMetric::Euclidean => euclidean_distance(a, b),
+        DistanceMetric::Cosine => kernels.cosine_distance(a, b),
+        DistanceMetric::Dot => kernels.dot(a, b),
+        DistanceMetric::Euclidean => kernels.l2_squared(a, b).sqrt(),
+        DistanceMetric::Manhattan => manhattan_distance(a, b),
+        DistanceMetric::Chebyshev => chebyshev_distance(a, b),
+        DistanceMetric::Hamming => hamming_distance(a, b),
+        DistanceMetric::Jaccard => jaccard_distance(a, b),
     }
 }
 
-/// Cosine distance: 1 - cosine similarity
-fn cosine_distance(a: &Vector, b: &Vector) -> f32 {
-    let dot = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>();
-    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+/// Same as `score`, for vectors already passed through `HNSWIndex::maybe_normalize`: Cosine skips
+/// recomputing both norms and reduces to `1 - a·b`.
+pub fn score_normalized(a: &[f32], b: &[f32], metric: DistanceMetric) -> f32 {
+    match metric {
+        DistanceMetric::Cosine => {
+            assert_eq!(a.len(), b.len(), "Vectors must be the same length");
+            1.0 - kernels().dot(a, b)
+        }
+        _ => score(a, b, metric),
+    }
+}
 
-    1.0 - (dot / (norm_a * norm_b + 1e-10))  // + epsilon to avoid NaNs
+/// Score used for ranking inside the index, between vectors already passed through
+/// `HNSWIndex::maybe_normalize`. Orders points exactly like `score`, but Euclidean skips the
+/// `sqrt` and is the squared distance; use `to_user_score` before handing it out.
+pub fn internal_score(a: &[f32], b: &[f32], metric: DistanceMetric) -> f32 {
+    match metric {
+        DistanceMetric::Euclidean => {
+            assert_eq!(a.len(), b.len(), "Vectors must be the same length");
+            kernels().l2_squared(a, b)
+        }
+        _ => score_normalized(a, b, metric),
+    }
 }
 
-/// Dot product similarity (inverted to behave like a distance)
-fn dot_product_similarity(a: &Vector, b: &Vector) -> f32 {
-    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>()
+/// `internal_score` between a normalized f32 query and a stored vector of any element type.
+/// Cosine, Dot and Euclidean run on the stored elements directly; the other metrics widen the
+/// stored vector to f32 first.
+pub fn internal_score_ref(query: &[f32], stored: VectorRef, metric: DistanceMetric) -> f32 {
+    if let VectorRef::Float32(v) = stored {
+        return internal_score(query, v, metric);
+    }
+    assert_eq!(query.len(), stored.len(), "Vectors must be the same length");
+    match metric {
+        DistanceMetric::Cosine => 1.0 - kernels().dot_ref(query, stored),
+        DistanceMetric::Dot => kernels().dot_ref(query, stored),
+        DistanceMetric::Euclidean => kernels().l2_squared_ref(query, stored),
+        _ => internal_score(query, &stored.to_f32(), metric),
+    }
+}
+
+/// Convert an `internal_score` into the value `score` would have returned.
+pub fn to_user_score(internal: f32, metric: DistanceMetric) -> f32 {
+    match metric {
+        DistanceMetric::Euclidean => internal.max(0.0).sqrt(),
+        _ => internal,
+    }
+}
+
+/// L1 distance
+fn manhattan_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum()
+}
+
+/// L-infinity distance
+fn chebyshev_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f32::max)
+}
+
+/// Hamming distance over binary embeddings; non-zero components are set bits.
+fn hamming_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).filter(|&(x, y)| (*x != 0.0) != (*y != 0.0)).count() as f32
+}
+
+/// Jaccard distance over the sets of non-zero positions.
+fn jaccard_distance(a: &[f32], b: &[f32]) -> f32 {
+    let (intersection, union) = a.iter().zip(b).fold((0usize, 0usize), |(i, u), (x, y)| {
+        let (in_a, in_b) = (*x != 0.0, *y != 0.0);
+        (i + (in_a && in_b) as usize, u + (in_a || in_b) as usize)
+    });
+    if union == 0 {
+        0.0
+    } else {
+        1.0 - intersection as f32 / union as f32
+    }
+}
+
+/// Instruction sets the distance kernels are compiled for, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    Avx512,
+    Avx2,
+    Sse,
+    Scalar,
+}
+
+impl SimdLevel {
+    pub const ALL: [SimdLevel; 4] = [SimdLevel::Avx512, SimdLevel::Avx2, SimdLevel::Sse, SimdLevel::Scalar];
+
+    /// Whether the running CPU supports this level.
+    pub fn is_available(self) -> bool {
+        #[cfg(target_arch = "x86_64")]
+        {
+            match self {
+                SimdLevel::Avx512 => is_x86_feature_detected!("avx512f"),
+                SimdLevel::Avx2 => is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"),
+                SimdLevel::Sse => is_x86_feature_detected!("sse"),
+                SimdLevel::Scalar => true,
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            self == SimdLevel::Scalar
+        }
+    }
+}
+
+/// A set of distance kernels for one `SimdLevel`.
+#[derive(Clone, Copy)]
+pub struct Kernels {
+    level: SimdLevel,
+    dot: unsafe fn(&[f32], &[f32]) -> f32,
+    l2_squared: unsafe fn(&[f32], &[f32]) -> f32,
+    cosine_parts: unsafe fn(&[f32], &[f32]) -> CosineParts,
+    mixed: MixedKernels,
+}
+
+/// Kernels scoring an f32 query against narrower stored elements.
+#[derive(Clone, Copy)]
+struct MixedKernels {
+    dot_f16: unsafe fn(&[f32], &[f16]) -> f32,
+    l2_squared_f16: unsafe fn(&[f32], &[f16]) -> f32,
+    dot_bf16: unsafe fn(&[f32], &[bf16]) -> f32,
+    l2_squared_bf16: unsafe fn(&[f32], &[bf16]) -> f32,
+    dot_u8: unsafe fn(&[f32], &[u8]) -> f32,
+    l2_squared_u8: unsafe fn(&[f32], &[u8]) -> f32,
+}
+
+impl MixedKernels {
+    fn scalar() -> Self {
+        MixedKernels {
+            dot_f16: simd::scalar::dot_mixed::<f16>,
+            l2_squared_f16: simd::scalar::l2_squared_mixed::<f16>,
+            dot_bf16: simd::scalar::dot_mixed::<bf16>,
+            l2_squared_bf16: simd::scalar::l2_squared_mixed::<bf16>,
+            dot_u8: simd::scalar::dot_mixed::<u8>,
+            l2_squared_u8: simd::scalar::l2_squared_mixed::<u8>,
+        }
+    }
+
+    /// AVX2 versions when the level allows them and the CPU has F16C, scalar otherwise.
+    fn for_level(level: SimdLevel) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        if matches!(level, SimdLevel::Avx512 | SimdLevel::Avx2)
+            && SimdLevel::Avx2.is_available()
+            && is_x86_feature_detected!("f16c")
+        {
+            return MixedKernels {
+                dot_f16: simd::avx2_mixed::dot_f16,
+                l2_squared_f16: simd::avx2_mixed::l2_squared_f16,
+                dot_bf16: simd::avx2_mixed::dot_bf16,
+                l2_squared_bf16: simd::avx2_mixed::l2_squared_bf16,
+                dot_u8: simd::avx2_mixed::dot_u8,
+                l2_squared_u8: simd::avx2_mixed::l2_squared_u8,
+            };
+        }
+        let _ = level;
+        Self::scalar()
+    }
+}
+
+impl Kernels {
+    /// Kernels for `level`, or `None` if the running CPU does not support it.
+    pub fn for_level(level: SimdLevel) -> Option<Self> {
+        if !level.is_available() {
+            return None;
+        }
+        #[cfg(target_arch = "x86_64")]
+        let kernels = match level {
+            SimdLevel::Avx512 => Kernels {
+                level,
+                dot: simd::avx512::dot,
+                l2_squared: simd::avx512::l2_squared,
+                cosine_parts: simd::avx512::cosine_parts,
+                mixed: MixedKernels::for_level(level),
+            },
+            SimdLevel::Avx2 => Kernels {
+                level,
+                dot: simd::avx2::dot,
+                l2_squared: simd::avx2::l2_squared,
+                cosine_parts: simd::avx2::cosine_parts,
+                mixed: MixedKernels::for_level(level),
+            },
+            SimdLevel::Sse => Kernels {
+                level,
+                dot: simd::sse::dot,
+                l2_squared: simd::sse::l2_squared,
+                cosine_parts: simd::sse::cosine_parts,
+                mixed: MixedKernels::for_level(level),
+            },
+            SimdLevel::Scalar => Self::scalar(),
+        };
+        #[cfg(not(target_arch = "x86_64"))]
+        let kernels = Self::scalar();
+        Some(kernels)
+    }
+
+    fn scalar() -> Self {
+        Kernels {
+            level: SimdLevel::Scalar,
+            dot: simd::scalar::dot,
+            l2_squared: simd::scalar::l2_squared,
+            cosine_parts: simd::scalar::cosine_parts,
+            mixed: MixedKernels::scalar(),
+        }
+    }
+
+    /// The best kernels the running CPU supports.
+    pub fn detect() -> Self {
+        SimdLevel::ALL.into_iter().find_map(Self::for_level).unwrap_or_else(Self::scalar)
+    }
+
+    pub fn level(&self) -> SimdLevel {
+        self.level
+    }
+
+    pub fn dot(&self, a: &[f32], b: &[f32]) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        let len = a.len().min(b.len());
+        // Safety: `for_level` only hands out kernels whose CPU features were detected.
+        unsafe { (self.dot)(&a[..len], &b[..len]) }
+    }
+
+    pub fn l2_squared(&self, a: &[f32], b: &[f32]) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        let len = a.len().min(b.len());
+        unsafe { (self.l2_squared)(&a[..len], &b[..len]) }
+    }
+
+    /// Dot product of an f32 query with a stored vector of any element type.
+    pub fn dot_ref(&self, a: &[f32], b: VectorRef) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        let len = a.len().min(b.len());
+        unsafe {
+            match b {
+                VectorRef::Float32(b) => (self.dot)(&a[..len], &b[..len]),
+                VectorRef::Float16(b) => (self.mixed.dot_f16)(&a[..len], &b[..len]),
+                VectorRef::BFloat16(b) => (self.mixed.dot_bf16)(&a[..len], &b[..len]),
+                VectorRef::Uint8(b) => (self.mixed.dot_u8)(&a[..len], &b[..len]),
+            }
+        }
+    }
+
+    /// Squared L2 distance between an f32 query and a stored vector of any element type.
+    pub fn l2_squared_ref(&self, a: &[f32], b: VectorRef) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        let len = a.len().min(b.len());
+        unsafe {
+            match b {
+                VectorRef::Float32(b) => (self.l2_squared)(&a[..len], &b[..len]),
+                VectorRef::Float16(b) => (self.mixed.l2_squared_f16)(&a[..len], &b[..len]),
+                VectorRef::BFloat16(b) => (self.mixed.l2_squared_bf16)(&a[..len], &b[..len]),
+                VectorRef::Uint8(b) => (self.mixed.l2_squared_u8)(&a[..len], &b[..len]),
+            }
+        }
+    }
+
+    /// Cosine distance: 1 - cosine similarity
+    pub fn cosine_distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        let len = a.len().min(b.len());
+        let (dot, norm_a, norm_b) = unsafe { (self.cosine_parts)(&a[..len], &b[..len]) };
+        1.0 - (dot / (norm_a.sqrt() * norm_b.sqrt() + 1e-10)) // + epsilon to avoid NaNs
+    }
 }
 
+static KERNELS: OnceLock<Kernels> = OnceLock::new();
 
-/// Euclidean distance
-fn euclidean_distance(a: &Vector, b: &Vector) -> f32 {
-    a.iter().zip(b.iter())
-        .map(|(x, y)| (x - y).powi(2))
-        .sum::<f32>()
-        .sqrt()
+/// Kernels chosen by CPU-feature detection on first use; `level()` reports which were picked.
+pub fn kernels() -> &'static Kernels {
+    KERNELS.get_or_init(Kernels::detect)
 }
@@ -1,2 +1,8 @@
 pub mod metric;
+pub(crate) mod simd;
 pub mod hnsw;
+pub mod storage;
+pub mod quantization;
+pub mod sparse;
+pub mod fusion;
+pub mod multivector;
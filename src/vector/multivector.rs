@@ -0,0 +1,245 @@
+//! Multi-vectors for late-interaction models such as ColBERT: a point stores one vector per token,
+//! and a query (itself a set of token vectors) is compared against it token by token.
+use std::collections::HashMap;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{DistanceMetric, PointId, Score, Vector};
+use crate::vector::fusion::{ScoreSource, SourceScore};
+use crate::vector::hnsw::ScoredPoint;
+use crate::vector::metric::{kernels, score};
+
+/// A variable number of token vectors of one dimension, stored row after row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiVector {
+    dim: usize,
+    data: Vec<f32>,
+}
+
+impl MultiVector {
+    /// Build a multi-vector from its token vectors. Fails if there are none or their lengths differ.
+    pub fn new(tokens: Vec<Vector>) -> Result<Self, DBError> {
+        let dim = tokens.first().map_or(0, Vec::len);
+        if dim == 0 {
+            return Err(DBError::InvalidConfig("a multi-vector needs at least one non-empty token".into()));
+        }
+        if let Some(t) = tokens.iter().find(|t| t.len() != dim) {
+            return Err(DBError::VectorLengthMismatch { expected: dim, actual: t.len() });
+        }
+        Ok(Self { dim, data: tokens.concat() })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of token vectors.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dim
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &[f32]> {
+        self.data.chunks_exact(self.dim)
+    }
+
+    fn normalized(&self) -> Self {
+        let mut data = self.data.clone();
+        for token in data.chunks_exact_mut(self.dim) {
+            let norm = kernels().dot(token, token).sqrt();
+            if norm > 0.0 {
+                token.iter_mut().for_each(|x| *x /= norm);
+            }
+        }
+        Self { dim: self.dim, data }
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u64(buf, self.dim as u64);
+        codec::put_vector(buf, &self.data);
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        let dim = dec.u64()? as usize;
+        let data = dec.vector()?;
+        if dim == 0 || data.is_empty() || data.len() % dim != 0 {
+            return Err(DBError::SerializationError(anyhow::anyhow!("malformed multi-vector")));
+        }
+        Ok(Self { dim, data })
+    }
+}
+
+/// How a query multi-vector is compared with a stored one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiVectorComparator {
+    /// Sum over query tokens of the best similarity to any stored token (ColBERT's late
+    /// interaction). Larger is better.
+    #[default]
+    MaxSim,
+}
+
+/// Dimension and token-level metric shared by all multi-vectors of a segment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiVectorConfig {
+    pub dim: usize,
+    /// Token similarity is `1 - distance` for Cosine (the cosine similarity), the dot product for
+    /// Dot, and the negated distance for every other metric.
+    pub metric: DistanceMetric,
+    pub comparator: MultiVectorComparator,
+}
+
+impl MultiVectorConfig {
+    pub fn new(dim: usize, metric: DistanceMetric) -> Self {
+        Self { dim, metric, comparator: MultiVectorComparator::MaxSim }
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u64(buf, self.dim as u64);
+        codec::put_metric(buf, self.metric);
+        codec::put_u8(buf, match self.comparator {
+            MultiVectorComparator::MaxSim => 0,
+        });
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        let dim = dec.u64()? as usize;
+        let metric = dec.metric()?;
+        let comparator = match dec.u8()? {
+            0 => MultiVectorComparator::MaxSim,
+            other => return Err(DBError::SerializationError(anyhow::anyhow!("unknown multi-vector comparator {}", other))),
+        };
+        Ok(Self { dim, metric, comparator })
+    }
+
+    /// Similarity of two tokens, larger being better. Cosine tokens are normalized on the way in.
+    fn similarity(&self, a: &[f32], b: &[f32]) -> Score {
+        match self.metric {
+            DistanceMetric::Cosine | DistanceMetric::Dot => kernels().dot(a, b),
+            other => -score(a, b, other),
+        }
+    }
+
+    /// Compare `query` with `stored`, both already prepared for this config.
+    fn compare(&self, query: &MultiVector, stored: &MultiVector) -> Score {
+        match self.comparator {
+            MultiVectorComparator::MaxSim => query
+                .tokens()
+                .map(|q| stored.tokens().map(|d| self.similarity(q, d)).fold(Score::NEG_INFINITY, Score::max))
+                .sum(),
+        }
+    }
+}
+
+/// Per-point multi-vectors under one `MultiVectorConfig`. Not indexed: they score candidates that
+/// another search produced.
+#[derive(Debug, Clone)]
+pub struct MultiVectorStorage {
+    config: MultiVectorConfig,
+    vectors: HashMap<PointId, MultiVector>,
+}
+
+impl MultiVectorStorage {
+    pub fn new(config: MultiVectorConfig) -> Self {
+        Self { config, vectors: HashMap::new() }
+    }
+
+    pub fn config(&self) -> MultiVectorConfig {
+        self.config
+    }
+
+    fn check_dim(&self, vector: &MultiVector) -> Result<(), DBError> {
+        if vector.dim() != self.config.dim {
+            return Err(DBError::VectorLengthMismatch { expected: self.config.dim, actual: vector.dim() });
+        }
+        Ok(())
+    }
+
+    fn prepare(&self, vector: &MultiVector) -> MultiVector {
+        match self.config.metric {
+            DistanceMetric::Cosine => vector.normalized(),
+            _ => vector.clone(),
+        }
+    }
+
+    /// Store `vector` for `point_id`, replacing any previous one.
+    pub fn insert(&mut self, point_id: PointId, vector: MultiVector) -> Result<(), DBError> {
+        self.check_dim(&vector)?;
+        let vector = self.prepare(&vector);
+        self.vectors.insert(point_id, vector);
+        Ok(())
+    }
+
+    /// The stored multi-vector; Cosine tokens come back normalized.
+    pub fn get(&self, point_id: &PointId) -> Option<&MultiVector> {
+        self.vectors.get(point_id)
+    }
+
+    pub fn remove(&mut self, point_id: PointId) -> Option<MultiVector> {
+        self.vectors.remove(&point_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// Score of `point_id` against `query`, or `None` if it has no multi-vector.
+    pub fn score(&self, query: &MultiVector, point_id: PointId) -> Result<Option<Score>, DBError> {
+        self.check_dim(query)?;
+        let query = self.prepare(query);
+        Ok(self.vectors.get(&point_id).map(|stored| self.config.compare(&query, stored)))
+    }
+
+    /// Re-rank best-first `candidates` by comparing `query` with their multi-vectors and keep the
+    /// best `top_k`. Results use Dot semantics (`sort_key` is `-raw_score`). Candidates from a plain
+    /// search get their original rank and score recorded as a `ScoreSource::Dense` source; fused
+    /// ones keep their sources. Candidates without a multi-vector are dropped.
+    pub fn rescore(&self, query: &MultiVector, candidates: Vec<ScoredPoint>, top_k: usize) -> Result<Vec<ScoredPoint>, DBError> {
+        self.check_dim(query)?;
+        let query = self.prepare(query);
+        let mut results: Vec<ScoredPoint> = candidates
+            .into_iter()
+            .enumerate()
+            .filter_map(|(rank, candidate)| {
+                let stored = self.vectors.get(&candidate.id)?;
+                let score = self.config.compare(&query, stored);
+                let mut sources = candidate.sources;
+                if sources.is_empty() {
+                    sources.push(SourceScore { source: ScoreSource::Dense, rank, raw_score: candidate.raw_score });
+                }
+                Some(ScoredPoint { id: candidate.id, raw_score: score, sort_key: -score, sources })
+            })
+            .collect();
+        results.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap().then(a.id.cmp(&b.id)));
+        results.truncate(top_k);
+        Ok(results)
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        self.config.write(buf);
+        let mut ids: Vec<_> = self.vectors.keys().copied().collect();
+        ids.sort_unstable();
+        codec::put_u64(buf, ids.len() as u64);
+        for id in ids {
+            codec::put_u64(buf, id);
+            self.vectors[&id].write(buf);
+        }
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let mut storage = Self::new(MultiVectorConfig::read(dec)?);
+        for _ in 0..dec.length_prefix(16)? {
+            let id = dec.u64()?;
+            let vector = MultiVector::read(dec)?;
+            storage.check_dim(&vector).map_err(|e| DBError::SerializationError(e.into()))?;
+            storage.vectors.insert(id, vector);
+        }
+        Ok(storage)
+    }
+}
@@ -0,0 +1,118 @@
+//! Binary quantization: one bit per dimension, set when the component lies above its threshold.
+//! Codes are compared by Hamming distance with popcount, which only ranks candidates roughly;
+//! it is meant to be paired with oversampling and rescoring against the original vectors.
+use std::f32::consts::PI;
+
+use anyhow::anyhow;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::DistanceMetric;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BinaryQuantizationConfig {
+    /// Threshold every dimension at its mean over the training vectors instead of at zero. Helps
+    /// with embeddings that are not centered around the origin.
+    pub center: bool,
+}
+
+impl BinaryQuantizationConfig {
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u8(buf, self.center as u8);
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        Ok(Self { center: dec.bool()? })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryQuantizer {
+    config: BinaryQuantizationConfig,
+    metric: DistanceMetric,
+    thresholds: Vec<f32>,
+}
+
+/// The query's own code; scoring is a popcount against each stored code.
+pub struct BinaryQuery {
+    code: Vec<u8>,
+}
+
+impl BinaryQuantizer {
+    pub fn train(
+        config: BinaryQuantizationConfig,
+        metric: DistanceMetric,
+        dim: usize,
+        vectors: &[&[f32]],
+    ) -> Result<Self, DBError> {
+        let thresholds = if config.center {
+            let mut sums = vec![0.0f64; dim];
+            for v in vectors {
+                sums.iter_mut().zip(v.iter()).for_each(|(s, &x)| *s += x as f64);
+            }
+            sums.iter().map(|s| (s / vectors.len() as f64) as f32).collect()
+        } else {
+            vec![0.0; dim]
+        };
+        Ok(Self { config, metric, thresholds })
+    }
+
+    pub fn config(&self) -> BinaryQuantizationConfig {
+        self.config
+    }
+
+    /// Sign bits packed least significant bit first, padded with zeros to whole 8-byte words.
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        let mut code = vec![0u8; self.thresholds.len().div_ceil(64) * 8];
+        for (d, (&x, &t)) in vector.iter().zip(&self.thresholds).enumerate() {
+            if x > t {
+                code[d / 8] |= 1 << (d % 8);
+            }
+        }
+        code
+    }
+
+    pub fn prepare(&self, query: &[f32]) -> BinaryQuery {
+        BinaryQuery { code: self.encode(query) }
+    }
+
+    /// Number of differing bits, mapped onto the metric's orientation so that it ranks like an
+    /// internal score: Dot is negated, and Cosine becomes `1 - cos(π · h / dim)`, the angle
+    /// estimate random-hyperplane hashing gives.
+    pub fn score(&self, query: &BinaryQuery, code: &[u8]) -> f32 {
+        let hamming = hamming(&query.code, code) as f32;
+        match self.metric {
+            DistanceMetric::Dot => -hamming,
+            DistanceMetric::Cosine => 1.0 - (PI * hamming / self.thresholds.len() as f32).cos(),
+            _ => hamming,
+        }
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        self.config.write(buf);
+        codec::put_metric(buf, self.metric);
+        codec::put_vector(buf, &self.thresholds);
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let config = BinaryQuantizationConfig::read(dec)?;
+        let metric = dec.metric()?;
+        let thresholds = dec.vector()?;
+        if thresholds.is_empty() {
+            return Err(DBError::SerializationError(anyhow!("binary quantizer has no thresholds")));
+        }
+        Ok(Self { config, metric, thresholds })
+    }
+}
+
+/// Popcount of `a XOR b`, a word at a time.
+pub fn hamming(a: &[u8], b: &[u8]) -> u32 {
+    a.chunks_exact(8)
+        .zip(b.chunks_exact(8))
+        .map(|(x, y)| {
+            let x = u64::from_le_bytes(x.try_into().unwrap());
+            let y = u64::from_le_bytes(y.try_into().unwrap());
+            (x ^ y).count_ones()
+        })
+        .sum()
+}
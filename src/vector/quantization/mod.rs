@@ -0,0 +1,189 @@
+//! Compressed copies of the stored vectors. Graph traversal scores queries against the compact
+//! codes; the original f32 vectors stay in `HNSWIndex::vectors` for optional rescoring.
+pub mod binary;
+pub mod product;
+pub mod scalar;
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{DistanceMetric, PointId};
+use crate::vector::quantization::binary::{BinaryQuantizationConfig, BinaryQuantizer, BinaryQuery};
+use crate::vector::quantization::product::{ProductQuantizationConfig, ProductQuantizer, ProductQuery};
+use crate::vector::quantization::scalar::{ScalarQuantizationConfig, ScalarQuantizer, ScalarQuery};
+
+/// Which codec to train, and its options.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuantizationConfig {
+    Scalar(ScalarQuantizationConfig),
+    Product(ProductQuantizationConfig),
+    Binary(BinaryQuantizationConfig),
+}
+
+/// Per-query control over how a quantized index is searched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantizationSearchParams {
+    /// Traverse the graph with the original vectors, as if the index were not quantized.
+    pub ignore: bool,
+    /// Re-rank the candidates with the original vectors before returning them.
+    pub rescore: bool,
+    /// Rescore `ceil(top_k * oversampling)` candidates instead of just `top_k`. Values below 1
+    /// are treated as 1.
+    pub oversampling: f32,
+}
+
+impl Default for QuantizationSearchParams {
+    fn default() -> Self {
+        Self { ignore: false, rescore: true, oversampling: 1.0 }
+    }
+}
+
+impl QuantizationSearchParams {
+    /// Number of candidates to collect so that `top_k` survive rescoring.
+    pub fn candidates(&self, top_k: usize) -> usize {
+        if !self.rescore {
+            return top_k;
+        }
+        (top_k as f32 * self.oversampling.max(1.0)).ceil() as usize
+    }
+}
+
+/// A trained codec.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quantizer {
+    Scalar(ScalarQuantizer),
+    Product(ProductQuantizer),
+    Binary(BinaryQuantizer),
+}
+
+/// A query preprocessed once per search so that scoring each code is cheap.
+pub enum PreparedQuery {
+    Scalar(ScalarQuery),
+    Product(ProductQuery),
+    Binary(BinaryQuery),
+}
+
+impl Quantizer {
+    /// Fit a codec of the given kind to `vectors`, which must already be normalized for Cosine.
+    pub fn train(
+        config: &QuantizationConfig,
+        metric: DistanceMetric,
+        dim: usize,
+        vectors: &[&[f32]],
+    ) -> Result<Self, DBError> {
+        if vectors.is_empty() {
+            return Err(DBError::QuantizationError("cannot train a quantizer without vectors".into()));
+        }
+        match config {
+            QuantizationConfig::Scalar(c) => Ok(Quantizer::Scalar(ScalarQuantizer::train(*c, metric, dim, vectors)?)),
+            QuantizationConfig::Product(c) => Ok(Quantizer::Product(ProductQuantizer::train(*c, metric, dim, vectors)?)),
+            QuantizationConfig::Binary(c) => Ok(Quantizer::Binary(BinaryQuantizer::train(*c, metric, dim, vectors)?)),
+        }
+    }
+
+    pub fn config(&self) -> QuantizationConfig {
+        match self {
+            Quantizer::Scalar(q) => QuantizationConfig::Scalar(q.config()),
+            Quantizer::Product(q) => QuantizationConfig::Product(q.config()),
+            Quantizer::Binary(q) => QuantizationConfig::Binary(q.config()),
+        }
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            Quantizer::Scalar(q) => q.encode(vector),
+            Quantizer::Product(q) => q.encode(vector),
+            Quantizer::Binary(q) => q.encode(vector),
+        }
+    }
+
+    pub fn prepare(&self, query: &[f32]) -> PreparedQuery {
+        match self {
+            Quantizer::Scalar(q) => PreparedQuery::Scalar(q.prepare(query)),
+            Quantizer::Product(q) => PreparedQuery::Product(q.prepare(query)),
+            Quantizer::Binary(q) => PreparedQuery::Binary(q.prepare(query)),
+        }
+    }
+
+    /// Approximate internal score (see `metric::internal_score`) between a query and a code.
+    pub fn score(&self, query: &PreparedQuery, code: &[u8]) -> f32 {
+        match (self, query) {
+            (Quantizer::Scalar(q), PreparedQuery::Scalar(query)) => q.score(query, code),
+            (Quantizer::Product(q), PreparedQuery::Product(query)) => q.score(query, code),
+            (Quantizer::Binary(q), PreparedQuery::Binary(query)) => q.score(query, code),
+            _ => panic!("query was prepared by a different quantizer"),
+        }
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        match self {
+            Quantizer::Scalar(q) => {
+                codec::put_u8(buf, 0);
+                q.write_snapshot(buf);
+            }
+            Quantizer::Product(q) => {
+                codec::put_u8(buf, 1);
+                q.write_snapshot(buf);
+            }
+            Quantizer::Binary(q) => {
+                codec::put_u8(buf, 2);
+                q.write_snapshot(buf);
+            }
+        }
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        match dec.u8()? {
+            0 => Ok(Quantizer::Scalar(ScalarQuantizer::read_snapshot(dec)?)),
+            1 => Ok(Quantizer::Product(ProductQuantizer::read_snapshot(dec)?)),
+            2 => Ok(Quantizer::Binary(BinaryQuantizer::read_snapshot(dec)?)),
+            other => Err(DBError::SerializationError(anyhow!("unknown quantizer tag {}", other))),
+        }
+    }
+}
+
+/// A trained quantizer plus the code of every stored point.
+#[derive(Debug, Clone)]
+pub struct QuantizedVectors {
+    quantizer: Quantizer,
+    codes: HashMap<PointId, Vec<u8>>,
+}
+
+impl QuantizedVectors {
+    pub fn new(quantizer: Quantizer) -> Self {
+        Self { quantizer, codes: HashMap::new() }
+    }
+
+    pub fn quantizer(&self) -> &Quantizer {
+        &self.quantizer
+    }
+
+    pub fn insert(&mut self, point_id: PointId, vector: &[f32]) {
+        self.codes.insert(point_id, self.quantizer.encode(vector));
+    }
+
+    pub fn get(&self, point_id: &PointId) -> Option<&[u8]> {
+        self.codes.get(point_id).map(|c| c.as_slice())
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Bytes held by the codes themselves.
+    pub fn code_bytes(&self) -> usize {
+        self.codes.values().map(|c| c.len()).sum()
+    }
+
+    /// Same quantizer, no codes.
+    pub fn empty_like(&self) -> Self {
+        Self::new(self.quantizer.clone())
+    }
+}
@@ -0,0 +1,279 @@
+//! Product quantization: the dimensions are split into contiguous subspaces, each subspace gets
+//! its own k-means codebook of `2^bits` centroids, and a vector is stored as the index of the
+//! nearest centroid in every subspace. Queries are scored by asymmetric distance computation
+//! (ADC): the query is compared against every centroid once, then each code is a sum of lookups.
+use anyhow::anyhow;
+use rand::Rng;
+use rand::seq::IteratorRandom;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{DistanceMetric, Vector};
+use crate::vector::metric::{internal_score, kernels};
+
+/// At most this many training vectors per centroid are used for k-means, as in most PQ
+/// implementations; more barely moves the centroids and only slows training down.
+const TRAINING_POINTS_PER_CENTROID: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProductQuantizationConfig {
+    /// Number of subspaces, and so of centroid indices per code. At most `dim`; subspaces differ
+    /// in width by at most one dimension when it does not divide `dim`.
+    pub subspaces: usize,
+    /// Bits per centroid index, 1 to 8. Every subspace gets `2^bits` centroids.
+    pub bits: u8,
+    /// Lloyd iterations when training each codebook.
+    pub iterations: usize,
+}
+
+impl ProductQuantizationConfig {
+    /// `subspaces` codebooks of 256 centroids each, i.e. one byte per subspace.
+    pub fn new(subspaces: usize) -> Self {
+        Self { subspaces, bits: 8, iterations: 25 }
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u64(buf, self.subspaces as u64);
+        codec::put_u8(buf, self.bits);
+        codec::put_u64(buf, self.iterations as u64);
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        let subspaces = dec.u64()? as usize;
+        let bits = dec.u8()?;
+        let iterations = dec.u64()? as usize;
+        Ok(Self { subspaces, bits, iterations })
+    }
+
+    fn centroids(&self) -> usize {
+        1 << self.bits
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductQuantizer {
+    config: ProductQuantizationConfig,
+    metric: DistanceMetric,
+    // Start of each subspace, plus `dim` at the end.
+    bounds: Vec<usize>,
+    // One codebook per subspace: `centroids()` centroids, flattened.
+    codebooks: Vec<Vec<f32>>,
+}
+
+/// Per-query ADC table: the score of every centroid of every subspace against the query.
+pub struct ProductQuery {
+    table: Vec<f32>,
+}
+
+impl ProductQuantizer {
+    pub fn train(
+        config: ProductQuantizationConfig,
+        metric: DistanceMetric,
+        dim: usize,
+        vectors: &[&[f32]],
+    ) -> Result<Self, DBError> {
+        if config.subspaces == 0 || config.subspaces > dim {
+            return Err(DBError::QuantizationError(format!(
+                "subspaces must be between 1 and {}, got {}",
+                dim, config.subspaces
+            )));
+        }
+        if !(1..=8).contains(&config.bits) {
+            return Err(DBError::QuantizationError(format!("bits must be between 1 and 8, got {}", config.bits)));
+        }
+        if metric == DistanceMetric::Jaccard {
+            return Err(DBError::QuantizationError("Jaccard does not decompose over subspaces".into()));
+        }
+
+        let bounds = subspace_bounds(dim, config.subspaces);
+        let k = config.centroids();
+        let mut rng = rand::rng();
+        let sample: Vec<&[f32]> = if vectors.len() > k * TRAINING_POINTS_PER_CENTROID {
+            vectors.iter().copied().choose_multiple(&mut rng, k * TRAINING_POINTS_PER_CENTROID)
+        } else {
+            vectors.to_vec()
+        };
+
+        let codebooks = bounds
+            .windows(2)
+            .map(|w| {
+                let points: Vec<&[f32]> = sample.iter().map(|v| &v[w[0]..w[1]]).collect();
+                kmeans(&points, k, config.iterations, &mut rng)
+            })
+            .collect();
+        Ok(Self { config, metric, bounds, codebooks })
+    }
+
+    pub fn config(&self) -> ProductQuantizationConfig {
+        self.config
+    }
+
+    fn subspace(&self, s: usize) -> std::ops::Range<usize> {
+        self.bounds[s]..self.bounds[s + 1]
+    }
+
+    fn centroid(&self, s: usize, c: usize) -> &[f32] {
+        let width = self.bounds[s + 1] - self.bounds[s];
+        &self.codebooks[s][c * width..(c + 1) * width]
+    }
+
+    /// Centroid indices, `bits` each, packed least significant bit first.
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        let bits = self.config.bits as usize;
+        let mut code = vec![0u8; (self.config.subspaces * bits).div_ceil(8)];
+        for s in 0..self.config.subspaces {
+            let c = nearest(&vector[self.subspace(s)], &self.codebooks[s]);
+            let bit = s * bits;
+            let packed = (c as u16) << (bit % 8);
+            code[bit / 8] |= packed as u8;
+            if bit % 8 + bits > 8 {
+                code[bit / 8 + 1] |= (packed >> 8) as u8;
+            }
+        }
+        code
+    }
+
+    fn code_at(&self, code: &[u8], s: usize) -> usize {
+        let bits = self.config.bits as usize;
+        let bit = s * bits;
+        let mut value = (code[bit / 8] as u16) >> (bit % 8);
+        if bit % 8 + bits > 8 {
+            value |= (code[bit / 8 + 1] as u16) << (8 - bit % 8);
+        }
+        (value & ((1 << bits) - 1)) as usize
+    }
+
+    pub fn decode(&self, code: &[u8]) -> Vector {
+        (0..self.config.subspaces).flat_map(|s| self.centroid(s, self.code_at(code, s)).to_vec()).collect()
+    }
+
+    pub fn prepare(&self, query: &[f32]) -> ProductQuery {
+        let k = self.config.centroids();
+        let mut table = Vec::with_capacity(self.config.subspaces * k);
+        for s in 0..self.config.subspaces {
+            let q = &query[self.subspace(s)];
+            for c in 0..k {
+                let centroid = self.centroid(s, c);
+                table.push(match self.metric {
+                    // Cosine vectors are normalized, so the parts sum to the full dot product.
+                    DistanceMetric::Dot | DistanceMetric::Cosine => kernels().dot(q, centroid),
+                    other => internal_score(q, centroid, other),
+                });
+            }
+        }
+        ProductQuery { table }
+    }
+
+    pub fn score(&self, query: &ProductQuery, code: &[u8]) -> f32 {
+        let k = self.config.centroids();
+        let parts = (0..self.config.subspaces).map(|s| query.table[s * k + self.code_at(code, s)]);
+        match self.metric {
+            DistanceMetric::Cosine => 1.0 - parts.sum::<f32>(),
+            DistanceMetric::Chebyshev => parts.fold(0.0, f32::max),
+            _ => parts.sum(),
+        }
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        self.config.write(buf);
+        codec::put_metric(buf, self.metric);
+        codec::put_u64(buf, *self.bounds.last().unwrap() as u64);
+        for codebook in &self.codebooks {
+            codec::put_vector(buf, codebook);
+        }
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let config = ProductQuantizationConfig::read(dec)?;
+        let metric = dec.metric()?;
+        let dim = dec.u64()? as usize;
+        if config.subspaces == 0 || config.subspaces > dim || !(1..=8).contains(&config.bits) {
+            return Err(DBError::SerializationError(anyhow!("invalid product quantizer config {:?}", config)));
+        }
+        let bounds = subspace_bounds(dim, config.subspaces);
+        let mut codebooks = Vec::with_capacity(config.subspaces);
+        for w in bounds.windows(2) {
+            let codebook = dec.vector()?;
+            if codebook.len() != (w[1] - w[0]) * config.centroids() {
+                return Err(DBError::SerializationError(anyhow!("product quantizer codebook has the wrong size")));
+            }
+            codebooks.push(codebook);
+        }
+        Ok(Self { config, metric, bounds, codebooks })
+    }
+}
+
+/// Split `dim` into `subspaces` contiguous ranges whose widths differ by at most one.
+fn subspace_bounds(dim: usize, subspaces: usize) -> Vec<usize> {
+    (0..=subspaces).map(|s| s * dim / subspaces).collect()
+}
+
+/// Index of the centroid in the flattened `codebook` closest to `point` in L2.
+fn nearest(point: &[f32], codebook: &[f32]) -> usize {
+    let width = point.len();
+    codebook
+        .chunks_exact(width)
+        .map(|c| kernels().l2_squared(point, c))
+        .enumerate()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map_or(0, |(i, _)| i)
+}
+
+/// Lloyd's k-means with k-means++ seeding. Returns `k` centroids, flattened. Clusters that end
+/// up empty are re-seeded with a random point.
+fn kmeans(points: &[&[f32]], k: usize, iterations: usize, rng: &mut impl Rng) -> Vec<f32> {
+    let width = points[0].len();
+    let mut centroids: Vec<f32> = Vec::with_capacity(k * width);
+    centroids.extend_from_slice(points[rng.random_range(0..points.len())]);
+    let mut closest: Vec<f32> = points.iter().map(|p| kernels().l2_squared(p, &centroids[..width])).collect();
+    for _ in 1..k {
+        let total: f32 = closest.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = rng.random_range(0.0..total);
+            closest.iter().position(|&d| {
+                target -= d;
+                target < 0.0
+            })
+            .unwrap_or(points.len() - 1)
+        } else {
+            // Fewer distinct points than centroids: duplicates are harmless.
+            rng.random_range(0..points.len())
+        };
+        let start = centroids.len();
+        centroids.extend_from_slice(points[next]);
+        for (d, p) in closest.iter_mut().zip(points) {
+            *d = d.min(kernels().l2_squared(p, &centroids[start..]));
+        }
+    }
+
+    let mut assignment = vec![0usize; points.len()];
+    for _ in 0..iterations {
+        let mut changed = false;
+        for (a, p) in assignment.iter_mut().zip(points) {
+            let c = nearest(p, &centroids);
+            changed |= *a != c;
+            *a = c;
+        }
+
+        let mut sums = vec![0.0f32; k * width];
+        let mut counts = vec![0usize; k];
+        for (&c, p) in assignment.iter().zip(points) {
+            counts[c] += 1;
+            sums[c * width..(c + 1) * width].iter_mut().zip(p.iter()).for_each(|(s, x)| *s += x);
+        }
+        for c in 0..k {
+            let centroid = &mut centroids[c * width..(c + 1) * width];
+            if counts[c] == 0 {
+                centroid.copy_from_slice(points[rng.random_range(0..points.len())]);
+            } else {
+                let sum = &sums[c * width..(c + 1) * width];
+                centroid.iter_mut().zip(sum).for_each(|(x, s)| *x = s / counts[c] as f32);
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+    centroids
+}
@@ -0,0 +1,180 @@
+//! Scalar (int8) quantization: every component is mapped linearly onto 256 levels between a
+//! calibrated lower and upper bound, one byte per dimension.
+use anyhow::anyhow;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{DistanceMetric, Vector};
+use crate::vector::metric::internal_score;
+
+const LEVELS: f32 = 255.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalarQuantizationConfig {
+    /// Calibrate bounds separately for every dimension rather than once for all of them.
+    pub per_dimension: bool,
+    /// Fraction of values the bounds must cover, e.g. `0.99` clips the lowest and highest 0.5%
+    /// as outliers. `None` uses the plain minimum and maximum.
+    pub quantile: Option<f32>,
+}
+
+impl Default for ScalarQuantizationConfig {
+    fn default() -> Self {
+        Self { per_dimension: true, quantile: None }
+    }
+}
+
+impl ScalarQuantizationConfig {
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u8(buf, self.per_dimension as u8);
+        match self.quantile {
+            Some(q) => {
+                codec::put_u8(buf, 1);
+                codec::put_f32(buf, q);
+            }
+            None => codec::put_u8(buf, 0),
+        }
+    }
+
+    pub(crate) fn read(dec: &mut Decoder) -> Result<Self, DBError> {
+        let per_dimension = dec.bool()?;
+        let quantile = if dec.bool()? { Some(dec.f32()?) } else { None };
+        Ok(Self { per_dimension, quantile })
+    }
+}
+
+/// Value of component `d` for code `c` is `offsets[d] + scales[d] * c`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarQuantizer {
+    config: ScalarQuantizationConfig,
+    metric: DistanceMetric,
+    offsets: Vec<f32>,
+    scales: Vec<f32>,
+}
+
+/// Query folded into the quantizer's affine map, so scoring is a single pass over the code.
+pub struct ScalarQuery {
+    // Dot/Cosine: `query * scale`; Euclidean: `query - offset`.
+    values: Vec<f32>,
+    // Dot/Cosine: `query · offset`.
+    bias: f32,
+    // Kept for metrics without a folded form.
+    query: Vector,
+}
+
+impl ScalarQuantizer {
+    pub fn train(
+        config: ScalarQuantizationConfig,
+        metric: DistanceMetric,
+        dim: usize,
+        vectors: &[&[f32]],
+    ) -> Result<Self, DBError> {
+        if let Some(q) = config.quantile
+            && !(q > 0.0 && q <= 1.0)
+        {
+            return Err(DBError::QuantizationError(format!("quantile must be in (0, 1], got {}", q)));
+        }
+
+        let bounds: Vec<(f32, f32)> = if config.per_dimension {
+            (0..dim).map(|d| bounds(vectors.iter().map(|v| v[d]).collect(), config.quantile)).collect()
+        } else {
+            let all = bounds(vectors.iter().flat_map(|v| v.iter().copied()).collect(), config.quantile);
+            vec![all; dim]
+        };
+
+        let offsets = bounds.iter().map(|&(lo, _)| lo).collect();
+        let scales = bounds.iter().map(|&(lo, hi)| (hi - lo).max(0.0) / LEVELS).collect();
+        Ok(Self { config, metric, offsets, scales })
+    }
+
+    pub fn config(&self) -> ScalarQuantizationConfig {
+        self.config
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        vector
+            .iter()
+            .zip(self.offsets.iter().zip(&self.scales))
+            .map(|(&x, (&offset, &scale))| {
+                if scale == 0.0 { 0 } else { ((x - offset) / scale).round().clamp(0.0, LEVELS) as u8 }
+            })
+            .collect()
+    }
+
+    pub fn decode(&self, code: &[u8]) -> Vector {
+        code.iter()
+            .zip(self.offsets.iter().zip(&self.scales))
+            .map(|(&c, (&offset, &scale))| offset + scale * c as f32)
+            .collect()
+    }
+
+    pub fn prepare(&self, query: &[f32]) -> ScalarQuery {
+        let (values, bias) = match self.metric {
+            DistanceMetric::Dot | DistanceMetric::Cosine => (
+                query.iter().zip(&self.scales).map(|(q, s)| q * s).collect(),
+                query.iter().zip(&self.offsets).map(|(q, o)| q * o).sum(),
+            ),
+            DistanceMetric::Euclidean => (query.iter().zip(&self.offsets).map(|(q, o)| q - o).collect(), 0.0),
+            _ => (Vec::new(), 0.0),
+        };
+        ScalarQuery { values, bias, query: query.to_vec() }
+    }
+
+    pub fn score(&self, query: &ScalarQuery, code: &[u8]) -> f32 {
+        match self.metric {
+            DistanceMetric::Dot => query.bias + dot_code(&query.values, code),
+            DistanceMetric::Cosine => 1.0 - (query.bias + dot_code(&query.values, code)),
+            DistanceMetric::Euclidean => query
+                .values
+                .iter()
+                .zip(code.iter().zip(&self.scales))
+                .map(|(r, (&c, s))| {
+                    let d = r - s * c as f32;
+                    d * d
+                })
+                .sum(),
+            other => internal_score(&query.query, &self.decode(code), other),
+        }
+    }
+
+    pub(crate) fn write_snapshot(&self, buf: &mut Vec<u8>) {
+        self.config.write(buf);
+        codec::put_metric(buf, self.metric);
+        codec::put_vector(buf, &self.offsets);
+        codec::put_vector(buf, &self.scales);
+    }
+
+    pub(crate) fn read_snapshot(dec: &mut Decoder) -> Result<Self, DBError> {
+        let config = ScalarQuantizationConfig::read(dec)?;
+        let metric = dec.metric()?;
+        let offsets = dec.vector()?;
+        let scales = dec.vector()?;
+        if offsets.len() != scales.len() {
+            return Err(DBError::SerializationError(anyhow!("scalar quantizer offsets and scales differ in length")));
+        }
+        Ok(Self { config, metric, offsets, scales })
+    }
+}
+
+fn dot_code(values: &[f32], code: &[u8]) -> f32 {
+    values.iter().zip(code).map(|(v, &c)| v * c as f32).sum()
+}
+
+/// Lower and upper bound covering `quantile` of `values` (all of them when `None`).
+fn bounds(mut values: Vec<f32>, quantile: Option<f32>) -> (f32, f32) {
+    values.retain(|x| x.is_finite());
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    match quantile {
+        None => values.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x))),
+        Some(q) => {
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let last = (values.len() - 1) as f32;
+            let tail = (1.0 - q) / 2.0;
+            let lo = (tail * last).floor() as usize;
+            let hi = ((1.0 - tail) * last).ceil() as usize;
+            (values[lo], values[hi])
+        }
+    }
+}
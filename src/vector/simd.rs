@@ -0,0 +1,307 @@
+//! Distance kernels for each supported instruction set, plus the scalar fallback.
+//!
+//! Every x86 kernel is an `unsafe fn` compiled with `#[target_feature]`; callers must only invoke
+//! it after runtime detection confirmed the feature. `metric::Kernels` does that once per process.
+//!
+//! The `_f16`, `_bf16` and `_u8` kernels score an f32 query against a stored vector of that element
+//! type, widening one register at a time rather than converting the whole vector first.
+use half::{bf16, f16};
+
+/// Sum of squares, and the dot product, computed in a single pass: `(a·b, a·a, b·b)`.
+pub type CosineParts = (f32, f32, f32);
+
+/// A stored element type that widens losslessly to f32.
+pub trait Element: Copy {
+    fn to_f32(self) -> f32;
+}
+
+impl Element for f16 {
+    fn to_f32(self) -> f32 {
+        f16::to_f32(self)
+    }
+}
+
+impl Element for bf16 {
+    fn to_f32(self) -> f32 {
+        bf16::to_f32(self)
+    }
+}
+
+impl Element for u8 {
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+pub mod scalar {
+    use super::{CosineParts, Element};
+
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    pub fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+    }
+
+    pub fn cosine_parts(a: &[f32], b: &[f32]) -> CosineParts {
+        a.iter().zip(b).fold((0.0, 0.0, 0.0), |(ab, aa, bb), (x, y)| (ab + x * y, aa + x * x, bb + y * y))
+    }
+
+    pub fn dot_mixed<T: Element>(a: &[f32], b: &[T]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y.to_f32()).sum()
+    }
+
+    pub fn l2_squared_mixed<T: Element>(a: &[f32], b: &[T]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| (x - y.to_f32()) * (x - y.to_f32())).sum()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod sse {
+    use std::arch::x86_64::*;
+
+    use super::{CosineParts, scalar};
+
+    #[inline]
+    #[target_feature(enable = "sse")]
+    unsafe fn hsum(v: __m128) -> f32 {
+        let hi = _mm_movehl_ps(v, v);
+        let sum = _mm_add_ps(v, hi);
+        let odd = _mm_shuffle_ps(sum, sum, 0b01);
+        _mm_cvtss_f32(_mm_add_ss(sum, odd))
+    }
+
+    #[target_feature(enable = "sse")]
+    pub unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 4;
+        let mut acc = _mm_setzero_ps();
+        for i in (0..split).step_by(4) {
+            // Safety: `i + 4 <= split <= len` for both slices.
+            let (x, y) = unsafe { (_mm_loadu_ps(a.as_ptr().add(i)), _mm_loadu_ps(b.as_ptr().add(i))) };
+            acc = _mm_add_ps(acc, _mm_mul_ps(x, y));
+        }
+        unsafe { hsum(acc) + scalar::dot(&a[split..], &b[split..]) }
+    }
+
+    #[target_feature(enable = "sse")]
+    pub unsafe fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 4;
+        let mut acc = _mm_setzero_ps();
+        for i in (0..split).step_by(4) {
+            let (x, y) = unsafe { (_mm_loadu_ps(a.as_ptr().add(i)), _mm_loadu_ps(b.as_ptr().add(i))) };
+            let d = _mm_sub_ps(x, y);
+            acc = _mm_add_ps(acc, _mm_mul_ps(d, d));
+        }
+        unsafe { hsum(acc) + scalar::l2_squared(&a[split..], &b[split..]) }
+    }
+
+    #[target_feature(enable = "sse")]
+    pub unsafe fn cosine_parts(a: &[f32], b: &[f32]) -> CosineParts {
+        let split = a.len() - a.len() % 4;
+        let (mut ab, mut aa, mut bb) = (_mm_setzero_ps(), _mm_setzero_ps(), _mm_setzero_ps());
+        for i in (0..split).step_by(4) {
+            let (x, y) = unsafe { (_mm_loadu_ps(a.as_ptr().add(i)), _mm_loadu_ps(b.as_ptr().add(i))) };
+            ab = _mm_add_ps(ab, _mm_mul_ps(x, y));
+            aa = _mm_add_ps(aa, _mm_mul_ps(x, x));
+            bb = _mm_add_ps(bb, _mm_mul_ps(y, y));
+        }
+        let (t_ab, t_aa, t_bb) = scalar::cosine_parts(&a[split..], &b[split..]);
+        unsafe { (hsum(ab) + t_ab, hsum(aa) + t_aa, hsum(bb) + t_bb) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod avx2 {
+    use std::arch::x86_64::*;
+
+    use super::{CosineParts, scalar};
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn hsum(v: __m256) -> f32 {
+        let sum = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps(v, 1));
+        let hi = _mm_movehl_ps(sum, sum);
+        let sum = _mm_add_ps(sum, hi);
+        let odd = _mm_shuffle_ps(sum, sum, 0b01);
+        _mm_cvtss_f32(_mm_add_ss(sum, odd))
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 8;
+        let mut acc = _mm256_setzero_ps();
+        for i in (0..split).step_by(8) {
+            // Safety: `i + 8 <= split <= len` for both slices.
+            let (x, y) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i))) };
+            acc = _mm256_fmadd_ps(x, y, acc);
+        }
+        unsafe { hsum(acc) + scalar::dot(&a[split..], &b[split..]) }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 8;
+        let mut acc = _mm256_setzero_ps();
+        for i in (0..split).step_by(8) {
+            let (x, y) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i))) };
+            let d = _mm256_sub_ps(x, y);
+            acc = _mm256_fmadd_ps(d, d, acc);
+        }
+        unsafe { hsum(acc) + scalar::l2_squared(&a[split..], &b[split..]) }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn cosine_parts(a: &[f32], b: &[f32]) -> CosineParts {
+        let split = a.len() - a.len() % 8;
+        let (mut ab, mut aa, mut bb) = (_mm256_setzero_ps(), _mm256_setzero_ps(), _mm256_setzero_ps());
+        for i in (0..split).step_by(8) {
+            let (x, y) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i))) };
+            ab = _mm256_fmadd_ps(x, y, ab);
+            aa = _mm256_fmadd_ps(x, x, aa);
+            bb = _mm256_fmadd_ps(y, y, bb);
+        }
+        let (t_ab, t_aa, t_bb) = scalar::cosine_parts(&a[split..], &b[split..]);
+        unsafe { (hsum(ab) + t_ab, hsum(aa) + t_aa, hsum(bb) + t_bb) }
+    }
+}
+
+/// AVX2 kernels for the narrow element types. Half precision needs F16C on top of AVX2.
+#[cfg(target_arch = "x86_64")]
+pub mod avx2_mixed {
+    use std::arch::x86_64::*;
+
+    use half::{bf16, f16};
+
+    use super::scalar;
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn hsum(v: __m256) -> f32 {
+        let sum = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps(v, 1));
+        let hi = _mm_movehl_ps(sum, sum);
+        let sum = _mm_add_ps(sum, hi);
+        let odd = _mm_shuffle_ps(sum, sum, 0b01);
+        _mm_cvtss_f32(_mm_add_ss(sum, odd))
+    }
+
+    // Widen 8 stored elements starting at `p` to f32 lanes.
+    #[inline]
+    #[target_feature(enable = "avx2,fma,f16c")]
+    unsafe fn load_f16(p: *const f16) -> __m256 {
+        unsafe { _mm256_cvtph_ps(_mm_loadu_si128(p as *const __m128i)) }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma,f16c")]
+    unsafe fn load_bf16(p: *const bf16) -> __m256 {
+        let wide = unsafe { _mm256_cvtepu16_epi32(_mm_loadu_si128(p as *const __m128i)) };
+        _mm256_castsi256_ps(_mm256_slli_epi32(wide, 16))
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2,fma,f16c")]
+    unsafe fn load_u8(p: *const u8) -> __m256 {
+        unsafe { _mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(_mm_loadl_epi64(p as *const __m128i))) }
+    }
+
+    macro_rules! mixed_kernels {
+        ($elem:ty, $load:ident, $dot:ident, $l2:ident) => {
+            #[target_feature(enable = "avx2,fma,f16c")]
+            pub unsafe fn $dot(a: &[f32], b: &[$elem]) -> f32 {
+                let split = a.len() - a.len() % 8;
+                let mut acc = _mm256_setzero_ps();
+                for i in (0..split).step_by(8) {
+                    // Safety: `i + 8 <= split <= len` for both slices.
+                    let (x, y) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i)), $load(b.as_ptr().add(i))) };
+                    acc = _mm256_fmadd_ps(x, y, acc);
+                }
+                unsafe { hsum(acc) + scalar::dot_mixed(&a[split..], &b[split..]) }
+            }
+
+            #[target_feature(enable = "avx2,fma,f16c")]
+            pub unsafe fn $l2(a: &[f32], b: &[$elem]) -> f32 {
+                let split = a.len() - a.len() % 8;
+                let mut acc = _mm256_setzero_ps();
+                for i in (0..split).step_by(8) {
+                    let (x, y) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i)), $load(b.as_ptr().add(i))) };
+                    let d = _mm256_sub_ps(x, y);
+                    acc = _mm256_fmadd_ps(d, d, acc);
+                }
+                unsafe { hsum(acc) + scalar::l2_squared_mixed(&a[split..], &b[split..]) }
+            }
+        };
+    }
+
+    mixed_kernels!(f16, load_f16, dot_f16, l2_squared_f16);
+    mixed_kernels!(bf16, load_bf16, dot_bf16, l2_squared_bf16);
+    mixed_kernels!(u8, load_u8, dot_u8, l2_squared_u8);
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod avx512 {
+    use std::arch::x86_64::*;
+
+    use super::CosineParts;
+
+    /// Mask selecting the first `len % 16` lanes, used to load the tail without reading past it.
+    #[inline]
+    fn tail_mask(len: usize) -> __mmask16 {
+        ((1u32 << (len % 16)) - 1) as __mmask16
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 16;
+        let mut acc = _mm512_setzero_ps();
+        for i in (0..split).step_by(16) {
+            // Safety: `i + 16 <= split <= len` for both slices.
+            let (x, y) = unsafe { (_mm512_loadu_ps(a.as_ptr().add(i)), _mm512_loadu_ps(b.as_ptr().add(i))) };
+            acc = _mm512_fmadd_ps(x, y, acc);
+        }
+        let mask = tail_mask(a.len());
+        // Safety: the mask only enables lanes that lie inside both slices.
+        let (x, y) = unsafe {
+            (_mm512_maskz_loadu_ps(mask, a.as_ptr().add(split)), _mm512_maskz_loadu_ps(mask, b.as_ptr().add(split)))
+        };
+        _mm512_reduce_add_ps(_mm512_fmadd_ps(x, y, acc))
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 16;
+        let mut acc = _mm512_setzero_ps();
+        for i in (0..split).step_by(16) {
+            let (x, y) = unsafe { (_mm512_loadu_ps(a.as_ptr().add(i)), _mm512_loadu_ps(b.as_ptr().add(i))) };
+            let d = _mm512_sub_ps(x, y);
+            acc = _mm512_fmadd_ps(d, d, acc);
+        }
+        let mask = tail_mask(a.len());
+        let (x, y) = unsafe {
+            (_mm512_maskz_loadu_ps(mask, a.as_ptr().add(split)), _mm512_maskz_loadu_ps(mask, b.as_ptr().add(split)))
+        };
+        let d = _mm512_sub_ps(x, y);
+        _mm512_reduce_add_ps(_mm512_fmadd_ps(d, d, acc))
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn cosine_parts(a: &[f32], b: &[f32]) -> CosineParts {
+        let split = a.len() - a.len() % 16;
+        let (mut ab, mut aa, mut bb) = (_mm512_setzero_ps(), _mm512_setzero_ps(), _mm512_setzero_ps());
+        for i in (0..split).step_by(16) {
+            let (x, y) = unsafe { (_mm512_loadu_ps(a.as_ptr().add(i)), _mm512_loadu_ps(b.as_ptr().add(i))) };
+            ab = _mm512_fmadd_ps(x, y, ab);
+            aa = _mm512_fmadd_ps(x, x, aa);
+            bb = _mm512_fmadd_ps(y, y, bb);
+        }
+        let mask = tail_mask(a.len());
+        let (x, y) = unsafe {
+            (_mm512_maskz_loadu_ps(mask, a.as_ptr().add(split)), _mm512_maskz_loadu_ps(mask, b.as_ptr().add(split)))
+        };
+        (
+            _mm512_reduce_add_ps(_mm512_fmadd_ps(x, y, ab)),
+            _mm512_reduce_add_ps(_mm512_fmadd_ps(x, x, aa)),
+            _mm512_reduce_add_ps(_mm512_fmadd_ps(y, y, bb)),
+        )
+    }
+}
@@ -0,0 +1,275 @@
+//! Sparse vectors and an inverted index answering exact dot-product top-k queries over them.
+//!
+//! Each dimension has a posting list of `(point, weight)` sorted by point ID. Queries walk the
+//! lists document-at-a-time with MaxScore pruning: lists are ordered by the most they can add to a
+//! score, and once the current top-k threshold is out of reach of the weakest lists combined, those
+//! lists are only probed for documents the stronger lists already produced.
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::utils::errors::DBError;
+use crate::utils::types::PointId;
+use crate::vector::hnsw::ScoredPoint;
+
+/// A sparse vector: strictly increasing dimension `indices`, with one value each.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    indices: Vec<u32>,
+    values: Vec<f32>,
+}
+
+impl SparseVector {
+    /// Build a vector from parallel `indices` and `values`, in any order. Fails on a length mismatch,
+    /// a repeated index or a non-finite value.
+    pub fn new(indices: Vec<u32>, values: Vec<f32>) -> Result<Self, DBError> {
+        if indices.len() != values.len() {
+            return Err(DBError::VectorLengthMismatch { expected: indices.len(), actual: values.len() });
+        }
+        if let Some(v) = values.iter().find(|v| !v.is_finite()) {
+            return Err(DBError::InvalidConfig(format!("sparse vector value {} is not finite", v)));
+        }
+        let mut pairs: Vec<(u32, f32)> = indices.into_iter().zip(values).collect();
+        pairs.sort_by_key(|&(i, _)| i);
+        if let Some(w) = pairs.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(DBError::InvalidConfig(format!("sparse vector repeats index {}", w[0].0)));
+        }
+        let (indices, values) = pairs.into_iter().unzip();
+        Ok(Self { indices, values })
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Number of stored (non-implicit) entries.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.indices.iter().copied().zip(self.values.iter().copied())
+    }
+
+    /// Dot product, merging the two sorted index lists.
+    pub fn dot(&self, other: &SparseVector) -> f32 {
+        let (mut i, mut j, mut sum) = (0, 0, 0.0);
+        while i < self.indices.len() && j < other.indices.len() {
+            match self.indices[i].cmp(&other.indices[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    sum += self.values[i] * other.values[j];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        sum
+    }
+}
+
+#[derive(Debug, Default)]
+struct PostingList {
+    // Sorted by point ID.
+    postings: Vec<(PointId, f32)>,
+    max_weight: f32,
+    min_weight: f32,
+}
+
+impl PostingList {
+    fn insert(&mut self, point_id: PointId, weight: f32) {
+        if self.postings.is_empty() {
+            self.max_weight = weight;
+            self.min_weight = weight;
+        } else {
+            self.max_weight = self.max_weight.max(weight);
+            self.min_weight = self.min_weight.min(weight);
+        }
+        match self.postings.last() {
+            Some(&(last, _)) if last < point_id => self.postings.push((point_id, weight)),
+            None => self.postings.push((point_id, weight)),
+            _ => match self.postings.binary_search_by_key(&point_id, |&(id, _)| id) {
+                Ok(pos) => self.postings[pos].1 = weight,
+                Err(pos) => self.postings.insert(pos, (point_id, weight)),
+            },
+        }
+    }
+
+    // Bounds are left as they were: they stay valid upper bounds, just looser.
+    fn remove(&mut self, point_id: PointId) {
+        if let Ok(pos) = self.postings.binary_search_by_key(&point_id, |&(id, _)| id) {
+            self.postings.remove(pos);
+        }
+    }
+}
+
+/// A query term during search: its posting list, a cursor into it, and the largest amount it can
+/// add to any document's score.
+struct Term<'a> {
+    postings: &'a [(PointId, f32)],
+    cursor: usize,
+    weight: f32,
+    upper_bound: f32,
+}
+
+impl Term<'_> {
+    fn current(&self) -> Option<PointId> {
+        self.postings.get(self.cursor).map(|&(id, _)| id)
+    }
+
+    /// Move to the first posting at or after `target` and return its contribution if it is `target`.
+    fn advance_to(&mut self, target: PointId) -> Option<f32> {
+        let rest = &self.postings[self.cursor..];
+        self.cursor += rest.partition_point(|&(id, _)| id < target);
+        match self.postings.get(self.cursor) {
+            Some(&(id, w)) if id == target => Some(self.weight * w),
+            _ => None,
+        }
+    }
+}
+
+// Min-heap entry on score, so the weakest of the current top-k is on top.
+#[derive(PartialEq)]
+struct Candidate(f32, PointId);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.0.partial_cmp(&self.0).unwrap().then(other.1.cmp(&self.1))
+    }
+}
+
+/// Inverted index over sparse vectors.
+#[derive(Debug, Default)]
+pub struct SparseIndex {
+    postings: HashMap<u32, PostingList>,
+    vectors: HashMap<PointId, SparseVector>,
+}
+
+impl SparseIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index `vector` under `point_id`, replacing any vector it had.
+    pub fn insert(&mut self, point_id: PointId, vector: SparseVector) {
+        self.remove(point_id);
+        for (index, value) in vector.iter() {
+            self.postings.entry(index).or_default().insert(point_id, value);
+        }
+        self.vectors.insert(point_id, vector);
+    }
+
+    pub fn remove(&mut self, point_id: PointId) -> Option<SparseVector> {
+        let vector = self.vectors.remove(&point_id)?;
+        for index in vector.indices() {
+            if let Some(list) = self.postings.get_mut(index) {
+                list.remove(point_id);
+                if list.postings.is_empty() {
+                    self.postings.remove(index);
+                }
+            }
+        }
+        Some(vector)
+    }
+
+    pub fn get(&self, point_id: &PointId) -> Option<&SparseVector> {
+        self.vectors.get(point_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PointId, &SparseVector)> {
+        self.vectors.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// Exact top-k by dot product among points that share at least one dimension with `query` and
+    /// pass `accept`, best first. Scores follow `DistanceMetric::Dot`: `sort_key` is `-raw_score`.
+    pub fn search(&self, query: &SparseVector, top_k: usize, accept: impl Fn(PointId) -> bool) -> Vec<ScoredPoint> {
+        if top_k == 0 {
+            return Vec::new();
+        }
+
+        let mut terms: Vec<Term> = query
+            .iter()
+            .filter_map(|(index, weight)| {
+                let list = self.postings.get(&index)?;
+                let upper_bound = (weight * list.max_weight).max(weight * list.min_weight).max(0.0);
+                Some(Term { postings: &list.postings, cursor: 0, weight, upper_bound })
+            })
+            .collect();
+        terms.sort_by(|a, b| a.upper_bound.partial_cmp(&b.upper_bound).unwrap());
+        // prefix_bound[i]: the most terms[..i] can add together.
+        let prefix_bound: Vec<f32> = std::iter::once(0.0)
+            .chain(terms.iter().scan(0.0, |sum, t| {
+                *sum += t.upper_bound;
+                Some(*sum)
+            }))
+            .collect();
+
+        let mut heap: BinaryHeap<Candidate> = BinaryHeap::with_capacity(top_k + 1);
+        // Terms before `first_essential` cannot lift a document into the top-k on their own.
+        let mut first_essential = 0;
+        while let Some(doc) = terms[first_essential..].iter().filter_map(Term::current).min() {
+
+            let mut score = 0.0;
+            for term in &mut terms[first_essential..] {
+                if term.current() == Some(doc) {
+                    score += term.weight * term.postings[term.cursor].1;
+                    term.cursor += 1;
+                }
+            }
+
+            let threshold = (heap.len() == top_k).then(|| heap.peek().unwrap().0);
+            let mut alive = true;
+            for i in (0..first_essential).rev() {
+                if threshold.is_some_and(|t| score + prefix_bound[i + 1] <= t) {
+                    alive = false;
+                    break;
+                }
+                score += terms[i].advance_to(doc).unwrap_or(0.0);
+            }
+
+            if !alive || threshold.is_some_and(|t| score <= t) || !accept(doc) {
+                continue;
+            }
+            heap.push(Candidate(score, doc));
+            if heap.len() > top_k {
+                heap.pop();
+            }
+            if heap.len() == top_k {
+                let threshold = heap.peek().unwrap().0;
+                while first_essential < terms.len() && prefix_bound[first_essential + 1] <= threshold {
+                    first_essential += 1;
+                }
+            }
+        }
+
+        let mut results: Vec<ScoredPoint> = heap
+            .into_iter()
+            .map(|Candidate(score, id)| ScoredPoint { id, raw_score: score, sort_key: -score, sources: Vec::new() })
+            .collect();
+        results.sort_by(|a, b| a.sort_key.partial_cmp(&b.sort_key).unwrap().then(a.id.cmp(&b.id)));
+        results
+    }
+}
@@ -0,0 +1,361 @@
+//! Backends for the raw vectors held by an `HNSWIndex`.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use half::slice::HalfFloatSliceExt;
+use half::{bf16, f16};
+use itertools::Either;
+use memmap2::Mmap;
+
+use crate::utils::codec::{self, Decoder};
+use crate::utils::errors::DBError;
+use crate::utils::types::{PointId, Vector, VectorElementType};
+
+const MMAP_MAGIC: &[u8; 8] = b"VDBVECS\0";
+const MMAP_VERSION: u32 = 1;
+// magic | version u32 | element type u32 | dim u64 | count u64. Keeps the vector block 4-byte
+// aligned after the u64 ids.
+const MMAP_HEADER_LEN: usize = 8 + 4 + 4 + 8 + 8;
+
+/// An owned vector in an index's storage element type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoredVector {
+    Float32(Vector),
+    Float16(Vec<f16>),
+    BFloat16(Vec<bf16>),
+    Uint8(Vec<u8>),
+}
+
+impl StoredVector {
+    /// Convert `vector` to `element_type`. See `VectorElementType` for how each type rounds.
+    pub fn encode(vector: Vector, element_type: VectorElementType) -> Self {
+        match element_type {
+            VectorElementType::Float32 => StoredVector::Float32(vector),
+            VectorElementType::Float16 => {
+                let mut out = vec![f16::ZERO; vector.len()];
+                out.convert_from_f32_slice(&vector);
+                StoredVector::Float16(out)
+            }
+            VectorElementType::BFloat16 => {
+                let mut out = vec![bf16::ZERO; vector.len()];
+                out.convert_from_f32_slice(&vector);
+                StoredVector::BFloat16(out)
+            }
+            VectorElementType::Uint8 => {
+                StoredVector::Uint8(vector.iter().map(|x| x.round().clamp(0.0, 255.0) as u8).collect())
+            }
+        }
+    }
+
+    pub fn as_ref(&self) -> VectorRef<'_> {
+        match self {
+            StoredVector::Float32(v) => VectorRef::Float32(v),
+            StoredVector::Float16(v) => VectorRef::Float16(v),
+            StoredVector::BFloat16(v) => VectorRef::BFloat16(v),
+            StoredVector::Uint8(v) => VectorRef::Uint8(v),
+        }
+    }
+
+    /// Read a vector written by `VectorRef::write` as `element_type`.
+    pub(crate) fn read(dec: &mut Decoder, element_type: VectorElementType) -> Result<Self, DBError> {
+        let len = dec.length_prefix(element_type.size())?;
+        let bytes = dec.bytes(len * element_type.size())?;
+        Ok(match element_type {
+            VectorElementType::Float32 => {
+                StoredVector::Float32(bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect())
+            }
+            VectorElementType::Float16 => StoredVector::Float16(
+                bytes.chunks_exact(2).map(|b| f16::from_le_bytes(b.try_into().unwrap())).collect(),
+            ),
+            VectorElementType::BFloat16 => StoredVector::BFloat16(
+                bytes.chunks_exact(2).map(|b| bf16::from_le_bytes(b.try_into().unwrap())).collect(),
+            ),
+            VectorElementType::Uint8 => StoredVector::Uint8(bytes.to_vec()),
+        })
+    }
+}
+
+/// A borrowed vector in an index's storage element type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VectorRef<'a> {
+    Float32(&'a [f32]),
+    Float16(&'a [f16]),
+    BFloat16(&'a [bf16]),
+    Uint8(&'a [u8]),
+}
+
+impl<'a> VectorRef<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            VectorRef::Float32(v) => v.len(),
+            VectorRef::Float16(v) => v.len(),
+            VectorRef::BFloat16(v) => v.len(),
+            VectorRef::Uint8(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn element_type(&self) -> VectorElementType {
+        match self {
+            VectorRef::Float32(_) => VectorElementType::Float32,
+            VectorRef::Float16(_) => VectorElementType::Float16,
+            VectorRef::BFloat16(_) => VectorElementType::BFloat16,
+            VectorRef::Uint8(_) => VectorElementType::Uint8,
+        }
+    }
+
+    /// The vector as f32; borrowed when it already is f32.
+    pub fn to_f32(self) -> Cow<'a, [f32]> {
+        match self {
+            VectorRef::Float32(v) => Cow::Borrowed(v),
+            VectorRef::Float16(v) => Cow::Owned(v.to_f32_vec()),
+            VectorRef::BFloat16(v) => Cow::Owned(v.to_f32_vec()),
+            VectorRef::Uint8(v) => Cow::Owned(v.iter().map(|&x| x as f32).collect()),
+        }
+    }
+
+    /// Length-prefixed little-endian components, in the stored element type.
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        codec::put_u64(buf, self.len() as u64);
+        self.write_components(buf);
+    }
+
+    fn write_components(&self, out: &mut Vec<u8>) {
+        match self {
+            VectorRef::Float32(v) => v.iter().for_each(|x| out.extend_from_slice(&x.to_le_bytes())),
+            VectorRef::Float16(v) => v.iter().for_each(|x| out.extend_from_slice(&x.to_le_bytes())),
+            VectorRef::BFloat16(v) => v.iter().for_each(|x| out.extend_from_slice(&x.to_le_bytes())),
+            VectorRef::Uint8(v) => out.extend_from_slice(v),
+        }
+    }
+}
+
+pub enum VectorStorage {
+    /// Mutable, heap-allocated vectors.
+    InMemory(HashMap<PointId, StoredVector>),
+    /// Read-only vectors served straight from a memory-mapped file.
+    Mmap(MmapVectors),
+}
+
+impl VectorStorage {
+    pub fn get(&self, point_id: &PointId) -> Option<VectorRef<'_>> {
+        match self {
+            VectorStorage::InMemory(map) => map.get(point_id).map(StoredVector::as_ref),
+            VectorStorage::Mmap(mmap) => mmap.get(point_id),
+        }
+    }
+
+    pub fn contains(&self, point_id: &PointId) -> bool {
+        match self {
+            VectorStorage::InMemory(map) => map.contains_key(point_id),
+            VectorStorage::Mmap(mmap) => mmap.rows.contains_key(point_id),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            VectorStorage::InMemory(map) => map.len(),
+            VectorStorage::Mmap(mmap) => mmap.ids.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PointId, VectorRef<'_>)> {
+        match self {
+            VectorStorage::InMemory(map) => Either::Left(map.iter().map(|(id, v)| (id, v.as_ref()))),
+            VectorStorage::Mmap(mmap) => Either::Right(mmap.ids.iter().enumerate().map(|(row, id)| (id, mmap.row(row)))),
+        }
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &PointId> {
+        self.iter().map(|(id, _)| id)
+    }
+
+    pub fn insert(&mut self, point_id: PointId, vector: StoredVector) -> Result<(), DBError> {
+        match self {
+            VectorStorage::InMemory(map) => {
+                map.insert(point_id, vector);
+                Ok(())
+            }
+            VectorStorage::Mmap(mmap) => Err(DBError::ReadOnly(format!(
+                "vectors are memory-mapped from {}",
+                mmap.path.display()
+            ))),
+        }
+    }
+
+    pub fn is_mmap(&self) -> bool {
+        matches!(self, VectorStorage::Mmap(_))
+    }
+
+    /// Bytes of vector components held on the heap; zero once the vectors are memory-mapped.
+    pub fn heap_bytes(&self) -> usize {
+        match self {
+            VectorStorage::InMemory(map) => {
+                map.values().map(|v| v.as_ref().len() * v.as_ref().element_type().size()).sum()
+            }
+            VectorStorage::Mmap(_) => 0,
+        }
+    }
+}
+
+/// Contiguous, row-major vectors mapped from disk, in any `VectorElementType`.
+///
+/// File layout:
+///   magic (8) | version u32 | element type u32 | dim u64 | count u64 | ids (count x u64) |
+///   vectors (count x dim x element)
+pub struct MmapVectors {
+    mmap: Mmap,
+    path: PathBuf,
+    element_type: VectorElementType,
+    dim: usize,
+    ids: Vec<PointId>,
+    rows: HashMap<PointId, usize>,
+    data_offset: usize,
+}
+
+impl MmapVectors {
+    /// Write `vectors` to `path` in the layout `open` expects.
+    pub fn write<'a>(
+        path: &Path,
+        element_type: VectorElementType,
+        dim: usize,
+        vectors: impl Iterator<Item = (&'a PointId, VectorRef<'a>)>,
+    ) -> Result<(), DBError> {
+        let rows: Vec<_> = vectors.collect();
+        if let Some((_, vec)) = rows.iter().find(|(_, vec)| vec.len() != dim) {
+            return Err(DBError::VectorLengthMismatch { expected: dim, actual: vec.len() });
+        }
+        if let Some((id, vec)) = rows.iter().find(|(_, vec)| vec.element_type() != element_type) {
+            return Err(DBError::SerializationError(anyhow!(
+                "point {} is stored as {:?}, expected {:?}",
+                id,
+                vec.element_type(),
+                element_type
+            )));
+        }
+        let count = rows.len();
+
+        let mut header = Vec::with_capacity(MMAP_HEADER_LEN);
+        header.extend_from_slice(MMAP_MAGIC);
+        codec::put_u32(&mut header, MMAP_VERSION);
+        codec::put_u32(&mut header, element_type_tag(element_type));
+        codec::put_u64(&mut header, dim as u64);
+        codec::put_u64(&mut header, count as u64);
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&header)?;
+        for &(id, _) in &rows {
+            writer.write_all(&id.to_le_bytes())?;
+        }
+        let mut row = Vec::with_capacity(dim * element_type.size());
+        for (_, vec) in &rows {
+            row.clear();
+            vec.write_components(&mut row);
+            writer.write_all(&row)?;
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        Ok(())
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DBError> {
+        if cfg!(target_endian = "big") {
+            return Err(DBError::SerializationError(anyhow!("memory-mapped vectors require a little-endian host")));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        // Safety: the file is treated as immutable for the lifetime of the mapping.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let mut dec = Decoder::new(&mmap);
+        if dec.bytes(MMAP_MAGIC.len())? != MMAP_MAGIC {
+            return Err(DBError::SerializationError(anyhow!("not a vector file (bad magic)")));
+        }
+        let version = dec.u32()?;
+        if version != MMAP_VERSION {
+            return Err(DBError::SerializationError(anyhow!("unsupported vector file version {}", version)));
+        }
+        let element_type = element_type_from_tag(dec.u32()?)?;
+        let dim = dec.u64()? as usize;
+        let count = dec.length_prefix(8)?;
+        let ids = (0..count).map(|_| dec.u64()).collect::<Result<Vec<_>, _>>()?;
+
+        let data_offset = dec.position();
+        let expected_len = data_offset + count * dim * element_type.size();
+        if mmap.len() != expected_len {
+            return Err(DBError::SerializationError(anyhow!(
+                "vector file is {} bytes, expected {}",
+                mmap.len(),
+                expected_len
+            )));
+        }
+
+        let rows = ids.iter().enumerate().map(|(row, &id)| (id, row)).collect();
+        Ok(Self { mmap, path, element_type, dim, ids, rows, data_offset })
+    }
+
+    fn row(&self, row: usize) -> VectorRef<'_> {
+        let size = self.element_type.size();
+        let start = self.data_offset + row * self.dim * size;
+        let ptr = self.mmap[start..start + self.dim * size].as_ptr();
+        // Safety: the mapping is page-aligned and `data_offset` is a multiple of 4, so every row is
+        // aligned for its element type; `open` verified the file length and the host is
+        // little-endian. `f16` and `bf16` are `repr(transparent)` over `u16`.
+        unsafe {
+            match self.element_type {
+                VectorElementType::Float32 => VectorRef::Float32(std::slice::from_raw_parts(ptr as *const f32, self.dim)),
+                VectorElementType::Float16 => VectorRef::Float16(std::slice::from_raw_parts(ptr as *const f16, self.dim)),
+                VectorElementType::BFloat16 => {
+                    VectorRef::BFloat16(std::slice::from_raw_parts(ptr as *const bf16, self.dim))
+                }
+                VectorElementType::Uint8 => VectorRef::Uint8(std::slice::from_raw_parts(ptr, self.dim)),
+            }
+        }
+    }
+
+    pub fn get(&self, point_id: &PointId) -> Option<VectorRef<'_>> {
+        self.rows.get(point_id).map(|&row| self.row(row))
+    }
+
+    pub fn element_type(&self) -> VectorElementType {
+        self.element_type
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn element_type_tag(element_type: VectorElementType) -> u32 {
+    match element_type {
+        VectorElementType::Float32 => 0,
+        VectorElementType::Float16 => 1,
+        VectorElementType::BFloat16 => 2,
+        VectorElementType::Uint8 => 3,
+    }
+}
+
+fn element_type_from_tag(tag: u32) -> Result<VectorElementType, DBError> {
+    match tag {
+        0 => Ok(VectorElementType::Float32),
+        1 => Ok(VectorElementType::Float16),
+        2 => Ok(VectorElementType::BFloat16),
+        3 => Ok(VectorElementType::Uint8),
+        other => Err(DBError::SerializationError(anyhow!("unknown vector element type tag {}", other))),
+    }
+}
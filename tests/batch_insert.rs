@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rand::Rng;
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+
+const DIM: usize = 16;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_batch_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn random_vector(rng: &mut impl Rng) -> Vector {
+    (0..DIM).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+fn recall(index: &HNSWIndex, queries: &[Vector]) -> usize {
+    queries
+        .iter()
+        .map(|q| {
+            let approx: HashSet<PointId> = index.search(q, 10).unwrap().iter().map(|r| r.id).collect();
+            let exact = index.exact_search(q, 10, &Default::default(), None).unwrap();
+            exact.iter().filter(|r| approx.contains(&r.id)).count()
+        })
+        .sum()
+}
+
+fn check_graph(index: &HNSWIndex, ids: impl Iterator<Item = PointId>) {
+    for id in ids {
+        assert!(!index.layer_neighbors(0, id).unwrap().is_empty(), "point {} is not linked", id);
+        for level in 0..=index.current_max_level() {
+            let neighbors = index.layer_neighbors(level, id).cloned().unwrap_or_default();
+            assert!(neighbors.len() <= index.max_degree(level));
+            for n in &neighbors {
+                assert_ne!(*n, id);
+                assert!(index.contains(n));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parallel_build_matches_serial_quality() {
+    let mut rng = rand::rng();
+    let vectors: Vec<Vector> = (0..3000).map(|_| random_vector(&mut rng)).collect();
+    let queries: Vec<Vector> = (0..30).map(|_| random_vector(&mut rng)).collect();
+
+    let mut serial = HNSWIndex::new(DistanceMetric::Euclidean, 8, 64, 8, DIM);
+    for (i, v) in vectors.iter().enumerate() {
+        serial.insert(i as PointId, v.clone()).unwrap();
+    }
+    let mut parallel = HNSWIndex::new(DistanceMetric::Euclidean, 8, 64, 8, DIM);
+    parallel.insert_batch(vectors.iter().cloned().enumerate().map(|(i, v)| (i as PointId, v)).collect(), 8).unwrap();
+
+    assert_eq!(parallel.len(), 3000);
+    check_graph(&parallel, 0..3000);
+    let (serial_hits, parallel_hits) = (recall(&serial, &queries), recall(&parallel, &queries));
+    assert!(parallel_hits >= 270, "parallel recall {}/300", parallel_hits);
+    assert!(parallel_hits + 15 >= serial_hits, "parallel {} vs serial {}", parallel_hits, serial_hits);
+}
+
+#[test]
+fn test_insert_batch_into_existing_graph() {
+    let mut rng = rand::rng();
+    let mut index = HNSWIndex::new(DistanceMetric::Cosine, 8, 64, 8, DIM);
+    for id in 0..500 {
+        index.insert(id, random_vector(&mut rng)).unwrap();
+    }
+    index.mark_deleted(0);
+    index.mark_deleted(1);
+
+    // Points 400..500 are replaced, 500..2000 are new.
+    let batch: Vec<_> = (400..2000).map(|id| (id, random_vector(&mut rng))).collect();
+    index.insert_batch(batch, 4).unwrap();
+    assert_eq!(index.len(), 2000);
+    assert_eq!(index.get_vector(&450).unwrap().len(), DIM);
+    check_graph(&index, 2..2000);
+
+    let queries: Vec<Vector> = (0..30).map(|_| random_vector(&mut rng)).collect();
+    assert!(recall(&index, &queries) >= 270);
+    for q in &queries {
+        assert!(index.search(q, 10).unwrap().iter().all(|r| r.id > 1));
+    }
+
+    assert!(matches!(
+        index.insert_batch(vec![(3000, random_vector(&mut rng)), (3000, random_vector(&mut rng))], 2),
+        Err(DBError::InvalidConfig(_))
+    ));
+    assert!(matches!(index.insert_batch(vec![(3001, vec![1.0])], 2), Err(DBError::VectorLengthMismatch { .. })));
+    assert!(!index.contains(&3000));
+}
+
+#[test]
+fn test_segment_insert_batch_with_payloads_and_replay() {
+    let mut rng = rand::rng();
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Euclidean, 8, 64, 8, DIM)).unwrap();
+    segment.set_full_scan_threshold(0);
+    segment.insert(random_vector(&mut rng), None).unwrap();
+    let points: Vec<(Vector, Option<Payload>)> = (0..2500)
+        .map(|i| {
+            let mut payload = Payload::default();
+            payload.set("shard", PayloadValue::Int(i % 5));
+            (random_vector(&mut rng), (i % 10 != 0).then_some(payload))
+        })
+        .collect();
+    let ids = segment.insert_batch(points).unwrap();
+    assert_eq!(ids, (2..=2501).collect::<Vec<_>>());
+    assert_eq!(segment.insert(random_vector(&mut rng), None).unwrap(), 2502);
+    let mut payload = Payload::default();
+    payload.set("shard", PayloadValue::Int(3));
+    let invalid = vec![(random_vector(&mut rng), Some(payload)), (vec![1.0], None)];
+    assert!(matches!(segment.insert_batch(invalid), Err(DBError::VectorLengthMismatch { .. })));
+    assert_eq!(segment.payloads().len(), 2250);
+
+    let filter = Filter::Match { key: "shard".into(), value: PayloadValue::Int(3) };
+    let mut hits = 0;
+    for _ in 0..20 {
+        let query = random_vector(&mut rng);
+        let approx = segment.search_with_filter_and_params(&query, 10, Some(&filter), &SearchParams::default()).unwrap();
+        let exact: HashSet<PointId> = segment.exact_search(&query, 10, Some(&filter)).unwrap().iter().map(|r| r.id).collect();
+        for r in &approx {
+            assert_eq!(segment.get_payload(r.id).unwrap().get("shard"), Some(&PayloadValue::Int(3)));
+        }
+        hits += approx.iter().filter(|r| exact.contains(&r.id)).count();
+    }
+    assert!(hits >= 180, "filtered recall {}/200", hits);
+
+    segment.save(&snap_path).unwrap();
+    let query = random_vector(&mut rng);
+    let expected = segment.exact_search(&query, 10, Some(&filter)).unwrap();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.hnsw().len(), 2502);
+        assert_eq!(restored.payloads().len(), 2250);
+        let results = restored.exact_search(&query, 10, Some(&filter)).unwrap();
+        assert_eq!(results.iter().map(|r| r.id).collect::<Vec<_>>(), expected.iter().map(|r| r.id).collect::<Vec<_>>());
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
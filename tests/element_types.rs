@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::types::{DistanceMetric, PointId, Vector, VectorElementType};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+use vectordb::vector::storage::VectorRef;
+
+const DIM: usize = 24;
+
+fn random_vectors(n: usize, seed: u64, range: std::ops::Range<f32>) -> Vec<Vector> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|_| (0..DIM).map(|_| rng.random_range(range.clone())).collect()).collect()
+}
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_elem_{}_{}.{}", name, std::process::id(), ext));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn build_index(metric: DistanceMetric, element_type: VectorElementType, vectors: &[Vector]) -> HNSWIndex {
+    let mut index = HNSWIndex::new(metric, 16, 100, 8, DIM);
+    index.set_element_type(element_type).unwrap();
+    for (i, v) in vectors.iter().enumerate() {
+        index.insert(i as PointId, v.clone()).unwrap();
+    }
+    index
+}
+
+#[test]
+fn test_vectors_are_stored_in_the_element_type() {
+    let vectors = random_vectors(50, 1, -1.0..1.0);
+    for (element_type, tolerance) in [
+        (VectorElementType::Float32, 0.0),
+        (VectorElementType::Float16, 1e-3),
+        (VectorElementType::BFloat16, 1e-2),
+    ] {
+        let index = build_index(DistanceMetric::Euclidean, element_type, &vectors);
+        assert_eq!(index.element_type(), element_type);
+        let (_, stored) = index.iter_stored_vectors().next().unwrap();
+        assert_eq!(stored.element_type(), element_type);
+        assert_eq!(stored.len(), DIM);
+
+        let restored = index.get_vector(&7).unwrap();
+        for (a, b) in restored.iter().zip(&vectors[7]) {
+            assert!((a - b).abs() <= tolerance, "{:?}: {} vs {}", element_type, a, b);
+        }
+    }
+
+    let bytes = vec![vec![0.4, 1.6, 254.7, 300.0, -3.0].into_iter().chain(std::iter::repeat_n(0.0, DIM - 5)).collect::<Vector>()];
+    let index = build_index(DistanceMetric::Euclidean, VectorElementType::Uint8, &bytes);
+    assert_eq!(&index.get_vector(&0).unwrap()[..5], &[0.0, 2.0, 255.0, 255.0, 0.0]);
+    assert!(matches!(index.iter_stored_vectors().next().unwrap().1, VectorRef::Uint8(_)));
+}
+
+#[test]
+fn test_element_type_is_validated() {
+    let mut index = HNSWIndex::new(DistanceMetric::Cosine, 16, 100, 8, DIM);
+    assert!(matches!(index.set_element_type(VectorElementType::Uint8), Err(DBError::InvalidConfig(_))));
+    index.set_element_type(VectorElementType::Float16).unwrap();
+    index.insert(1, vec![1.0; DIM]).unwrap();
+    assert!(matches!(index.set_element_type(VectorElementType::Float32), Err(DBError::InvalidConfig(_))));
+}
+
+#[test]
+fn test_half_precision_search_matches_f32() {
+    let vectors = random_vectors(1500, 2, -1.0..1.0);
+    let queries = random_vectors(20, 3, -1.0..1.0);
+
+    for metric in [DistanceMetric::Cosine, DistanceMetric::Dot, DistanceMetric::Euclidean, DistanceMetric::Manhattan] {
+        let full = build_index(metric, VectorElementType::Float32, &vectors);
+        for element_type in [VectorElementType::Float16, VectorElementType::BFloat16] {
+            let half = build_index(metric, element_type, &vectors);
+            let mut hits = 0;
+            for q in &queries {
+                let truth: HashSet<_> = full.search_with_params(q, 10, &SearchParams::exact()).unwrap().iter().map(|sp| sp.id).collect();
+                let found = half.search_with_params(q, 10, &SearchParams::with_ef(200)).unwrap();
+                hits += found.iter().filter(|sp| truth.contains(&sp.id)).count();
+            }
+            let recall = hits as f32 / (queries.len() * 10) as f32;
+            assert!(recall >= 0.9, "{:?} {:?}: recall {}", metric, element_type, recall);
+        }
+    }
+}
+
+#[test]
+fn test_uint8_search_on_byte_data() {
+    let vectors = random_vectors(800, 4, 0.0..255.0).into_iter().map(|v| v.iter().map(|x| x.round()).collect()).collect::<Vec<Vector>>();
+    let index = build_index(DistanceMetric::Euclidean, VectorElementType::Uint8, &vectors);
+    for id in [0, 100, 799] {
+        let hit = &index.search(&vectors[id], 1).unwrap()[0];
+        assert_eq!(hit.id, id as PointId);
+        assert_eq!(hit.raw_score, 0.0, "integral vectors round-trip exactly");
+    }
+}
+
+#[test]
+fn test_element_type_survives_wal_snapshot_and_mmap() {
+    let vectors = random_vectors(200, 5, -1.0..1.0);
+    let wal_path = temp_path("wal", "wal");
+    let snap_path = temp_path("snap", "snap");
+    let vec_path = temp_path("mmap", "vecs");
+
+    let mut hnsw = HNSWIndex::new(DistanceMetric::Dot, 16, 100, 8, DIM);
+    hnsw.set_element_type(VectorElementType::BFloat16).unwrap();
+    let mut segment = Segment::create(&wal_path, hnsw).unwrap();
+    let ids: Vec<_> = vectors.iter().map(|v| segment.insert(v.clone(), None).unwrap()).collect();
+    let expected = segment.get_vector(ids[42]).unwrap().into_owned();
+
+    let reopened = Segment::open(&wal_path).unwrap();
+    assert_eq!(reopened.hnsw().element_type(), VectorElementType::BFloat16);
+    assert_eq!(reopened.get_vector(ids[42]).unwrap(), expected.as_slice());
+
+    segment.freeze_vectors(&vec_path).unwrap();
+    assert_eq!(segment.get_vector(ids[42]).unwrap(), expected.as_slice());
+    assert_eq!(std::fs::metadata(&vec_path).unwrap().len() as usize, 32 + 200 * 8 + 200 * DIM * 2);
+
+    segment.save(&snap_path).unwrap();
+    let loaded = Segment::load(&snap_path).unwrap();
+    assert_eq!(loaded.hnsw().element_type(), VectorElementType::BFloat16);
+    assert_eq!(loaded.get_vector(ids[42]).unwrap(), expected.as_slice());
+    assert_eq!(loaded.search(&vectors[0], 1).unwrap()[0].id, segment.search(&vectors[0], 1).unwrap()[0].id);
+
+    drop((segment, loaded));
+    for path in [wal_path, snap_path, vec_path] {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,38 @@
+use rand::Rng;
+
+use vectordb::evaluation::recall::{Dataset, EvalConfig, evaluate, recall_at_k};
+use vectordb::utils::types::{DistanceMetric, Vector};
+
+fn random_vectors(n: usize, dim: usize) -> Vec<Vector> {
+    let mut rng = rand::rng();
+    (0..n).map(|_| (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect()).collect()
+}
+
+#[test]
+fn test_recall_at_k() {
+    assert_eq!(recall_at_k(&[1, 2, 3], &[1, 2, 3]), 1.0);
+    assert_eq!(recall_at_k(&[3, 9, 1, 7], &[1, 2, 3, 4]), 0.5);
+    assert_eq!(recall_at_k(&[], &[5]), 0.0);
+    assert_eq!(recall_at_k(&[5], &[]), 1.0);
+}
+
+#[test]
+fn test_evaluation_reports_every_metric_and_ef() {
+    let dataset = Dataset::new("random", random_vectors(1500, 8), random_vectors(40, 8));
+    let config = EvalConfig { ef_values: vec![10, 128], ..EvalConfig::default() };
+    let reports = evaluate(&dataset, &config).unwrap();
+    assert_eq!(reports.len(), 6);
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let cells: Vec<_> = reports.iter().filter(|r| r.metric == metric).collect();
+        assert_eq!(cells.iter().map(|r| r.ef).collect::<Vec<_>>(), vec![10, 128]);
+        for r in &cells {
+            assert_eq!(r.num_queries, 40);
+            assert!((0.0..=1.0).contains(&r.recall));
+            assert!(r.qps > 0.0);
+            assert!(r.p50 <= r.p95 && r.p95 <= r.p99);
+        }
+        // A wide beam should recover nearly all of the exact neighbors.
+        assert!(cells[1].recall >= 0.9, "{}", cells[1]);
+    }
+}
@@ -0,0 +1,102 @@
+use vectordb::payload_storage::filters::{Filter, evaluate_filter};
+use vectordb::segment::segment::{DEFAULT_FULL_SCAN_THRESHOLD, Segment};
+use vectordb::utils::payload::{Payload, PayloadValue, ScalarComparisonOp};
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::hnsw::HNSWIndex;
+use vectordb::vector::metric::score;
+
+fn vecf(v: &[f32]) -> Vector {
+    v.to_vec()
+}
+
+fn build_segment(metric: DistanceMetric, n: i64) -> Segment {
+    let hnsw = HNSWIndex::new(metric, 8, 16, 8, 3);
+    let mut segment = Segment::new(hnsw);
+    for i in 0..n {
+        let mut payload = Payload::default();
+        payload.set("bucket", PayloadValue::Int(i % 10));
+        payload.set("rare", PayloadValue::Bool(i % 97 == 0));
+        let vec = vecf(&[(i as f32 * 0.7).sin() * 4.0, (i as f32 * 0.13).cos() * 2.0, (i % 9) as f32 * 0.5]);
+        segment.insert(vec, Some(payload)).unwrap();
+    }
+    segment
+}
+
+fn ground_truth(segment: &Segment, query: &[f32], top_k: usize, filter: Option<&Filter>) -> Vec<PointId> {
+    let metric = segment.hnsw().metric();
+    let mut all: Vec<_> = segment
+        .hnsw()
+        .iter_vectors()
+        .filter(|(id, _)| !segment.is_deleted(**id))
+        .filter(|(id, _)| {
+            filter.is_none_or(|f| {
+                evaluate_filter(f, segment.get_payload(**id).unwrap()).unwrap()
+            })
+        })
+        .map(|(id, v)| (*id, score(query, &v, metric)))
+        .collect();
+    match metric {
+        DistanceMetric::Dot => all.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap()),
+        _ => all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap()),
+    }
+    all.into_iter().take(top_k).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_exact_search_returns_ground_truth_with_filters_and_deletes() {
+    let bucket = |b: i64| Filter::Match { key: "bucket".into(), value: PayloadValue::Int(b) };
+    let filters = [
+        None,
+        Some(bucket(3)),
+        Some(Filter::Or(vec![bucket(1), bucket(7)])),
+        Some(Filter::And(vec![
+            bucket(4),
+            Filter::Compare { key: "bucket".into(), op: ScalarComparisonOp::Gte, value: PayloadValue::Int(2) },
+        ])),
+        Some(Filter::Not(Box::new(bucket(0)))),
+    ];
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let mut segment = build_segment(metric, 600);
+        for id in (5..600).step_by(13) {
+            segment.delete(id).unwrap();
+        }
+        let query = vecf(&[-1.0, 0.5, 2.5]);
+
+        for filter in &filters {
+            let results = segment.exact_search(&query, 10, filter.as_ref()).unwrap();
+            let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+            assert_eq!(ids, ground_truth(&segment, &query, 10, filter.as_ref()), "{:?} {:?}", metric, filter);
+            assert!(results.windows(2).all(|w| w[0].sort_key <= w[1].sort_key));
+        }
+    }
+}
+
+#[test]
+fn test_selective_filter_falls_back_to_exact_scan() {
+    let segment = build_segment(DistanceMetric::Euclidean, 2000);
+    assert_eq!(segment.full_scan_threshold(), DEFAULT_FULL_SCAN_THRESHOLD);
+
+    // Only ~21 points carry rare = true, far below the full-scan threshold.
+    let filter = Filter::Match { key: "rare".into(), value: PayloadValue::Bool(true) };
+    let query = vecf(&[3.0, -1.0, 0.0]);
+    let results = segment.search_with_filter(&query, 15, Some(&filter)).unwrap();
+    let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+    assert_eq!(ids, ground_truth(&segment, &query, 15, Some(&filter)));
+}
+
+#[test]
+fn test_small_segment_is_searched_exactly() {
+    let mut segment = build_segment(DistanceMetric::Cosine, 150);
+    let query = vecf(&[0.2, 0.9, -0.4]);
+    let expected = ground_truth(&segment, &query, 150, None);
+
+    let ids: Vec<_> = segment.search(&query, 150).unwrap().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, expected);
+
+    // With the fallback disabled the graph is used and may return fewer than every point.
+    segment.set_full_scan_threshold(0);
+    let graph = segment.search(&query, 150).unwrap();
+    assert!(graph.len() <= 150);
+    assert_eq!(graph[0].id, expected[0]);
+}
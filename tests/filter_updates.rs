@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::payload::{Payload, PayloadUpdate, PayloadValue, ScalarComparisonOp};
+use vectordb::utils::types::{DistanceMetric, PointId};
+use vectordb::vector::hnsw::HNSWIndex;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_filter_updates_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn doc(tenant: &str, size: i64) -> Payload {
+    let mut payload = Payload::default();
+    payload.set("tenant", PayloadValue::Str(tenant.into()));
+    payload.set("size", PayloadValue::Int(size));
+    payload
+}
+
+fn tenant(value: &str) -> Filter {
+    Filter::Match { key: "tenant".into(), value: PayloadValue::Str(value.into()) }
+}
+
+fn archived() -> Payload {
+    let mut payload = Payload::default();
+    payload.set("archived", PayloadValue::Bool(true));
+    payload
+}
+
+/// 300 points spread over tenants "a", "b" and "c", with sizes 0..300, plus one without a payload.
+fn build(segment: &mut Segment) {
+    for i in 0..300 {
+        segment.insert(vec![i as f32, 1.0], Some(doc(["a", "b", "c"][i % 3], i as i64))).unwrap();
+    }
+    segment.insert(vec![-1.0, 1.0], None).unwrap();
+}
+
+fn matching(segment: &Segment, filter: &Filter) -> Vec<PointId> {
+    let mut ids: Vec<_> = segment.exact_search(&vec![0.0, 0.0], 1000, Some(filter)).unwrap().iter().map(|r| r.id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn test_update_payload_and_delete_where() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    build(&mut segment);
+
+    // Indexed filter: narrowed through the payload index.
+    assert_eq!(segment.update_payload_where(&tenant("a"), PayloadUpdate::Set(archived())).unwrap(), 100);
+    let is_archived = Filter::Match { key: "archived".into(), value: PayloadValue::Bool(true) };
+    let archived_ids = matching(&segment, &is_archived);
+    assert_eq!(archived_ids, matching(&segment, &tenant("a")));
+    assert_eq!(segment.get_payload(archived_ids[0]).unwrap().get("size"), Some(&PayloadValue::Int(0)));
+
+    // Range filter: evaluated over every stored payload.
+    let large = Filter::Compare { key: "size".into(), op: ScalarComparisonOp::Gte, value: PayloadValue::Int(250) };
+    assert_eq!(segment.update_payload_where(&large, PayloadUpdate::DeleteKeys(vec!["tenant".into()])).unwrap(), 50);
+    assert_eq!(matching(&segment, &tenant("b")).len(), 83);
+
+    let no_tenant = Filter::Not(Box::new(Filter::Or(vec![tenant("a"), tenant("b"), tenant("c")])));
+    assert_eq!(segment.delete_where(&no_tenant).unwrap(), 50);
+    assert!(!segment.is_deleted(301), "points without a payload never match");
+    assert!(matching(&segment, &large).is_empty());
+    assert_eq!(segment.delete_where(&no_tenant).unwrap(), 0);
+    assert_eq!(segment.update_payload_where(&tenant("x"), PayloadUpdate::Clear).unwrap(), 0);
+
+    assert_eq!(segment.delete_where(&tenant("a")).unwrap(), 84);
+    assert!(matching(&segment, &is_archived).is_empty());
+    assert_eq!(segment.search(&vec![0.0, 1.0], 300).unwrap().len(), 300 - 50 - 84 + 1);
+}
+
+#[test]
+fn test_filter_updates_replay_from_wal_and_snapshot() {
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2)).unwrap();
+    build(&mut segment);
+    segment.update_payload_where(&tenant("b"), PayloadUpdate::Set(archived())).unwrap();
+    // Enough deletions to trigger a purge part-way through the batch.
+    segment.delete_where(&tenant("c")).unwrap();
+    segment.save(&snap_path).unwrap();
+    let expected: Vec<_> = (1..=301).map(|id| (segment.is_deleted(id), segment.get_payload(id).cloned())).collect();
+    let len = segment.hnsw().len();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.hnsw().len(), len);
+        for (i, (deleted, payload)) in expected.iter().enumerate() {
+            let id = i as PointId + 1;
+            assert_eq!(restored.is_deleted(id), *deleted);
+            assert_eq!(restored.get_payload(id), payload.as_ref());
+        }
+        assert!(matching(&restored, &tenant("c")).is_empty());
+        assert_eq!(matching(&restored, &Filter::Match { key: "archived".into(), value: PayloadValue::Bool(true) }).len(), 100);
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
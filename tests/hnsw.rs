@@ -1,5 +1,5 @@
 use vectordb::utils::types::{DistanceMetric, Vector};
-use vectordb::vector::hnsw::HNSWIndex;
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
 use vectordb::vector::metric::score;
 use vectordb::utils::errors::DBError;
 use rand::Rng;
@@ -90,6 +90,7 @@ fn test_all_metrics_consistency() {
 
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_hnsw_robust_score_metrics() {
     // Create 5 vectors in a line with easily predictable order.
     let vectors = vec![
@@ -310,6 +311,7 @@ fn test_search_k_greater_than_total_points() {
 }
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_single_insertion_exact_retrieval() {
     let mut hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 16, 50, 16, 2);
     let vec = vecf(&[3.14, 2.71]);
@@ -388,3 +390,164 @@ fn test_stable_search_results() {
 
     assert_eq!(first, second, "Search results should be deterministic");
 }
+
+fn clustered_points(clusters: usize, per_cluster: usize, dim: usize) -> Vec<Vector> {
+    let mut rng = rand::rng();
+    let mut points = Vec::with_capacity(clusters * per_cluster);
+    for _ in 0..clusters {
+        let center: Vec<f32> = (0..dim).map(|_| rng.random_range(-100.0..100.0)).collect();
+        for _ in 0..per_cluster {
+            points.push(center.iter().map(|c| c + rng.random_range(-0.5..0.5)).collect());
+        }
+    }
+    points
+}
+
+#[test]
+fn test_heuristic_neighbor_selection_recall_on_clustered_data() {
+    let points = clustered_points(20, 50, 8);
+    let top_k = 10;
+
+    for (extend, keep_pruned) in [(false, false), (true, false), (false, true), (true, true)] {
+        let mut hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 16, 64, 16, 8);
+        hnsw.set_extend_candidates(extend);
+        hnsw.set_keep_pruned_connections(keep_pruned);
+        for (i, vec) in points.iter().enumerate() {
+            hnsw.insert(i as u64, vec.clone()).unwrap();
+        }
+
+        let mut hits = 0;
+        let queries = points.iter().step_by(20).collect::<Vec<_>>();
+        for query in &queries {
+            let mut exact: Vec<(u64, f32)> = points
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i as u64, score(query, v, DistanceMetric::Euclidean)))
+                .collect();
+            exact.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            let truth: Vec<u64> = exact.iter().take(top_k).map(|(id, _)| *id).collect();
+
+            let results = hnsw.search(query, top_k).unwrap();
+            hits += results.iter().filter(|r| truth.contains(&r.id)).count();
+        }
+
+        let recall = hits as f32 / (queries.len() * top_k) as f32;
+        assert!(
+            recall >= 0.85,
+            "extend_candidates={}, keep_pruned_connections={}: recall@{} was {:.3}",
+            extend,
+            keep_pruned,
+            top_k,
+            recall
+        );
+    }
+}
+
+#[test]
+fn test_neighbor_lists_respect_max_degree() {
+    let m = 4;
+    let mut hnsw = HNSWIndex::new(DistanceMetric::Euclidean, m, 32, 16, 3);
+    let points = generate_points(1000, 3, 100.0);
+    for (i, vec) in points.iter().enumerate() {
+        hnsw.insert(i as u64, vec.clone()).unwrap();
+    }
+
+    // Force a hub: every point tries to link to point 0 on layer 0.
+    for i in 1..points.len() as u64 {
+        hnsw.add_bidirectional_edge(0, 0, i);
+    }
+
+    assert_eq!(hnsw.max_degree(0), 2 * m);
+    assert_eq!(hnsw.max_degree(1), m);
+    for (&id, _) in hnsw.iter_vectors() {
+        for level in 0..=hnsw.current_max_level() {
+            let Some(neighbors) = hnsw.layer_neighbors(level, id) else { continue };
+            assert!(
+                neighbors.len() <= hnsw.max_degree(level),
+                "point {} has {} neighbors at level {}",
+                id,
+                neighbors.len(),
+                level
+            );
+            assert!(!neighbors.contains(&id), "point {} links to itself at level {}", id, level);
+            let unique: std::collections::HashSet<_> = neighbors.iter().collect();
+            assert_eq!(unique.len(), neighbors.len(), "point {} has duplicate neighbors", id);
+        }
+    }
+
+    // The pruned graph must still be navigable. m = 4 is deliberately tiny, so use a wide beam and
+    // allow the odd point to be missed rather than requiring every lookup to succeed.
+    let params = SearchParams::with_ef(200);
+    let found = (0..points.len())
+        .step_by(10)
+        .filter(|&i| hnsw.search_with_params(&points[i], 1, &params).unwrap()[0].id == i as u64)
+        .count();
+    assert!(found >= 95, "only {}/100 points found themselves", found);
+}
+
+#[test]
+fn test_additional_metrics_search() {
+    let mut rng = rand::rng();
+    let dense: Vec<Vector> = (0..400).map(|_| (0..6).map(|_| rng.random_range(-10.0..10.0)).collect()).collect();
+    let binary: Vec<Vector> =
+        (0..400).map(|_| (0..32).map(|_| if rng.random_bool(0.3) { 1.0 } else { 0.0 }).collect()).collect();
+
+    for (metric, points) in [
+        (DistanceMetric::Manhattan, &dense),
+        (DistanceMetric::Chebyshev, &dense),
+        (DistanceMetric::Hamming, &binary),
+        (DistanceMetric::Jaccard, &binary),
+    ] {
+        let mut hnsw = HNSWIndex::new(metric, 16, 64, 8, points[0].len());
+        for (i, vec) in points.iter().enumerate() {
+            hnsw.insert(i as u64, vec.clone()).unwrap();
+        }
+
+        for q in [3usize, 150, 399] {
+            let results = hnsw.search_with_params(&points[q], 10, &SearchParams::with_ef(100)).unwrap();
+            let exact = hnsw.exact_search(&points[q], 10, &Default::default(), None).unwrap();
+            // Smaller is better for every one of these metrics.
+            assert!(results.windows(2).all(|w| w[0].raw_score <= w[1].raw_score), "{:?}", metric);
+            assert_eq!(results[0].raw_score, 0.0, "{:?}: query point not found", metric);
+            assert_eq!(results[0].sort_key, results[0].raw_score);
+            // Binary metrics have many ties, so compare the k-th distance rather than IDs.
+            assert!(results[9].raw_score <= exact[9].raw_score * 1.5 + 1e-6, "{:?}", metric);
+        }
+    }
+}
+
+#[test]
+fn test_filtered_search_returns_best_matches_only() {
+    use std::collections::HashMap;
+    use vectordb::payload_storage::filters::Filter;
+    use vectordb::payload_storage::stores::PayloadIndex;
+    use vectordb::utils::payload::{Payload, PayloadValue};
+
+    // A single layer, so the search starts from the first point inserted.
+    let mut hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 16, 50, 0, 2);
+    let mut payloads = HashMap::new();
+    let mut payload_index = PayloadIndex::new();
+    for i in 0..400u64 {
+        hnsw.insert(i, vecf(&[i as f32, 0.0])).unwrap();
+        let mut payload = Payload::default();
+        payload.set("every", PayloadValue::Int(if i % 20 == 0 { 20 } else if i % 2 == 0 { 2 } else { 1 }));
+        payload_index.insert(i, &payload);
+        payloads.insert(i, payload);
+    }
+    let every = |n: i64| Filter::Match { key: "every".into(), value: PayloadValue::Int(n) };
+    let ids = |results: Vec<vectordb::vector::hnsw::ScoredPoint>| results.iter().map(|r| r.id).collect::<Vec<_>>();
+
+    // Sparse matches around a matching entry point: keep exploring until the beam is full.
+    let query = vecf(&[0.0, 0.0]);
+    let results = hnsw.in_place_filtered_search(&query, 5, &payloads, &payload_index, Some(&every(20))).unwrap();
+    assert_eq!(ids(results), vec![0, 20, 40, 60, 80]);
+
+    // Nothing matches: the entry point must not sneak in.
+    assert!(hnsw.in_place_filtered_search(&query, 5, &payloads, &payload_index, Some(&every(7))).unwrap().is_empty());
+
+    // More matches than the beam holds: the worst ones are dropped, not the best.
+    let query = vecf(&[200.4, 0.0]);
+    let params = SearchParams::with_ef(5);
+    let results = hnsw.in_place_filtered_search_with_params(&query, 5, &payloads, &payload_index, Some(&every(2)), &params).unwrap();
+    assert_eq!(ids(results), vec![202, 198, 204, 196, 206]);
+}
@@ -0,0 +1,100 @@
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, PointId};
+use vectordb::vector::fusion::{Fusion, ScoreSource};
+use vectordb::vector::hnsw::{HNSWIndex, ScoredPoint};
+use vectordb::vector::sparse::SparseVector;
+
+fn hits(scores: &[(PointId, f32)]) -> Vec<ScoredPoint> {
+    scores.iter().map(|&(id, raw)| ScoredPoint { id, raw_score: raw, sort_key: -raw, sources: Vec::new() }).collect()
+}
+
+fn ids(results: &[ScoredPoint]) -> Vec<PointId> {
+    results.iter().map(|r| r.id).collect()
+}
+
+#[test]
+fn test_rrf_fusion_ranks_and_sources() {
+    let dense = hits(&[(1, 0.9), (2, 0.8), (3, 0.7)]);
+    let sparse = hits(&[(3, 12.0), (4, 10.0), (1, 1.0)]);
+    let fused = Fusion::Rrf { k: 60.0 }.fuse(&[(ScoreSource::Dense, dense), (ScoreSource::Sparse, sparse)], 10);
+
+    // 1: 1/61 + 1/63, 3: 1/63 + 1/61, then 2 and 4 at 1/62.
+    assert_eq!(ids(&fused), vec![1, 3, 2, 4]);
+    assert!((fused[0].raw_score - (1.0 / 61.0 + 1.0 / 63.0)).abs() < 1e-6);
+    assert_eq!(fused[0].sort_key, -fused[0].raw_score);
+
+    let sources = &fused[1].sources;
+    assert_eq!(sources.len(), 2);
+    assert_eq!((sources[0].source, sources[0].rank, sources[0].raw_score), (ScoreSource::Dense, 2, 0.7));
+    assert_eq!((sources[1].source, sources[1].rank, sources[1].raw_score), (ScoreSource::Sparse, 0, 12.0));
+    assert_eq!(fused[3].sources.len(), 1);
+
+    let top2 = Fusion::default().fuse(&[(ScoreSource::Dense, hits(&[(1, 0.9), (2, 0.8), (3, 0.7)]))], 2);
+    assert_eq!(ids(&top2), vec![1, 2]);
+}
+
+#[test]
+fn test_linear_fusion_normalizes_and_weights() {
+    // Euclidean-style list: smaller distance is better, sort_key equals raw_score.
+    let dense = vec![
+        ScoredPoint { id: 1, raw_score: 0.5, sort_key: 0.5, sources: Vec::new() },
+        ScoredPoint { id: 2, raw_score: 1.5, sort_key: 1.5, sources: Vec::new() },
+        ScoredPoint { id: 3, raw_score: 2.5, sort_key: 2.5, sources: Vec::new() },
+    ];
+    let sparse = hits(&[(3, 30.0), (2, 20.0), (1, 10.0)]);
+    let lists = [(ScoreSource::Dense, dense), (ScoreSource::Sparse, sparse)];
+
+    let fused = Fusion::Linear { dense_weight: 1.0, sparse_weight: 0.0 }.fuse(&lists, 3);
+    assert_eq!(ids(&fused), vec![1, 2, 3]);
+    assert!((fused[0].raw_score - 1.0).abs() < 1e-6);
+    assert!((fused[1].raw_score - 0.5).abs() < 1e-6);
+    assert!(fused[2].raw_score.abs() < 1e-6);
+
+    let fused = Fusion::Linear { dense_weight: 0.3, sparse_weight: 0.7 }.fuse(&lists, 3);
+    assert_eq!(ids(&fused), vec![3, 2, 1]);
+    assert!((fused[0].raw_score - 0.7).abs() < 1e-6);
+}
+
+#[test]
+fn test_segment_hybrid_search() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Cosine, 8, 32, 8, 2));
+    for i in 0..400u32 {
+        let angle = i as f32 * 0.01;
+        let dense = vec![angle.cos(), angle.sin()];
+        // Term 7 weight peaks at i = 200, far from the dense query's best match at i = 0.
+        let sparse = SparseVector::new(vec![i % 5, 7], vec![1.0, 1.0 / (1.0 + (i as f32 - 200.0).abs())]).unwrap();
+        let mut payload = Payload::default();
+        payload.set("parity", PayloadValue::Int((i % 2) as i64));
+        segment.insert_with_sparse(dense, sparse, Some(payload)).unwrap();
+    }
+    segment.delete(1).unwrap();
+
+    let dense_query = vec![1.0, 0.0];
+    let sparse_query = SparseVector::new(vec![7], vec![1.0]).unwrap();
+
+    let fused = segment.hybrid_search(&dense_query, &sparse_query, 10, None, &Fusion::default()).unwrap();
+    assert_eq!(fused.len(), 10);
+    assert!(fused.iter().all(|r| r.id != 1));
+    // Both retrievals contribute their best hits: ID 2 is the nearest live dense point, ID 201
+    // (i = 200) the best sparse one.
+    let top: Vec<PointId> = ids(&fused[..2]);
+    assert!(top.contains(&2) && top.contains(&201), "{:?}", top);
+    for r in &fused {
+        assert!(!r.sources.is_empty());
+        assert!(r.sources.iter().all(|s| s.rank < 40));
+    }
+
+    let dense_only = Fusion::Linear { dense_weight: 1.0, sparse_weight: 0.0 };
+    let fused = segment.hybrid_search(&dense_query, &sparse_query, 5, None, &dense_only).unwrap();
+    let plain = segment.search(&dense_query, 5).unwrap();
+    assert_eq!(ids(&fused), ids(&plain));
+
+    let filter = Filter::Match { key: "parity".into(), value: PayloadValue::Int(0) };
+    let fused = segment.hybrid_search(&dense_query, &sparse_query, 10, Some(&filter), &Fusion::default()).unwrap();
+    assert_eq!(fused.len(), 10);
+    for r in &fused {
+        assert_eq!(segment.get_payload(r.id).unwrap().get("parity"), Some(&PayloadValue::Int(0)));
+    }
+}
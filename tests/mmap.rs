@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, Vector, VectorElementType};
+use vectordb::vector::hnsw::HNSWIndex;
+use vectordb::vector::storage::{MmapVectors, VectorRef};
+
+fn vecf(v: &[f32]) -> Vector {
+    v.to_vec()
+}
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_{}_{}.{}", name, std::process::id(), ext));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn build_segment(metric: DistanceMetric) -> Segment {
+    let hnsw = HNSWIndex::new(metric, 8, 32, 8, 4);
+    let mut segment = Segment::new(hnsw);
+    for i in 0..500 {
+        let mut payload = Payload::default();
+        payload.set("bucket", PayloadValue::Int(i % 5));
+        let vec = vecf(&[(i as f32).sin(), (i as f32 * 0.3).cos(), (i % 11) as f32, 1.0]);
+        segment.insert(vec, Some(payload)).unwrap();
+    }
+    segment
+}
+
+#[test]
+fn test_frozen_segment_searches_match_in_memory() {
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let path = temp_path(&format!("frozen_{:?}", metric), "vecs");
+        let mut segment = build_segment(metric);
+        let queries = [vecf(&[0.5, 0.5, 3.0, 1.0]), vecf(&[-1.0, 0.2, 9.0, 1.0])];
+        let before: Vec<Vec<_>> = queries
+            .iter()
+            .map(|q| segment.search(q, 10).unwrap().into_iter().map(|r| (r.id, r.raw_score)).collect())
+            .collect();
+        let vector_before = segment.get_vector(42).unwrap().to_vec();
+
+        segment.freeze_vectors(&path).unwrap();
+        assert!(segment.hnsw().is_mmap());
+
+        let after: Vec<Vec<_>> = queries
+            .iter()
+            .map(|q| segment.search(q, 10).unwrap().into_iter().map(|r| (r.id, r.raw_score)).collect())
+            .collect();
+        assert_eq!(before, after);
+        assert_eq!(segment.get_vector(42).as_deref(), Some(vector_before.as_slice()));
+        assert_eq!(segment.hnsw().iter_vectors().count(), 500);
+
+        // Deletes still work on a frozen segment, inserts do not.
+        segment.delete(42).unwrap();
+        assert!(segment.get_vector(42).is_none());
+        assert!(matches!(
+            segment.insert(vecf(&[0.0, 0.0, 0.0, 1.0]), None),
+            Err(DBError::ReadOnly(_))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_snapshot_references_mmap_vectors() {
+    let vec_path = temp_path("snap_ref", "vecs");
+    let snap_path = temp_path("snap_ref", "snap");
+    let mut segment = build_segment(DistanceMetric::Euclidean);
+    segment.save(&snap_path).unwrap();
+    let inline_len = std::fs::metadata(&snap_path).unwrap().len();
+
+    segment.freeze_vectors(&vec_path).unwrap();
+    segment.save(&snap_path).unwrap();
+    let referenced_len = std::fs::metadata(&snap_path).unwrap().len();
+
+    // Vectors are referenced by path, not duplicated into the snapshot.
+    assert!(inline_len - referenced_len >= 500 * 4 * 4);
+
+    let loaded = Segment::load(&snap_path).unwrap();
+    assert!(loaded.hnsw().is_mmap());
+    let query = vecf(&[0.1, 0.9, 4.0, 1.0]);
+    let expected: Vec<_> = segment.search(&query, 5).unwrap().into_iter().map(|r| r.id).collect();
+    let actual: Vec<_> = loaded.search(&query, 5).unwrap().into_iter().map(|r| r.id).collect();
+    assert_eq!(expected, actual);
+
+    std::fs::remove_file(&snap_path).unwrap();
+    std::fs::remove_file(&vec_path).unwrap();
+}
+
+#[test]
+fn test_mmap_vectors_rejects_truncated_file() {
+    let path = temp_path("truncated", "vecs");
+    let vectors = [(1u64, vec![1.0f32, 2.0]), (2u64, vec![3.0f32, 4.0])];
+    MmapVectors::write(&path, VectorElementType::Float32, 2, vectors.iter().map(|(id, v)| (id, VectorRef::Float32(v)))).unwrap();
+
+    let mmap = MmapVectors::open(&path).unwrap();
+    assert_eq!(mmap.get(&2), Some(VectorRef::Float32(&[3.0, 4.0])));
+    assert_eq!(mmap.get(&3), None);
+    drop(mmap);
+
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+    assert!(matches!(MmapVectors::open(&path), Err(DBError::SerializationError(_))));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_frozen_segment_is_never_rebuilt() {
+    let path = temp_path("no_rebuild", "vecs");
+    let mut segment = build_segment(DistanceMetric::Euclidean);
+    segment.freeze_vectors(&path).unwrap();
+
+    // Freezing again would rewrite the file behind the live mapping.
+    assert!(matches!(segment.freeze_vectors(&path), Err(DBError::ReadOnly(_))));
+    assert!(matches!(segment.purge(), Err(DBError::ReadOnly(_))));
+
+    // Past the auto-purge threshold the deletions stay lazy and the vectors stay mapped.
+    for id in 1..=200 {
+        segment.delete(id).unwrap();
+    }
+    assert!(segment.hnsw().is_mmap());
+    assert_eq!(segment.hnsw().iter_vectors().count(), 500);
+    let results = segment.search(&vecf(&[0.5, 0.5, 3.0, 1.0]), 10).unwrap();
+    assert_eq!(results.len(), 10);
+    assert!(results.iter().all(|r| r.id > 200));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_frozen_vectors_survive_wal_replay() {
+    let wal_path = temp_path("freeze_replay", "wal");
+    let vec_path = temp_path("freeze_replay", "vecs");
+    let named_path = temp_path("freeze_replay", "vecs.title");
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2);
+        let mut segment = Segment::create(&wal_path, hnsw).unwrap();
+        segment.add_named_vector("title", HNSWIndex::new(DistanceMetric::Cosine, 8, 32, 8, 3)).unwrap();
+        for i in 0..50 {
+            let named = HashMap::from([("title".to_string(), vecf(&[i as f32, 1.0, 0.5]))]);
+            segment.insert_named(vecf(&[i as f32, 0.0]), named, None).unwrap();
+        }
+        segment.freeze_vectors(&vec_path).unwrap();
+        assert!(segment.hnsw().is_mmap());
+        assert!(segment.named_vector("title").unwrap().is_mmap());
+    }
+
+    // Replaying the log freezes both indexes again instead of serving from the heap.
+    let segment = Segment::open(&wal_path).unwrap();
+    assert!(segment.hnsw().is_mmap());
+    assert!(segment.named_vector("title").unwrap().is_mmap());
+    assert_eq!(segment.get_vector(7).as_deref(), Some(&[6.0, 0.0][..]));
+    let results = segment.search(&vecf(&[20.0, 0.0]), 1).unwrap();
+    assert_eq!(results[0].id, 21);
+    drop(segment);
+
+    std::fs::remove_file(&wal_path).unwrap();
+    std::fs::remove_file(&vec_path).unwrap();
+    std::fs::remove_file(&named_path).unwrap();
+}
@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use rand::Rng;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::fusion::ScoreSource;
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+use vectordb::vector::multivector::{MultiVector, MultiVectorConfig, MultiVectorStorage};
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_multivector_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn random_tokens(rng: &mut impl Rng, dim: usize) -> MultiVector {
+    let n = rng.random_range(1..12);
+    MultiVector::new((0..n).map(|_| (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect()).collect()).unwrap()
+}
+
+fn random_vector(rng: &mut impl Rng, dim: usize) -> Vector {
+    (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+#[test]
+fn test_maxsim_scores() {
+    assert!(MultiVector::new(vec![]).is_err());
+    assert!(matches!(MultiVector::new(vec![vec![1.0, 2.0], vec![1.0]]), Err(DBError::VectorLengthMismatch { .. })));
+
+    let doc = MultiVector::new(vec![vec![1.0, 0.0], vec![0.0, 2.0], vec![1.0, 1.0]]).unwrap();
+    let query = MultiVector::new(vec![vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+    assert_eq!(doc.len(), 3);
+
+    // Dot: best of (1, 0, 1) plus best of (0, 2, 1).
+    let mut storage = MultiVectorStorage::new(MultiVectorConfig::new(2, DistanceMetric::Dot));
+    storage.insert(1, doc.clone()).unwrap();
+    assert_eq!(storage.score(&query, 1).unwrap(), Some(3.0));
+    assert_eq!(storage.score(&query, 2).unwrap(), None);
+
+    // Cosine: both query tokens have an exactly aligned document token.
+    let mut storage = MultiVectorStorage::new(MultiVectorConfig::new(2, DistanceMetric::Cosine));
+    storage.insert(1, doc.clone()).unwrap();
+    assert!((storage.score(&query, 1).unwrap().unwrap() - 2.0).abs() < 1e-6);
+
+    // Euclidean: negated distance to the nearest token, 0 for (1, 0) and 1 for (0, 1).
+    let mut storage = MultiVectorStorage::new(MultiVectorConfig::new(2, DistanceMetric::Euclidean));
+    storage.insert(1, doc).unwrap();
+    assert!((storage.score(&query, 1).unwrap().unwrap() + 1.0).abs() < 1e-6);
+
+    let wrong_dim = MultiVector::new(vec![vec![1.0, 0.0, 0.0]]).unwrap();
+    assert!(storage.insert(2, wrong_dim.clone()).is_err());
+    assert!(storage.score(&wrong_dim, 1).is_err());
+}
+
+#[test]
+fn test_segment_rerank_orders_candidates_by_maxsim() {
+    let mut rng = rand::rng();
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Cosine, 8, 64, 8, 8));
+    let tokens = MultiVector::new(vec![vec![1.0; 4]]).unwrap();
+    assert!(matches!(segment.rerank(&tokens, Vec::new(), 5), Err(DBError::InvalidConfig(_))));
+    segment.enable_multivectors(MultiVectorConfig::new(4, DistanceMetric::Dot)).unwrap();
+    assert!(segment.enable_multivectors(MultiVectorConfig::new(4, DistanceMetric::Dot)).is_err());
+
+    for i in 0..500 {
+        let id = segment.insert(random_vector(&mut rng, 8), None).unwrap();
+        // Every tenth point has no multi-vector and can never come out of re-ranking.
+        if i % 10 != 0 {
+            segment.set_multivector(id, random_tokens(&mut rng, 4)).unwrap();
+        }
+    }
+    assert!(matches!(segment.set_multivector(9999, random_tokens(&mut rng, 4)), Err(DBError::NotFound(9999))));
+    assert!(matches!(
+        segment.set_multivector(2, random_tokens(&mut rng, 3)),
+        Err(DBError::VectorLengthMismatch { expected: 4, actual: 3 })
+    ));
+
+    for _ in 0..10 {
+        let query = random_vector(&mut rng, 8);
+        let query_tokens = random_tokens(&mut rng, 4);
+        let results = segment.search_with_rerank(&query, &query_tokens, 10, None, &SearchParams::default()).unwrap();
+        let candidates = segment.search_with_filter_and_params(&query, 40, None, &SearchParams::default()).unwrap();
+
+        let storage = segment.multivectors().unwrap();
+        let mut expected: Vec<(PointId, f32)> = candidates
+            .iter()
+            .filter_map(|c| storage.score(&query_tokens, c.id).unwrap().map(|s| (c.id, s)))
+            .collect();
+        expected.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        expected.truncate(10);
+
+        assert_eq!(results.len(), expected.len());
+        for (r, (id, score)) in results.iter().zip(&expected) {
+            assert_eq!(r.id, *id);
+            assert!((r.raw_score - score).abs() < 1e-5);
+            assert_eq!(r.sort_key, -r.raw_score);
+            assert_eq!(r.sources.len(), 1);
+            assert_eq!(r.sources[0].source, ScoreSource::Dense);
+            let original = &candidates[r.sources[0].rank];
+            assert_eq!((original.id, original.raw_score), (r.id, r.sources[0].raw_score));
+            assert!(segment.get_multivector(r.id).is_some());
+        }
+    }
+}
+
+#[test]
+fn test_multivectors_survive_wal_snapshot_and_delete() {
+    let mut rng = rand::rng();
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 3)).unwrap();
+    segment.enable_multivectors(MultiVectorConfig::new(3, DistanceMetric::Cosine)).unwrap();
+    for _ in 0..50 {
+        let id = segment.insert(random_vector(&mut rng, 3), None).unwrap();
+        segment.set_multivector(id, random_tokens(&mut rng, 3)).unwrap();
+    }
+    segment.set_multivector(5, random_tokens(&mut rng, 3)).unwrap();
+    segment.delete(7).unwrap();
+    assert!(segment.get_multivector(7).is_none());
+    assert!(matches!(segment.set_multivector(7, random_tokens(&mut rng, 3)), Err(DBError::NotFound(7))));
+    segment.save(&snap_path).unwrap();
+
+    let vectors: Vec<Option<MultiVector>> = (1..=50).map(|id| segment.get_multivector(id).cloned()).collect();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.multivectors().unwrap().config(), MultiVectorConfig::new(3, DistanceMetric::Cosine));
+        assert_eq!(restored.multivectors().unwrap().len(), 49);
+        for (i, expected) in vectors.iter().enumerate() {
+            let actual = restored.get_multivector(i as PointId + 1);
+            match (actual, expected) {
+                (Some(a), Some(e)) => {
+                    assert_eq!(a.len(), e.len());
+                    for (x, y) in a.tokens().flatten().zip(e.tokens().flatten()) {
+                        assert!((x - y).abs() < 1e-6);
+                    }
+                }
+                (None, None) => {}
+                _ => panic!("multi-vector of point {} not restored", i + 1),
+            }
+        }
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
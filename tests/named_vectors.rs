@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rand::Rng;
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_named_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn random_vector(rng: &mut impl Rng, dim: usize) -> Vector {
+    (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+fn ids(segment: &Segment, name: &str, query: &Vector, top_k: usize, filter: Option<&Filter>, exact: bool) -> Vec<PointId> {
+    let params = if exact { SearchParams::exact() } else { SearchParams::default() };
+    segment.search_named(name, query, top_k, filter, &params).unwrap().iter().map(|r| r.id).collect()
+}
+
+/// Default vector of dim 2, "title" (Cosine, dim 8) on every point and "image" (Euclidean, dim 4)
+/// on every third one.
+fn build_segment(segment: &mut Segment, rng: &mut impl Rng, n: usize) {
+    segment.add_named_vector("title", HNSWIndex::new(DistanceMetric::Cosine, 8, 64, 8, 8)).unwrap();
+    segment.add_named_vector("image", HNSWIndex::new(DistanceMetric::Euclidean, 8, 64, 8, 4)).unwrap();
+    for i in 0..n {
+        let mut named = HashMap::new();
+        named.insert("title".to_string(), random_vector(rng, 8));
+        if i % 3 == 0 {
+            named.insert("image".to_string(), random_vector(rng, 4));
+        }
+        let mut payload = Payload::default();
+        payload.set("bucket", PayloadValue::Int((i % 4) as i64));
+        segment.insert_named(random_vector(rng, 2), named, Some(payload)).unwrap();
+    }
+}
+
+#[test]
+fn test_named_vectors_are_searched_independently() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    segment.add_named_vector("title", HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, 3)).unwrap();
+
+    let a = segment.insert_named(vec![0.0, 0.0], HashMap::from([("title".to_string(), vec![1.0, 0.0, 0.0])]), None).unwrap();
+    let b = segment.insert_named(vec![10.0, 10.0], HashMap::from([("title".to_string(), vec![0.0, 5.0, 0.0])]), None).unwrap();
+    let c = segment.insert(vec![0.1, 0.1], None).unwrap();
+
+    assert_eq!(segment.search(&vec![0.0, 0.0], 3).unwrap().iter().map(|r| r.id).collect::<Vec<_>>(), vec![a, c, b]);
+    let hits = segment.search_named("title", &vec![0.0, 1.0, 0.0], 3, None, &SearchParams::default()).unwrap();
+    assert_eq!(hits.iter().map(|r| r.id).collect::<Vec<_>>(), vec![b, a]);
+    assert_eq!(hits[0].raw_score, 5.0);
+
+    assert_eq!(segment.get_named_vector(b, "title").as_deref(), Some(&[0.0, 5.0, 0.0][..]));
+    assert!(segment.get_named_vector(c, "title").is_none());
+    assert_eq!(segment.named_vector_names().collect::<Vec<_>>(), vec!["title"]);
+    assert_eq!(segment.named_vector("title").unwrap().metric(), DistanceMetric::Dot);
+
+    assert!(matches!(
+        segment.add_named_vector("title", HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, 3)),
+        Err(DBError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        segment.search_named("body", &vec![1.0], 1, None, &SearchParams::default()),
+        Err(DBError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        segment.insert_named(vec![0.0, 0.0], HashMap::from([("title".to_string(), vec![1.0])]), None),
+        Err(DBError::VectorLengthMismatch { expected: 3, actual: 1 })
+    ));
+    assert!(matches!(
+        segment.insert_named(vec![0.0, 0.0], HashMap::from([("body".to_string(), vec![1.0])]), None),
+        Err(DBError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_named_vector_graph_search_with_filter_delete_and_purge() {
+    let mut rng = rand::rng();
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    build_segment(&mut segment, &mut rng, 1500);
+    segment.set_full_scan_threshold(0);
+
+    for id in (1..=1500).step_by(3) {
+        segment.delete(id).unwrap();
+    }
+    let filter = Filter::Match { key: "bucket".into(), value: PayloadValue::Int(1) };
+
+    let mut hits = 0;
+    for _ in 0..20 {
+        let query = random_vector(&mut rng, 8);
+        let approx = ids(&segment, "title", &query, 10, Some(&filter), false);
+        let exact = ids(&segment, "title", &query, 10, Some(&filter), true);
+        for id in &approx {
+            assert!(!segment.is_deleted(*id));
+            assert_eq!(segment.get_payload(*id).unwrap().get("bucket"), Some(&PayloadValue::Int(1)));
+        }
+        hits += approx.iter().filter(|id| exact.contains(id)).count();
+    }
+    assert!(hits >= 180, "recall {}/200", hits);
+
+    // Only points with i % 3 == 0, i.e. IDs 1, 4, 7, ..., have an image, and all of those were deleted.
+    let query = random_vector(&mut rng, 4);
+    assert!(segment.search_named("image", &query, 5, None, &SearchParams::default()).is_err());
+
+    segment.purge().unwrap();
+    assert_eq!(segment.named_vector("title").unwrap().len(), 1000);
+    assert_eq!(segment.named_vector("image").unwrap().len(), 0);
+    let query = random_vector(&mut rng, 8);
+    assert_eq!(ids(&segment, "title", &query, 10, None, false).len(), 10);
+}
+
+#[test]
+fn test_named_vectors_survive_wal_and_snapshot() {
+    let mut rng = rand::rng();
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2)).unwrap();
+    build_segment(&mut segment, &mut rng, 300);
+    segment.delete(2).unwrap();
+    segment.save(&snap_path).unwrap();
+
+    let title_query = random_vector(&mut rng, 8);
+    let image_query = random_vector(&mut rng, 4);
+    let expected_title = ids(&segment, "title", &title_query, 10, None, true);
+    let expected_image = ids(&segment, "image", &image_query, 10, None, true);
+    let vectors: Vec<_> = (1..=300).map(|id| segment.get_named_vector(id, "image").map(|v| v.into_owned())).collect();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.named_vector_names().collect::<Vec<_>>(), vec!["image", "title"]);
+        let config = restored.named_vector("image").unwrap();
+        assert_eq!((config.metric(), config.dim()), (DistanceMetric::Euclidean, 4));
+        assert_eq!(ids(&restored, "title", &title_query, 10, None, true), expected_title);
+        assert_eq!(ids(&restored, "image", &image_query, 10, None, true), expected_image);
+        for (i, v) in vectors.iter().enumerate() {
+            assert_eq!(restored.get_named_vector(i as PointId + 1, "image").map(|v| v.into_owned()), *v);
+        }
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rand::Rng;
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+
+const DIM: usize = 8;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_payload_updates_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn random_vector(rng: &mut impl Rng) -> Vector {
+    (0..DIM).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+fn payload(fields: &[(&str, PayloadValue)]) -> Payload {
+    let mut payload = Payload::default();
+    for (key, value) in fields {
+        payload.set(key, value.clone());
+    }
+    payload
+}
+
+fn status(value: &str) -> PayloadValue {
+    PayloadValue::Str(value.into())
+}
+
+fn indexed(segment: &Segment, key: &str, value: &PayloadValue) -> Vec<PointId> {
+    let mut ids: Vec<_> = segment.payload_index().query_exact(key, value).into_iter().flatten().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn test_payload_updates_keep_index_consistent() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    let a = segment.insert(vec![0.0, 0.0], Some(payload(&[("status", status("new")), ("price", PayloadValue::Int(10))]))).unwrap();
+    let b = segment.insert(vec![1.0, 1.0], None).unwrap();
+
+    segment.set_payload(a, payload(&[("status", status("sold")), ("stock", PayloadValue::Int(0))])).unwrap();
+    assert_eq!(segment.get_payload(a).unwrap().get("price"), Some(&PayloadValue::Int(10)));
+    assert_eq!(indexed(&segment, "status", &status("sold")), vec![a]);
+    assert!(indexed(&segment, "status", &status("new")).is_empty());
+
+    segment.set_payload(b, payload(&[("status", status("sold"))])).unwrap();
+    assert_eq!(indexed(&segment, "status", &status("sold")), vec![a, b]);
+
+    segment.overwrite_payload(a, payload(&[("price", PayloadValue::Int(12))])).unwrap();
+    assert_eq!(segment.get_payload(a).unwrap().0.len(), 1);
+    assert!(indexed(&segment, "stock", &PayloadValue::Int(0)).is_empty());
+    assert_eq!(indexed(&segment, "price", &PayloadValue::Int(12)), vec![a]);
+
+    segment.delete_payload_keys(b, &["status", "missing"]).unwrap();
+    assert!(segment.get_payload(b).is_none());
+    assert!(indexed(&segment, "status", &status("sold")).is_empty());
+
+    segment.clear_payload(a).unwrap();
+    assert!(segment.get_payload(a).is_none());
+    assert!(indexed(&segment, "price", &PayloadValue::Int(12)).is_empty());
+
+    let filter = Filter::Match { key: "price".into(), value: PayloadValue::Int(12) };
+    assert!(segment.exact_search(&vec![0.0, 0.0], 5, Some(&filter)).unwrap().is_empty());
+
+    segment.delete(b).unwrap();
+    assert!(matches!(segment.set_payload(b, Payload::default()), Err(DBError::NotFound(id)) if id == b));
+    assert!(matches!(segment.clear_payload(999), Err(DBError::NotFound(999))));
+}
+
+#[test]
+fn test_filtered_search_after_payload_updates() {
+    let mut rng = rand::rng();
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Cosine, 8, 64, 8, DIM));
+    segment.set_full_scan_threshold(0);
+    let statuses = ["active", "archived", "draft", "deleted"];
+    let ids: Vec<PointId> = (0..2000)
+        .map(|i| segment.insert(random_vector(&mut rng), Some(payload(&[("status", status(statuses[i % 4]))]))).unwrap())
+        .collect();
+
+    // Move a third of the points to a status nobody had when the graph was built.
+    for &id in ids.iter().step_by(3) {
+        segment.set_payload(id, payload(&[("status", status("sold"))])).unwrap();
+    }
+    assert_eq!(indexed(&segment, "status", &status("sold")).len(), 667);
+
+    let mut hits = 0;
+    for _ in 0..20 {
+        let query = random_vector(&mut rng);
+        let filter = Filter::Match { key: "status".into(), value: status("sold") };
+        let approx = segment.search_with_filter_and_params(&query, 10, Some(&filter), &SearchParams::default()).unwrap();
+        let exact: HashSet<PointId> = segment.exact_search(&query, 10, Some(&filter)).unwrap().iter().map(|r| r.id).collect();
+        for r in &approx {
+            assert_eq!(segment.get_payload(r.id).unwrap().get("status"), Some(&status("sold")));
+        }
+        hits += approx.iter().filter(|r| exact.contains(&r.id)).count();
+    }
+    assert!(hits >= 180, "filtered recall {}/200", hits);
+}
+
+#[test]
+fn test_payload_updates_drop_edges_for_old_values() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    // A far-away "remote" cluster, and one "remote" point among local ones.
+    for i in 0..50 {
+        segment.insert(vec![1000.0 + i as f32, 1000.0], Some(payload(&[("site", status("remote"))]))).unwrap();
+    }
+    for i in 0..200 {
+        segment.insert(vec![(i % 20) as f32, (i / 20) as f32], Some(payload(&[("site", status("local"))]))).unwrap();
+    }
+    let p = segment.insert(vec![10.5, 5.5], Some(payload(&[("site", status("remote"))]))).unwrap();
+    let is_remote = |id: &PointId| id <= &50;
+    let remote_links = |segment: &Segment| {
+        let outgoing = segment.hnsw().layer_neighbors(0, p).unwrap().iter().filter(|n| is_remote(n)).count();
+        let incoming = (1..=50).filter(|id| segment.hnsw().layer_neighbors(0, *id).unwrap().contains(&p)).count();
+        (outgoing, incoming)
+    };
+    assert_ne!(remote_links(&segment), (0, 0), "filter-aware edges should reach the remote cluster");
+
+    segment.delete_payload_keys(p, &["site"]).unwrap();
+    assert_eq!(remote_links(&segment), (0, 0));
+    let neighbors = segment.hnsw().layer_neighbors(0, p).unwrap();
+    assert!(!neighbors.is_empty() && neighbors.iter().all(|n| !is_remote(n)));
+
+    // Moving to another value rebuilds edges for it and drops none of the proximity links.
+    segment.set_payload(p, payload(&[("site", status("remote"))])).unwrap();
+    assert_ne!(remote_links(&segment), (0, 0));
+    segment.set_payload(p, payload(&[("site", status("local"))])).unwrap();
+    assert_eq!(remote_links(&segment), (0, 0));
+    let results = segment.search(&vec![10.5, 5.5], 1).unwrap();
+    assert_eq!(results[0].id, p);
+}
+
+#[test]
+fn test_payload_updates_replay_from_wal_and_snapshot() {
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2)).unwrap();
+    for i in 0..20 {
+        segment.insert(vec![i as f32, 0.0], Some(payload(&[("status", status("new")), ("i", PayloadValue::Int(i))]))).unwrap();
+    }
+    segment.set_payload(3, payload(&[("status", status("sold"))])).unwrap();
+    segment.overwrite_payload(4, payload(&[("flag", PayloadValue::Bool(true))])).unwrap();
+    segment.delete_payload_keys(5, &["i"]).unwrap();
+    segment.clear_payload(6).unwrap();
+    segment.save(&snap_path).unwrap();
+    let expected: Vec<Option<Payload>> = (1..=20).map(|id| segment.get_payload(id).cloned()).collect();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        for (i, payload) in expected.iter().enumerate() {
+            assert_eq!(restored.get_payload(i as PointId + 1), payload.as_ref());
+        }
+        assert_eq!(indexed(&restored, "status", &status("sold")), vec![3]);
+        assert_eq!(indexed(&restored, "flag", &PayloadValue::Bool(true)), vec![4]);
+        assert_eq!(indexed(&restored, "status", &status("new")).len(), 17);
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
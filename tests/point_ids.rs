@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+use vectordb::segment::id_mapping::IdMapping;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, ExtendedPointId, PointId};
+use vectordb::vector::hnsw::HNSWIndex;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_point_ids_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn uuid(n: u8) -> Uuid {
+    Uuid::from_bytes([n; 16])
+}
+
+#[test]
+fn test_id_mapping() {
+    let mut mapping = IdMapping::new();
+    mapping.set(ExtendedPointId::Uuid(uuid(1)), 1);
+    mapping.set("doc-a".into(), 2);
+    assert_eq!(mapping.internal_id(&ExtendedPointId::Uuid(uuid(1))), Some(1));
+    assert_eq!(mapping.external_id(2), Some(&ExtendedPointId::Str("doc-a".into())));
+    assert_eq!(mapping.external_id(3), None);
+
+    // Re-mapping either side drops the old pair.
+    mapping.set("doc-a".into(), 3);
+    assert_eq!(mapping.external_id(2), None);
+    mapping.set(7u64.into(), 3);
+    assert_eq!(mapping.internal_id(&"doc-a".into()), None);
+    assert_eq!(mapping.len(), 2);
+
+    assert_eq!(mapping.remove_internal(1), Some(ExtendedPointId::Uuid(uuid(1))));
+    assert_eq!(mapping.iter().collect::<Vec<_>>(), vec![(3, &ExtendedPointId::Num(7))]);
+
+    assert_eq!(ExtendedPointId::Uuid(uuid(0xab)).to_string(), "abababab-abab-abab-abab-abababababab");
+    assert_eq!(ExtendedPointId::Num(5).to_string(), "5");
+}
+
+#[test]
+fn test_segment_maps_external_ids_to_dense_internal_ids() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    let a = segment.upsert(uuid(1), vec![0.0, 0.0], None).unwrap();
+    let b = segment.upsert("doc-b", vec![1.0, 1.0], None).unwrap();
+    let c = segment.upsert(u64::MAX, vec![2.0, 2.0], None).unwrap();
+    let d = segment.insert(vec![3.0, 3.0], None).unwrap();
+    assert_eq!(vec![a, b, c, d], vec![1, 2, 3, 4]);
+
+    // Search results are internal IDs that translate back to the caller's.
+    let hits: Vec<PointId> = segment.search(&vec![1.9, 1.9], 4).unwrap().iter().map(|r| r.id).collect();
+    assert_eq!(hits, vec![c, b, d, a]);
+    assert_eq!(segment.external_id(hits[0]), Some(&ExtendedPointId::Num(u64::MAX)));
+    assert_eq!(segment.external_id(hits[1]), Some(&ExtendedPointId::Str("doc-b".into())));
+    assert_eq!(segment.external_id(d), None);
+
+    // A string that happens to look like a UUID is still a string ID.
+    let as_str = segment.upsert(uuid(1).to_string(), vec![5.0, 5.0], None).unwrap();
+    assert_ne!(as_str, a);
+
+    segment.delete(b).unwrap();
+    assert_eq!(segment.internal_id(&"doc-b".into()), None);
+    segment.purge().unwrap();
+    let b2 = segment.upsert("doc-b", vec![1.0, 1.0], None).unwrap();
+    assert_eq!(b2, as_str + 1);
+    assert_eq!(segment.internal_id(&"doc-b".into()), Some(b2));
+    assert_eq!(segment.external_id(b), None);
+}
+
+#[test]
+fn test_external_ids_survive_wal_and_snapshot() {
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Cosine, 8, 32, 8, 3)).unwrap();
+    let mut expected = Vec::new();
+    for i in 0..60u8 {
+        let id: ExtendedPointId = match i % 3 {
+            0 => uuid(i).into(),
+            1 => format!("doc-{}", i).into(),
+            _ => (i as u64 * 1000).into(),
+        };
+        let mut payload = Payload::default();
+        payload.set("i", PayloadValue::Int(i as i64));
+        let point_id = segment.upsert(id.clone(), vec![1.0, i as f32, 0.5], Some(payload)).unwrap();
+        expected.push((id, point_id));
+    }
+    segment.delete(expected[10].1).unwrap();
+    segment.save(&snap_path).unwrap();
+    drop(segment);
+
+    for mut restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        for (i, (id, point_id)) in expected.iter().enumerate() {
+            if i == 10 {
+                assert_eq!(restored.internal_id(id), None);
+                continue;
+            }
+            assert_eq!(restored.internal_id(id), Some(*point_id));
+            assert_eq!(restored.external_id(*point_id), Some(id));
+            assert_eq!(restored.get_payload(*point_id).unwrap().get("i"), Some(&PayloadValue::Int(i as i64)));
+        }
+        assert_eq!(restored.insert(vec![1.0, 1.0, 1.0], None).unwrap(), 61);
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
+
+#[test]
+fn test_snapshot_rejects_mapping_beyond_next_id() {
+    let snap_path = temp_path("unallocated");
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    segment.upsert(7u64, vec![0.0, 0.0], None).unwrap();
+    segment.save(&snap_path).unwrap();
+
+    // The body ends with the single mapping: internal ID (8), tag (1), numeric ID (8). Point it far
+    // past any allocated ID and fix up the checksum, as a crafted file would.
+    let mut bytes = std::fs::read(&snap_path).unwrap();
+    let at = bytes.len() - 17;
+    bytes[at..at + 8].copy_from_slice(&(1u64 << 40).to_le_bytes());
+    let crc = crc32fast::hash(&bytes[24..]);
+    bytes[20..24].copy_from_slice(&crc.to_le_bytes());
+    std::fs::write(&snap_path, &bytes).unwrap();
+
+    assert!(matches!(Segment::load(&snap_path), Err(DBError::SerializationError(_))));
+    let _ = std::fs::remove_file(&snap_path);
+}
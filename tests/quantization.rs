@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use vectordb::segment::segment::Segment;
+use vectordb::utils::types::{DistanceMetric, PointId, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+use vectordb::vector::quantization::binary::{BinaryQuantizationConfig, hamming};
+use vectordb::vector::quantization::product::ProductQuantizationConfig;
+use vectordb::vector::quantization::scalar::ScalarQuantizationConfig;
+use vectordb::vector::quantization::{QuantizationConfig, QuantizationSearchParams, Quantizer};
+
+const DIM: usize = 32;
+
+fn random_vectors(n: usize, seed: u64) -> Vec<Vector> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|_| (0..DIM).map(|_| rng.random_range(-1.0..1.0)).collect()).collect()
+}
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_quant_{}_{}.{}", name, std::process::id(), ext));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn build_index(metric: DistanceMetric, vectors: &[Vector]) -> HNSWIndex {
+    let mut index = HNSWIndex::new(metric, 16, 100, 8, DIM);
+    for (i, v) in vectors.iter().enumerate() {
+        index.insert(i as PointId, v.clone()).unwrap();
+    }
+    index
+}
+
+fn scalar(per_dimension: bool, quantile: Option<f32>) -> QuantizationConfig {
+    QuantizationConfig::Scalar(ScalarQuantizationConfig { per_dimension, quantile })
+}
+
+fn product(subspaces: usize, bits: u8) -> QuantizationConfig {
+    QuantizationConfig::Product(ProductQuantizationConfig { bits, ..ProductQuantizationConfig::new(subspaces) })
+}
+
+fn binary(center: bool) -> QuantizationConfig {
+    QuantizationConfig::Binary(BinaryQuantizationConfig { center })
+}
+
+fn recall(index: &HNSWIndex, queries: &[Vector], top_k: usize, params: &SearchParams) -> f32 {
+    let mut hits = 0;
+    for q in queries {
+        let truth: HashSet<_> = index.search_with_params(q, top_k, &SearchParams::exact()).unwrap().iter().map(|sp| sp.id).collect();
+        let found = index.search_with_params(q, top_k, params).unwrap();
+        hits += found.iter().filter(|sp| truth.contains(&sp.id)).count();
+    }
+    hits as f32 / (queries.len() * top_k) as f32
+}
+
+#[test]
+fn test_scalar_codes_use_one_byte_per_dimension() {
+    let vectors = random_vectors(200, 1);
+    let mut index = build_index(DistanceMetric::Euclidean, &vectors);
+    assert!(index.quantization().is_none());
+
+    index.quantize(&scalar(true, None)).unwrap();
+    let quantized = index.quantization().unwrap();
+    assert_eq!(quantized.len(), 200);
+    assert_eq!(quantized.get(&0).unwrap().len(), DIM);
+    assert_eq!(quantized.code_bytes(), 200 * DIM, "codes should be a quarter of the f32 size");
+
+    // Points inserted afterwards are encoded with the trained quantizer.
+    index.insert(1000, vectors[0].clone()).unwrap();
+    assert_eq!(index.quantization().unwrap().get(&1000), index.quantization().unwrap().get(&0));
+
+    index.clear_quantization();
+    assert!(index.quantization().is_none());
+}
+
+#[test]
+fn test_quantized_originals_leave_the_heap_only_when_frozen() {
+    let vectors = random_vectors(300, 9);
+    let path = temp_path("frozen_originals", "vecs");
+    let mut index = build_index(DistanceMetric::Euclidean, &vectors);
+    assert_eq!(index.heap_vector_bytes(), 300 * DIM * 4);
+
+    // Quantizing adds the codes next to the originals, which are kept for rescoring.
+    index.quantize(&scalar(true, None)).unwrap();
+    assert_eq!(index.heap_vector_bytes(), 300 * DIM * 4);
+    assert_eq!(index.quantization().unwrap().code_bytes(), 300 * DIM);
+    let before: Vec<_> = index.search(&vectors[11], 10).unwrap().iter().map(|sp| (sp.id, sp.raw_score)).collect();
+
+    // Freezing moves the originals to the mapped file; only the codes stay on the heap.
+    index.freeze_vectors(&path).unwrap();
+    assert_eq!(index.heap_vector_bytes(), 0);
+    assert_eq!(index.quantization().unwrap().code_bytes(), 300 * DIM);
+    let after: Vec<_> = index.search(&vectors[11], 10).unwrap().iter().map(|sp| (sp.id, sp.raw_score)).collect();
+    assert_eq!(before, after);
+    assert_eq!(index.get_vector(&11).as_deref(), Some(vectors[11].as_slice()));
+
+    drop(index);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_scalar_quantized_search_recall() {
+    let vectors = random_vectors(2000, 2);
+    let queries = random_vectors(30, 3);
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let mut index = build_index(metric, &vectors);
+        index.quantize(&scalar(true, None)).unwrap();
+
+        let rescored = SearchParams {
+            quantization: Some(QuantizationSearchParams { oversampling: 2.0, ..Default::default() }),
+            ..SearchParams::default()
+        };
+        let r = recall(&index, &queries, 10, &rescored);
+        assert!(r >= 0.9, "{:?}: rescored recall {} too low", metric, r);
+
+        let raw = SearchParams {
+            quantization: Some(QuantizationSearchParams { rescore: false, ..Default::default() }),
+            ..SearchParams::default()
+        };
+        let r = recall(&index, &queries, 10, &raw);
+        assert!(r >= 0.7, "{:?}: unrescored recall {} too low", metric, r);
+    }
+}
+
+#[test]
+fn test_rescored_scores_are_exact() {
+    let vectors = random_vectors(500, 4);
+    let mut index = build_index(DistanceMetric::Euclidean, &vectors);
+    index.quantize(&scalar(false, Some(0.99))).unwrap();
+
+    let query = &vectors[7];
+    let results = index.search(query, 5).unwrap();
+    assert_eq!(results[0].id, 7);
+    assert!(results[0].raw_score.abs() < 1e-3);
+    for sp in &results {
+        let exact: f32 = query.iter().zip(&vectors[sp.id as usize]).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt();
+        assert!((sp.raw_score - exact).abs() < 1e-3);
+    }
+
+    let ignored = SearchParams {
+        quantization: Some(QuantizationSearchParams { ignore: true, ..Default::default() }),
+        ..SearchParams::default()
+    };
+    assert_eq!(index.search_with_params(query, 1, &ignored).unwrap()[0].id, 7);
+}
+
+#[test]
+fn test_scalar_calibration_options() {
+    let mut vectors = random_vectors(300, 5);
+    // One extreme outlier: quantile calibration should clip it instead of stretching the range.
+    vectors[0][0] = 1000.0;
+    let refs: Vec<&[f32]> = vectors.iter().map(|v| v.as_slice()).collect();
+
+    let error = |config: &QuantizationConfig| {
+        let q = Quantizer::train(config, DistanceMetric::Euclidean, DIM, &refs).unwrap();
+        let prepared = q.prepare(&vectors[1]);
+        let approx = q.score(&prepared, &q.encode(&vectors[2]));
+        let exact: f32 = vectors[1].iter().zip(&vectors[2]).map(|(a, b)| (a - b) * (a - b)).sum();
+        (approx - exact).abs()
+    };
+
+    let global = error(&scalar(false, None));
+    let per_dim = error(&scalar(true, None));
+    let quantile = error(&scalar(true, Some(0.99)));
+    assert!(per_dim < global, "per-dimension {} should beat global {}", per_dim, global);
+    assert!(quantile < global, "quantile {} should beat global {}", quantile, global);
+
+    assert!(Quantizer::train(&scalar(true, Some(1.5)), DistanceMetric::Euclidean, DIM, &refs).is_err());
+    assert!(Quantizer::train(&scalar(true, None), DistanceMetric::Euclidean, DIM, &[]).is_err());
+}
+
+#[test]
+fn test_quantization_survives_snapshot_and_wal() {
+    let vectors = random_vectors(300, 6);
+    let wal_path = temp_path("wal", "wal");
+    let snap_path = temp_path("snap", "snap");
+
+    {
+        let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Cosine, 16, 100, 8, DIM)).unwrap();
+        for v in &vectors {
+            segment.insert(v.clone(), None).unwrap();
+        }
+        segment.quantize(&scalar(true, Some(0.99))).unwrap();
+        segment.insert(vectors[0].clone(), None).unwrap();
+        segment.save(&snap_path).unwrap();
+    }
+
+    for segment in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        let quantized = segment.hnsw().quantization().expect("quantizer should be restored");
+        assert_eq!(quantized.len(), 301);
+        assert_eq!(quantized.quantizer().config(), scalar(true, Some(0.99)));
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
+
+#[test]
+fn test_product_codes_pack_bits() {
+    let vectors = random_vectors(300, 7);
+    let mut index = build_index(DistanceMetric::Euclidean, &vectors);
+
+    index.quantize(&product(8, 8)).unwrap();
+    assert_eq!(index.quantization().unwrap().get(&0).unwrap().len(), 8);
+
+    // 6 subspaces over 32 dimensions (widths 5 and 6) at 4 bits each: 24 bits.
+    index.quantize(&product(6, 4)).unwrap();
+    let quantized = index.quantization().unwrap();
+    assert_eq!(quantized.get(&0).unwrap().len(), 3);
+    assert_eq!(quantized.code_bytes(), 300 * 3, "42x smaller than the f32 vectors");
+
+    for bad in [product(0, 8), product(DIM + 1, 8), product(4, 0), product(4, 9)] {
+        assert!(index.quantize(&bad).is_err(), "{:?} should be rejected", bad);
+    }
+    assert!(index.quantization().is_some(), "a failed retrain keeps the previous quantizer");
+}
+
+#[test]
+fn test_product_adc_matches_decoded_distance() {
+    let vectors = random_vectors(500, 8);
+    let refs: Vec<&[f32]> = vectors.iter().map(|v| v.as_slice()).collect();
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Dot, DistanceMetric::Manhattan] {
+        let Quantizer::Product(pq) = Quantizer::train(&product(8, 5), metric, DIM, &refs).unwrap() else {
+            unreachable!()
+        };
+        let query = &vectors[3];
+        let table = pq.prepare(query);
+        for v in &vectors[..20] {
+            let code = pq.encode(v);
+            let decoded = pq.decode(&code);
+            let expected = match metric {
+                DistanceMetric::Euclidean => query.iter().zip(&decoded).map(|(a, b)| (a - b) * (a - b)).sum(),
+                other => vectordb::vector::metric::score(query, &decoded, other),
+            };
+            assert!((pq.score(&table, &code) - expected).abs() < 1e-3, "{:?}", metric);
+        }
+    }
+    assert!(Quantizer::train(&product(8, 5), DistanceMetric::Jaccard, DIM, &refs).is_err());
+}
+
+#[test]
+fn test_product_quantized_search_recall_with_oversampling() {
+    let vectors = random_vectors(2000, 9);
+    let queries = random_vectors(30, 10);
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine] {
+        let mut index = build_index(metric, &vectors);
+        index.quantize(&product(16, 6)).unwrap();
+
+        let params = |oversampling: f32| SearchParams {
+            hnsw_ef: Some(200),
+            quantization: Some(QuantizationSearchParams { oversampling, ..Default::default() }),
+            ..SearchParams::default()
+        };
+        let plain = recall(&index, &queries, 10, &params(1.0));
+        let oversampled = recall(&index, &queries, 10, &params(10.0));
+        assert!(oversampled >= 0.9, "{:?}: oversampled recall {} too low", metric, oversampled);
+        assert!(oversampled >= plain, "{:?}: oversampling lowered recall ({} < {})", metric, oversampled, plain);
+    }
+}
+
+#[test]
+fn test_product_quantizer_survives_snapshot_and_wal() {
+    let vectors = random_vectors(300, 11);
+    let wal_path = temp_path("pq", "wal");
+    let snap_path = temp_path("pq", "snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Dot, 16, 100, 8, DIM)).unwrap();
+    for v in &vectors {
+        segment.insert(v.clone(), None).unwrap();
+    }
+    segment.quantize(&product(8, 4)).unwrap();
+    segment.save(&snap_path).unwrap();
+
+    // k-means++ seeding is random, so a replay that retrained would come up with other codebooks.
+    for loaded in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        let (before, after) = (segment.hnsw().quantization().unwrap(), loaded.hnsw().quantization().unwrap());
+        assert_eq!(after.quantizer(), before.quantizer());
+        assert_eq!(after.quantizer().config(), product(8, 4));
+        for id in [1, 50, 300] {
+            assert_eq!(before.get(&id), after.get(&id));
+        }
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
+
+#[test]
+fn test_binary_codes_are_sign_bits() {
+    let vectors = random_vectors(100, 12);
+    let refs: Vec<&[f32]> = vectors.iter().map(|v| v.as_slice()).collect();
+    let Quantizer::Binary(bq) = Quantizer::train(&binary(false), DistanceMetric::Euclidean, DIM, &refs).unwrap() else {
+        unreachable!()
+    };
+
+    let code = bq.encode(&vectors[0]);
+    assert_eq!(code.len(), 8, "32 bits padded to one 64-bit word");
+    for (d, &x) in vectors[0].iter().enumerate() {
+        assert_eq!(code[d / 8] >> (d % 8) & 1 == 1, x > 0.0);
+    }
+
+    let flipped: Vector = vectors[0].iter().map(|x| -x).collect();
+    assert_eq!(hamming(&code, &bq.encode(&flipped)), DIM as u32);
+    assert_eq!(bq.score(&bq.prepare(&vectors[0]), &code), 0.0);
+
+    // Centering moves the thresholds to the per-dimension means.
+    let shifted: Vec<Vector> = vectors.iter().map(|v| v.iter().map(|x| x + 5.0).collect()).collect();
+    let refs: Vec<&[f32]> = shifted.iter().map(|v| v.as_slice()).collect();
+    let plain = Quantizer::train(&binary(false), DistanceMetric::Euclidean, DIM, &refs).unwrap();
+    let centered = Quantizer::train(&binary(true), DistanceMetric::Euclidean, DIM, &refs).unwrap();
+    assert!(plain.encode(&shifted[0])[..DIM / 8].iter().all(|&b| b == 0xFF));
+    let mean_code = &centered.encode(&shifted[0])[..DIM / 8];
+    assert!(mean_code.iter().any(|&b| b != 0xFF) && mean_code.iter().any(|&b| b != 0));
+}
+
+#[test]
+fn test_binary_quantized_segment_search_with_oversampling() {
+    let vectors = random_vectors(2000, 13);
+    let queries = random_vectors(20, 14);
+
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Cosine, 16, 100, 8, DIM));
+    segment.set_full_scan_threshold(0);
+    for v in &vectors {
+        segment.insert(v.clone(), None).unwrap();
+    }
+    segment.quantize(&binary(false)).unwrap();
+    assert_eq!(segment.hnsw().quantization().unwrap().code_bytes(), 2000 * 8, "32x smaller than f32");
+
+    let recall = |params: &SearchParams| {
+        let mut hits = 0;
+        for q in &queries {
+            let truth: HashSet<_> = segment.search_with_params(q, 10, &SearchParams::exact()).unwrap().iter().map(|sp| sp.id).collect();
+            let found = segment.search_with_params(q, 10, params).unwrap();
+            assert_eq!(found.len(), 10);
+            hits += found.iter().filter(|sp| truth.contains(&sp.id)).count();
+        }
+        hits as f32 / (queries.len() * 10) as f32
+    };
+
+    let plain = recall(&SearchParams::with_oversampling(1.0));
+    let oversampled = recall(&SearchParams { hnsw_ef: Some(300), ..SearchParams::with_oversampling(20.0) });
+    assert!(oversampled >= 0.9, "oversampled recall {} too low", oversampled);
+    assert!(oversampled > plain, "oversampling should help ({} <= {})", oversampled, plain);
+
+    // Rescored results carry exact cosine distances.
+    let hit = &segment.search_with_params(&vectors[5], 1, &SearchParams::with_oversampling(4.0)).unwrap()[0];
+    assert_eq!(hit.id, 6, "segment IDs start at 1");
+    assert!(hit.raw_score.abs() < 1e-5);
+}
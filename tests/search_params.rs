@@ -0,0 +1,132 @@
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+use vectordb::vector::metric::score;
+
+fn vecf(v: &[f32]) -> Vector {
+    v.to_vec()
+}
+
+fn build_segment(metric: DistanceMetric) -> Segment {
+    let hnsw = HNSWIndex::new(metric, 8, 16, 8, 3);
+    let mut segment = Segment::new(hnsw);
+    for i in 0..400 {
+        let mut payload = Payload::default();
+        payload.set("bucket", PayloadValue::Int(i % 4));
+        let vec = vecf(&[(i as f32 * 0.7).sin() * 4.0, (i as f32 * 0.13).cos() * 2.0, (i % 9) as f32 * 0.5]);
+        segment.insert(vec, Some(payload)).unwrap();
+    }
+    segment
+}
+
+fn brute_force(segment: &Segment, query: &[f32], top_k: usize, keep: impl Fn(u64) -> bool) -> Vec<u64> {
+    let metric = segment.hnsw().metric();
+    let mut all: Vec<_> = segment
+        .hnsw()
+        .iter_vectors()
+        .filter(|(id, _)| !segment.is_deleted(**id) && keep(**id))
+        .map(|(id, v)| (*id, score(query, &v, metric)))
+        .collect();
+    match metric {
+        DistanceMetric::Dot => all.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap()),
+        _ => all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap()),
+    }
+    all.into_iter().take(top_k).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_exact_search_matches_brute_force() {
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let mut segment = build_segment(metric);
+        segment.delete(17).unwrap();
+        let query = vecf(&[1.5, -0.5, 2.0]);
+
+        let results = segment.search_with_params(&query, 10, &SearchParams::exact()).unwrap();
+        let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, brute_force(&segment, &query, 10, |_| true), "{:?}", metric);
+
+        let filter = Filter::Match { key: "bucket".into(), value: PayloadValue::Int(2) };
+        let filtered = segment
+            .search_with_filter_and_params(&query, 10, Some(&filter), &SearchParams::exact())
+            .unwrap();
+        let ids: Vec<_> = filtered.iter().map(|r| r.id).collect();
+        let expected = brute_force(&segment, &query, 10, |id| {
+            segment.get_payload(id).and_then(|p| p.get("bucket")) == Some(&PayloadValue::Int(2))
+        });
+        assert_eq!(ids, expected, "{:?}", metric);
+    }
+}
+
+#[test]
+fn test_larger_ef_does_not_reduce_recall() {
+    let segment = build_segment(DistanceMetric::Euclidean);
+    let queries: Vec<Vector> = (0..20).map(|i| vecf(&[i as f32 * 0.3 - 3.0, 1.0 - i as f32 * 0.1, 2.0])).collect();
+
+    let recall = |ef: usize| -> usize {
+        queries
+            .iter()
+            .map(|q| {
+                let truth = brute_force(&segment, q, 10, |_| true);
+                let found = segment.search_with_params(q, 10, &SearchParams::with_ef(ef)).unwrap();
+                found.iter().filter(|r| truth.contains(&r.id)).count()
+            })
+            .sum()
+    };
+    let wide = recall(200);
+    assert!(wide >= recall(10));
+    assert!(wide >= 190, "recall with ef=200 too low: {}/200", wide);
+}
+
+#[test]
+fn test_score_threshold_respects_metric_direction() {
+    let query = vecf(&[0.0, 0.0, 0.0]);
+    let segment = build_segment(DistanceMetric::Euclidean);
+    let params = SearchParams { score_threshold: Some(1.0), ..SearchParams::exact() };
+    let results = segment.search_with_params(&query, 50, &params).unwrap();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.raw_score <= 1.0));
+
+    let segment = build_segment(DistanceMetric::Dot);
+    let query = vecf(&[1.0, 1.0, 1.0]);
+    let params = SearchParams { score_threshold: Some(6.0), ..SearchParams::default() };
+    let results = segment.search_with_params(&query, 50, &params).unwrap();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.raw_score >= 6.0));
+
+    let filter = Filter::Match { key: "bucket".into(), value: PayloadValue::Int(1) };
+    let results = segment.post_filter_with_params(&query, 50, Some(&filter), &params).unwrap();
+    // IDs start at 1, so bucket 1 holds the IDs congruent to 2 mod 4.
+    assert!(results.iter().all(|r| r.raw_score >= 6.0 && r.id % 4 == 2));
+}
+
+#[test]
+fn test_euclidean_results_report_plain_distance() {
+    let segment = build_segment(DistanceMetric::Euclidean);
+    let query = vecf(&[2.0, -1.0, 1.5]);
+    let filter = Filter::Match { key: "bucket".into(), value: PayloadValue::Int(3) };
+
+    let searches = [
+        segment.search(&query, 10).unwrap(),
+        segment.search_with_params(&query, 10, &SearchParams::exact()).unwrap(),
+        segment.search_with_filter(&query, 10, Some(&filter)).unwrap(),
+        segment.post_filter(&query, 10, Some(&filter)).unwrap(),
+        segment.exact_search(&query, 10, Some(&filter)).unwrap(),
+    ];
+    for results in &searches {
+        assert!(!results.is_empty());
+        for r in results {
+            let expected = score(&query, &segment.get_vector(r.id).unwrap(), DistanceMetric::Euclidean);
+            assert!((r.raw_score - expected).abs() < 1e-4, "{} vs {}", r.raw_score, expected);
+            assert_eq!(r.sort_key, r.raw_score);
+        }
+    }
+
+    // Thresholds are given in the same, non-squared units.
+    let nearest = searches[1][0].raw_score;
+    let params = SearchParams { score_threshold: Some(nearest + 1e-4), ..SearchParams::exact() };
+    let results = segment.search_with_params(&query, 10, &params).unwrap();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.raw_score <= nearest + 1e-4));
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, Vector};
+use vectordb::vector::hnsw::HNSWIndex;
+
+fn vecf(v: &[f32]) -> Vector {
+    v.to_vec()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_{}_{}.snap", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn build_segment(metric: DistanceMetric) -> Segment {
+    let hnsw = HNSWIndex::new(metric, 8, 32, 8, 3);
+    let mut segment = Segment::new(hnsw);
+    for i in 0..300 {
+        let mut payload = Payload::default();
+        payload.set("group", PayloadValue::Str(if i % 2 == 0 { "even" } else { "odd" }.into()));
+        payload.set("score", PayloadValue::Float((i as f64 / 3.0).into()));
+        payload.set("tags", PayloadValue::ListInt(vec![i, i * 2]));
+        let vec = vecf(&[(i as f32).sin() * 5.0, ((i * 3) as f32).cos() * 3.0, ((i % 7) as f32).sqrt()]);
+        segment.insert(vec, Some(payload)).unwrap();
+    }
+    for id in (1..300).step_by(11) {
+        segment.delete(id).unwrap();
+    }
+    segment
+}
+
+#[test]
+fn test_snapshot_round_trip_preserves_graph_and_payloads() {
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Dot] {
+        let path = temp_path(&format!("roundtrip_{:?}", metric));
+        let segment = build_segment(metric);
+        segment.save(&path).unwrap();
+        let loaded = Segment::load(&path).unwrap();
+
+        let (a, b) = (segment.hnsw(), loaded.hnsw());
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.get_entry_point(), b.get_entry_point());
+        assert_eq!(a.current_max_level(), b.current_max_level());
+        assert_eq!((a.metric(), a.m(), a.ef(), a.dim()), (b.metric(), b.m(), b.ef(), b.dim()));
+        for (id, vec) in a.iter_vectors() {
+            assert_eq!(b.iter_vectors().find(|(other, _)| *other == id).map(|(_, v)| v), Some(vec));
+            for level in 0..=a.current_max_level() {
+                assert_eq!(a.layer_neighbors(level, *id), b.layer_neighbors(level, *id));
+            }
+            assert_eq!(segment.is_deleted(*id), loaded.is_deleted(*id));
+            assert_eq!(segment.get_payload(*id), loaded.get_payload(*id));
+        }
+
+        let query = vecf(&[1.0, -2.0, 0.5]);
+        let before: Vec<_> = segment.search(&query, 10).unwrap().into_iter().map(|r| r.id).collect();
+        let after: Vec<_> = loaded.search(&query, 10).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(before, after);
+
+        let value = PayloadValue::Str("even".into());
+        assert_eq!(
+            segment.payload_index().query_exact("group", &value),
+            loaded.payload_index().query_exact("group", &value)
+        );
+        let filter = Filter::Match { key: "group".into(), value };
+        let filtered = loaded.search_with_filter(&query, 5, Some(&filter)).unwrap();
+        assert!(filtered.iter().all(|r| r.id % 2 == 1));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_snapshot_preserves_next_id() {
+    let path = temp_path("next_id");
+    let segment = build_segment(DistanceMetric::Euclidean);
+    segment.save(&path).unwrap();
+
+    let mut loaded = Segment::load(&path).unwrap();
+    let id = loaded.insert(vecf(&[0.0, 0.0, 0.0]), None).unwrap();
+    assert_eq!(id, 301);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_snapshot_rejects_bad_version_and_checksum() {
+    let path = temp_path("corrupt");
+    build_segment(DistanceMetric::Euclidean).save(&path).unwrap();
+    let original = std::fs::read(&path).unwrap();
+
+    // Version field follows the 8-byte magic.
+    let mut bytes = original.clone();
+    bytes[8] = 99;
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(Segment::load(&path), Err(DBError::SerializationError(_))));
+
+    // Flip a byte in the body.
+    let mut bytes = original.clone();
+    let mid = bytes.len() / 2;
+    bytes[mid] ^= 0xFF;
+    std::fs::write(&path, &bytes).unwrap();
+    let err = Segment::load(&path).err().expect("corrupted snapshot must not load");
+    assert!(matches!(err, DBError::SerializationError(_)));
+    assert!(err.to_string().contains("checksum"));
+
+    // Truncated file.
+    std::fs::write(&path, &original[..original.len() - 10]).unwrap();
+    assert!(matches!(Segment::load(&path), Err(DBError::SerializationError(_))));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, PointId};
+use vectordb::vector::hnsw::HNSWIndex;
+use vectordb::vector::sparse::{SparseIndex, SparseVector};
+
+const VOCAB: u32 = 500;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_sparse_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// SPLADE-like vector: a handful of dimensions, skewed towards small indices so posting lists
+/// vary a lot in length.
+fn random_sparse(rng: &mut impl Rng, nnz: usize, allow_negative: bool) -> SparseVector {
+    let mut indices: Vec<u32> = (0..VOCAB).choose_multiple(rng, nnz).into_iter().map(|i| i * i / VOCAB).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    let low = if allow_negative { -1.0 } else { 0.0 };
+    let values = indices.iter().map(|_| rng.random_range(low..2.0)).collect();
+    SparseVector::new(indices, values).unwrap()
+}
+
+fn brute_force(vectors: &HashMap<PointId, SparseVector>, query: &SparseVector, top_k: usize) -> Vec<(PointId, f32)> {
+    let mut scored: Vec<(PointId, f32)> = vectors
+        .iter()
+        .filter(|(_, v)| v.indices().iter().any(|i| query.indices().binary_search(i).is_ok()))
+        .map(|(&id, v)| (id, v.dot(query)))
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+    scored.truncate(top_k);
+    scored
+}
+
+#[test]
+fn test_sparse_vector_sorts_and_validates() {
+    let v = SparseVector::new(vec![7, 2, 5], vec![0.7, 0.2, 0.5]).unwrap();
+    assert_eq!(v.indices(), &[2, 5, 7]);
+    assert_eq!(v.values(), &[0.2, 0.5, 0.7]);
+
+    assert!(SparseVector::new(vec![1, 2], vec![1.0]).is_err());
+    assert!(SparseVector::new(vec![3, 3], vec![1.0, 2.0]).is_err());
+    assert!(SparseVector::new(vec![1], vec![f32::NAN]).is_err());
+
+    let other = SparseVector::new(vec![5, 7, 9], vec![2.0, 1.0, 3.0]).unwrap();
+    assert!((v.dot(&other) - 1.7).abs() < 1e-6);
+    assert_eq!(v.dot(&SparseVector::default()), 0.0);
+}
+
+#[test]
+fn test_sparse_index_matches_brute_force() {
+    let mut rng = rand::rng();
+    for allow_negative in [false, true] {
+        let mut index = SparseIndex::new();
+        let mut vectors = HashMap::new();
+        for id in 1..=2000 {
+            let nnz = rng.random_range(1..40);
+            let v = random_sparse(&mut rng, nnz, allow_negative);
+            index.insert(id, v.clone());
+            vectors.insert(id, v);
+        }
+
+        for _ in 0..30 {
+            let nnz = rng.random_range(1..20);
+            let query = random_sparse(&mut rng, nnz, allow_negative);
+            for top_k in [1, 10, 100] {
+                let expected = brute_force(&vectors, &query, top_k);
+                let results = index.search(&query, top_k, |_| true);
+                assert_eq!(results.len(), expected.len());
+                for (r, (_, score)) in results.iter().zip(&expected) {
+                    assert!((r.raw_score - score).abs() < 1e-4, "got {} expected {}", r.raw_score, score);
+                    assert_eq!(r.sort_key, -r.raw_score);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sparse_index_replace_remove_and_accept() {
+    let mut index = SparseIndex::new();
+    index.insert(1, SparseVector::new(vec![1, 2], vec![1.0, 1.0]).unwrap());
+    index.insert(2, SparseVector::new(vec![2, 3], vec![2.0, 1.0]).unwrap());
+    index.insert(3, SparseVector::new(vec![3], vec![5.0]).unwrap());
+    let query = SparseVector::new(vec![2, 3], vec![1.0, 1.0]).unwrap();
+
+    let ids: Vec<PointId> = index.search(&query, 10, |_| true).iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![3, 2, 1]);
+
+    // Replacing a vector drops its old postings.
+    index.insert(3, SparseVector::new(vec![9], vec![5.0]).unwrap());
+    let ids: Vec<PointId> = index.search(&query, 10, |_| true).iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![2, 1]);
+
+    index.remove(2);
+    assert_eq!(index.len(), 2);
+    let ids: Vec<PointId> = index.search(&query, 10, |_| true).iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    assert!(index.search(&query, 10, |id| id != 1).is_empty());
+    assert!(index.search(&query, 0, |_| true).is_empty());
+}
+
+fn build_segment(segment: &mut Segment, rng: &mut impl Rng) -> HashMap<PointId, SparseVector> {
+    let mut vectors = HashMap::new();
+    for i in 0..300 {
+        let dense = vec![rng.random::<f32>(), rng.random::<f32>(), rng.random::<f32>()];
+        let mut payload = Payload::default();
+        payload.set("even", PayloadValue::Bool(i % 2 == 0));
+        let id = if i % 5 == 0 {
+            segment.insert(dense, Some(payload)).unwrap()
+        } else {
+            let sparse = random_sparse(rng, 20, false);
+            let id = segment.insert_with_sparse(dense, sparse.clone(), Some(payload)).unwrap();
+            vectors.insert(id, sparse);
+            id
+        };
+        if i % 7 == 0 {
+            segment.delete(id).unwrap();
+            vectors.remove(&id);
+        }
+    }
+    vectors
+}
+
+#[test]
+fn test_segment_sparse_search_with_filter_and_deletes() {
+    let mut rng = rand::rng();
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, 3));
+    let vectors = build_segment(&mut segment, &mut rng);
+    let filter = Filter::Match { key: "even".into(), value: PayloadValue::Bool(true) };
+
+    for _ in 0..10 {
+        let query = random_sparse(&mut rng, 10, false);
+        let results = segment.search_sparse(&query, 10, None).unwrap();
+        let expected = brute_force(&vectors, &query, 10);
+        assert_eq!(results.iter().map(|r| r.id).collect::<Vec<_>>(), expected.iter().map(|e| e.0).collect::<Vec<_>>());
+
+        let results = segment.search_sparse(&query, 10, Some(&filter)).unwrap();
+        for r in &results {
+            assert!(!segment.is_deleted(r.id));
+            assert_eq!(segment.get_payload(r.id).unwrap().get("even"), Some(&PayloadValue::Bool(true)));
+        }
+        let even: HashMap<PointId, SparseVector> = vectors
+            .iter()
+            .filter(|(id, _)| segment.get_payload(**id).unwrap().get("even") == Some(&PayloadValue::Bool(true)))
+            .map(|(&id, v)| (id, v.clone()))
+            .collect();
+        assert_eq!(results.len(), brute_force(&even, &query, 10).len());
+    }
+}
+
+#[test]
+fn test_segment_sparse_vectors_survive_wal_and_snapshot() {
+    let mut rng = rand::rng();
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, 3)).unwrap();
+    let vectors = build_segment(&mut segment, &mut rng);
+    segment.save(&snap_path).unwrap();
+    let query = random_sparse(&mut rng, 10, false);
+    let expected: Vec<PointId> = segment.search_sparse(&query, 20, None).unwrap().iter().map(|r| r.id).collect();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.sparse_index().len(), vectors.len());
+        for (id, v) in &vectors {
+            assert_eq!(restored.get_sparse_vector(*id), Some(v));
+        }
+        let ids: Vec<PointId> = restored.search_sparse(&query, 20, None).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(ids, expected);
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
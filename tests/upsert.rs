@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rand::Rng;
+use vectordb::payload_storage::filters::Filter;
+use vectordb::segment::segment::Segment;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, ExtendedPointId, PointId, Vector};
+use vectordb::vector::hnsw::{HNSWIndex, SearchParams};
+
+const DIM: usize = 8;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_upsert_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn random_vector(rng: &mut impl Rng) -> Vector {
+    (0..DIM).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+fn color(value: &str) -> Payload {
+    let mut payload = Payload::default();
+    payload.set("color", PayloadValue::Str(value.into()));
+    payload
+}
+
+fn color_filter(value: &str) -> Filter {
+    Filter::Match { key: "color".into(), value: PayloadValue::Str(value.into()) }
+}
+
+#[test]
+fn test_hnsw_insert_replaces_existing_point() {
+    let mut index = HNSWIndex::new(DistanceMetric::Euclidean, 4, 16, 4, 2);
+    for i in 0..50 {
+        index.insert(i, vec![i as f32, 0.0]).unwrap();
+    }
+    index.mark_deleted(10);
+    index.insert(10, vec![100.0, 100.0]).unwrap();
+    index.insert(20, vec![-100.0, -100.0]).unwrap();
+
+    assert_eq!(index.len(), 50);
+    assert!(!index.is_deleted(&10));
+    assert_eq!(index.get_vector(&10).as_deref(), Some(&[100.0, 100.0][..]));
+    assert_eq!(index.search(&[99.0, 99.0], 1).unwrap()[0].id, 10);
+    assert_eq!(index.search(&[-99.0, -99.0], 1).unwrap()[0].id, 20);
+    // The old position of 20 now belongs to its neighbors.
+    let near_old = index.search(&[20.0, 0.0], 2).unwrap();
+    assert!(near_old.iter().all(|r| r.id != 20), "{:?}", near_old);
+}
+
+#[test]
+fn test_upsert_uses_caller_ids_and_replaces_points() {
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2));
+    let big = segment.upsert(1_000_000u64, vec![1.0, 1.0], Some(color("red"))).unwrap();
+    let answer = segment.upsert(42u64, vec![2.0, 2.0], Some(color("blue"))).unwrap();
+    assert_eq!(segment.get_vector(big).as_deref(), Some(&[1.0, 1.0][..]));
+    assert_eq!(segment.external_id(big), Some(&ExtendedPointId::Num(1_000_000)));
+    assert_eq!(segment.internal_id(&ExtendedPointId::Num(42)), Some(answer));
+    let auto = segment.insert(vec![3.0, 3.0], None).unwrap();
+    assert!(segment.external_id(auto).is_none());
+
+    assert_eq!(segment.upsert(42u64, vec![5.0, 5.0], Some(color("red"))).unwrap(), answer);
+    assert_eq!(segment.get_vector(answer).as_deref(), Some(&[5.0, 5.0][..]));
+    assert_eq!(segment.get_payload(answer).unwrap().get("color"), Some(&PayloadValue::Str("red".into())));
+    assert!(segment.payload_index().query_exact("color", &PayloadValue::Str("blue".into())).is_none_or(|ids| ids.is_empty()));
+    let red = segment.exact_search(&vec![5.0, 5.0], 10, Some(&color_filter("red"))).unwrap();
+    assert_eq!(red.iter().map(|r| r.id).collect::<Vec<_>>(), vec![answer, big]);
+    assert!(segment.exact_search(&vec![5.0, 5.0], 10, Some(&color_filter("blue"))).unwrap().is_empty());
+
+    // Upserting a deleted ID revives it; without a payload the point has none, nor a sparse vector.
+    let seven = segment.upsert(7u64, vec![0.0, 0.0], Some(color("green"))).unwrap();
+    segment.delete(seven).unwrap();
+    assert!(segment.get_vector(seven).is_none());
+    assert!(segment.internal_id(&ExtendedPointId::Num(7)).is_none());
+    assert_eq!(segment.upsert(7u64, vec![0.5, 0.5], None).unwrap(), seven);
+    assert!(!segment.is_deleted(seven));
+    assert!(segment.get_payload(seven).is_none());
+    assert_eq!(segment.search(&vec![0.5, 0.5], 1).unwrap()[0].id, seven);
+}
+
+#[test]
+fn test_upserts_keep_graph_and_filtered_search_accurate() {
+    let mut rng = rand::rng();
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Cosine, 8, 64, 8, DIM));
+    segment.set_full_scan_threshold(0);
+    let colors = ["red", "green", "blue", "yellow"];
+    let mut ids = Vec::new();
+    for id in 0..2000u64 {
+        ids.push(segment.upsert(id * 3, random_vector(&mut rng), Some(color(colors[id as usize % 4]))).unwrap());
+    }
+    // Re-embed and recolor a quarter of the points.
+    for id in (0..2000u64).step_by(4) {
+        segment.upsert(id * 3, random_vector(&mut rng), Some(color(colors[(id as usize + 1) % 4]))).unwrap();
+    }
+    assert_eq!(segment.hnsw().len(), 2000);
+
+    for id in ids {
+        for level in 0..=segment.hnsw().current_max_level() {
+            for n in segment.hnsw().layer_neighbors(level, id).into_iter().flatten() {
+                assert_ne!(*n, id);
+                assert!(segment.hnsw().contains(n));
+            }
+        }
+    }
+
+    let mut hits = 0;
+    let mut filtered_hits = 0;
+    for _ in 0..20 {
+        let query = random_vector(&mut rng);
+        let approx: HashSet<PointId> = segment.search(&query, 10).unwrap().iter().map(|r| r.id).collect();
+        let exact = segment.exact_search(&query, 10, None).unwrap();
+        hits += exact.iter().filter(|r| approx.contains(&r.id)).count();
+
+        let filter = color_filter("green");
+        let approx = segment.search_with_filter_and_params(&query, 10, Some(&filter), &SearchParams::default()).unwrap();
+        let exact: HashSet<PointId> = segment.exact_search(&query, 10, Some(&filter)).unwrap().iter().map(|r| r.id).collect();
+        for r in &approx {
+            assert_eq!(segment.get_payload(r.id).unwrap().get("color"), Some(&PayloadValue::Str("green".into())));
+        }
+        filtered_hits += approx.iter().filter(|r| exact.contains(&r.id)).count();
+    }
+    assert!(hits >= 180, "recall {}/200", hits);
+    assert!(filtered_hits >= 180, "filtered recall {}/200", filtered_hits);
+}
+
+#[test]
+fn test_upserts_replay_from_wal_and_snapshot() {
+    let mut rng = rand::rng();
+    let wal_path = temp_path("wal");
+    let snap_path = temp_path("snap");
+
+    let mut segment = Segment::create(&wal_path, HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, DIM)).unwrap();
+    let ids = [u64::MAX - 1, 17, 123_456_789, 5];
+    for &id in &ids {
+        segment.upsert(id, random_vector(&mut rng), Some(color("red"))).unwrap();
+    }
+    segment.upsert(17u64, random_vector(&mut rng), Some(color("blue"))).unwrap();
+    segment.save(&snap_path).unwrap();
+    let vectors: Vec<Vector> = ids
+        .iter()
+        .map(|&id| segment.get_vector(segment.internal_id(&id.into()).unwrap()).unwrap().into_owned())
+        .collect();
+    drop(segment);
+
+    for restored in [Segment::open(&wal_path).unwrap(), Segment::load(&snap_path).unwrap()] {
+        assert_eq!(restored.hnsw().len(), 4);
+        for (&id, v) in ids.iter().zip(&vectors) {
+            let point_id = restored.internal_id(&id.into()).unwrap();
+            assert_eq!(restored.get_vector(point_id).as_deref(), Some(&v[..]));
+            assert_eq!(restored.external_id(point_id), Some(&ExtendedPointId::Num(id)));
+        }
+        let point_id = restored.internal_id(&ExtendedPointId::Num(17)).unwrap();
+        assert_eq!(restored.get_payload(point_id).unwrap().get("color"), Some(&PayloadValue::Str("blue".into())));
+    }
+
+    let _ = std::fs::remove_file(&wal_path);
+    let _ = std::fs::remove_file(&snap_path);
+}
@@ -58,3 +58,99 @@ fn test_cosine_distance_exact() {
     let dist = score(&v1, &v2, DistanceMetric::Cosine);
     assert!((dist - 1.0).abs() < 1e-6);
 }
+
+fn reference_distance(a: &[f32], b: &[f32], metric: DistanceMetric) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    match metric {
+        DistanceMetric::Dot => dot,
+        DistanceMetric::Euclidean => a.iter().zip(b).map(|(x, y)| (*x as f64 - *y as f64).powi(2)).sum::<f64>().sqrt(),
+        DistanceMetric::Cosine => {
+            let na: f64 = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+            let nb: f64 = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+            1.0 - dot / (na * nb)
+        }
+        other => unreachable!("no reference for {:?}", other),
+    }
+}
+
+#[test]
+fn test_simd_kernels_match_scalar_reference() {
+    assert_eq!(Kernels::for_level(SimdLevel::Scalar).unwrap().level(), SimdLevel::Scalar);
+    assert!(kernels().level().is_available());
+
+    // Lengths cover empty input, pure tails and every remainder of the 4/8/16-lane loops.
+    for len in (0..40).chain([100, 127, 128, 129, 960]) {
+        let a: Vec<f32> = (0..len).map(|i| ((i * 7 + 3) as f32 * 0.61).sin() * 3.0).collect();
+        let b: Vec<f32> = (0..len).map(|i| ((i * 5 + 1) as f32 * 0.29).cos() * 2.0).collect();
+        for level in SimdLevel::ALL.into_iter().filter(|l| l.is_available()) {
+            let k = Kernels::for_level(level).unwrap();
+            let tol = 1e-4 * (1.0 + len as f64);
+            let expected_dot = reference_distance(&a, &b, DistanceMetric::Dot);
+            let expected_l2 = reference_distance(&a, &b, DistanceMetric::Euclidean).powi(2);
+            assert!((k.dot(&a, &b) as f64 - expected_dot).abs() < tol, "{:?} dot len {}", level, len);
+            assert!((k.l2_squared(&a, &b) as f64 - expected_l2).abs() < tol, "{:?} l2 len {}", level, len);
+            if len > 0 {
+                let expected_cos = reference_distance(&a, &b, DistanceMetric::Cosine);
+                assert!((k.cosine_distance(&a, &b) as f64 - expected_cos).abs() < 1e-4, "{:?} cosine len {}", level, len);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mixed_kernels_match_widened_f32() {
+    use vectordb::vector::storage::StoredVector;
+
+    for len in (0..40).chain([127, 128, 129, 768]) {
+        let a: Vec<f32> = (0..len).map(|i| ((i * 7 + 3) as f32 * 0.61).sin() * 3.0).collect();
+        let b: Vec<f32> = (0..len).map(|i| ((i * 5 + 1) as f32 * 0.29).cos() * 100.0 + 120.0).collect();
+        for element_type in [VectorElementType::Float16, VectorElementType::BFloat16, VectorElementType::Uint8] {
+            let stored = StoredVector::encode(b.clone(), element_type);
+            let widened = stored.as_ref().to_f32();
+            for level in SimdLevel::ALL.into_iter().filter(|l| l.is_available()) {
+                let k = Kernels::for_level(level).unwrap();
+                let expected_dot = reference_distance(&a, &widened, DistanceMetric::Dot);
+                let expected_l2 = reference_distance(&a, &widened, DistanceMetric::Euclidean).powi(2);
+                let tol = 1e-4 * (1.0 + expected_l2.abs().max(expected_dot.abs()));
+                let dot = k.dot_ref(&a, stored.as_ref()) as f64;
+                let l2 = k.l2_squared_ref(&a, stored.as_ref()) as f64;
+                assert!((dot - expected_dot).abs() < tol, "{:?} {:?} dot len {}", level, element_type, len);
+                assert!((l2 - expected_l2).abs() < tol, "{:?} {:?} l2 len {}", level, element_type, len);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_score_normalized_matches_score_for_unit_vectors() {
+    let normalize = |v: Vec<f32>| {
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.into_iter().map(|x| x / norm).collect::<Vec<f32>>()
+    };
+    let a = normalize((0..33).map(|i| (i as f32 * 0.4).sin()).collect());
+    let b = normalize((0..33).map(|i| (i as f32 * 0.9).cos()).collect());
+    for metric in [DistanceMetric::Cosine, DistanceMetric::Dot, DistanceMetric::Euclidean] {
+        let fast = score_normalized(&a, &b, metric);
+        let full = score(&a, &b, metric);
+        assert!((fast - full).abs() < 1e-5, "{:?}: {} vs {}", metric, fast, full);
+    }
+}
+
+#[test]
+fn test_additional_metrics() {
+    let a = vec![1.0, -2.0, 3.0, 0.0];
+    let b = vec![4.0, 0.0, 1.0, 0.0];
+    assert_eq!(score(&a, &b, DistanceMetric::Manhattan), 3.0 + 2.0 + 2.0);
+    assert_eq!(score(&a, &b, DistanceMetric::Chebyshev), 3.0);
+
+    let x = vec![1.0, 0.0, 1.0, 1.0, 0.0];
+    let y = vec![1.0, 1.0, 0.0, 1.0, 0.0];
+    assert_eq!(score(&x, &y, DistanceMetric::Hamming), 2.0);
+    // |A ∩ B| = 2, |A ∪ B| = 4
+    assert_eq!(score(&x, &y, DistanceMetric::Jaccard), 0.5);
+
+    let empty = vec![0.0; 5];
+    assert_eq!(score(&empty, &empty, DistanceMetric::Jaccard), 0.0);
+    assert_eq!(score(&x, &empty, DistanceMetric::Jaccard), 1.0);
+    assert_eq!(score(&x, &x, DistanceMetric::Hamming), 0.0);
+}
@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use vectordb::evaluation::recall::{Dataset, EvalConfig, evaluate};
+use vectordb::evaluation::vecs::{
+    insert_from_file, read_bvecs, read_fvecs, read_ground_truth, read_ivecs, write_bvecs, write_fvecs, write_ivecs,
+};
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::types::{DistanceMetric, Vector};
+use vectordb::vector::hnsw::HNSWIndex;
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_{}_{}.{}", name, std::process::id(), ext));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn sample_vectors(n: usize, dim: usize) -> Vec<Vector> {
+    (0..n).map(|i| (0..dim).map(|d| ((i * dim + d) as f32 * 0.37).sin() * 10.0).collect()).collect()
+}
+
+#[test]
+fn test_vecs_round_trip_and_limit() {
+    let fpath = temp_path("roundtrip", "fvecs");
+    let vectors = sample_vectors(20, 5);
+    write_fvecs(&fpath, &vectors).unwrap();
+    assert_eq!(read_fvecs(&fpath, None).unwrap(), vectors);
+    assert_eq!(read_fvecs(&fpath, Some(3)).unwrap(), vectors[..3].to_vec());
+
+    let ipath = temp_path("roundtrip", "ivecs");
+    let rows = vec![vec![3, 1, 4], vec![-1, 5, 9]];
+    write_ivecs(&ipath, &rows).unwrap();
+    assert_eq!(read_ivecs(&ipath, None).unwrap(), rows);
+    // Ground truth must be row indices.
+    assert!(matches!(read_ground_truth(&ipath, None), Err(DBError::SerializationError(_))));
+
+    let bpath = temp_path("roundtrip", "bvecs");
+    write_bvecs(&bpath, &[vec![0, 128, 255], vec![7, 8, 9]]).unwrap();
+    assert_eq!(read_bvecs(&bpath, None).unwrap(), vec![vec![0.0, 128.0, 255.0], vec![7.0, 8.0, 9.0]]);
+
+    for path in [fpath, ipath, bpath] {
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_vecs_rejects_truncated_and_ragged_files() {
+    let path = temp_path("bad", "fvecs");
+    write_fvecs(&path, &sample_vectors(4, 3)).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+    assert!(matches!(read_fvecs(&path, None), Err(DBError::SerializationError(_))));
+    // Records before the damage are still readable.
+    assert_eq!(read_fvecs(&path, Some(3)).unwrap().len(), 3);
+
+    write_fvecs(&path, &[vec![1.0, 2.0], vec![1.0, 2.0, 3.0]]).unwrap();
+    assert!(matches!(
+        read_fvecs(&path, None),
+        Err(DBError::VectorLengthMismatch { expected: 2, actual: 3 })
+    ));
+
+    // Empty rows would be unreadable, so they are never written.
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(write_fvecs(&path, &[vec![1.0], vec![]]), Err(DBError::SerializationError(_))));
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_insert_from_file_feeds_segment() {
+    let path = temp_path("insert", "fvecs");
+    let vectors = sample_vectors(300, 4);
+    write_fvecs(&path, &vectors).unwrap();
+
+    let mut segment = Segment::new(HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 4));
+    let ids = insert_from_file(&mut segment, &path, None).unwrap();
+    assert_eq!(ids.len(), 300);
+    assert_eq!(segment.get_vector(ids[123]).as_deref(), Some(vectors[123].as_slice()));
+
+    let results = segment.search(&vectors[42], 1).unwrap();
+    assert_eq!(results[0].id, ids[42]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_dataset_from_files_uses_shipped_ground_truth() {
+    let base_path = temp_path("dataset_base", "fvecs");
+    let query_path = temp_path("dataset_query", "fvecs");
+    let gt_path = temp_path("dataset_gt", "ivecs");
+
+    let base = sample_vectors(800, 6);
+    let queries: Vec<Vector> = sample_vectors(810, 6).split_off(800);
+    // Brute-force Euclidean neighbors, stored as row indices like the SIFT ground truth files.
+    let gt: Vec<Vec<i32>> = queries
+        .iter()
+        .map(|q| {
+            let mut rows: Vec<_> = base
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, v.iter().zip(q).map(|(a, b)| (a - b) * (a - b)).sum::<f32>()))
+                .collect();
+            rows.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            rows.iter().take(10).map(|&(i, _)| i as i32).collect()
+        })
+        .collect();
+    write_fvecs(&base_path, &base).unwrap();
+    write_fvecs(&query_path, &queries).unwrap();
+    write_ivecs(&gt_path, &gt).unwrap();
+
+    let dataset =
+        Dataset::from_files("synthetic", &base_path, &query_path, Some((&gt_path, DistanceMetric::Euclidean)), None)
+            .unwrap();
+    assert_eq!(dataset.vectors.len(), 800);
+    assert_eq!(dataset.queries.len(), 10);
+    assert_eq!(dataset.ground_truth.as_ref().unwrap().neighbors[0].len(), 10);
+
+    let config = EvalConfig { metrics: vec![DistanceMetric::Euclidean], ef_values: vec![200], ..EvalConfig::default() };
+    let reports = evaluate(&dataset, &config).unwrap();
+    assert!(reports[0].recall >= 0.95, "{}", reports[0]);
+
+    // Ground truth that points past the loaded base set is rejected rather than silently scored.
+    let truncated =
+        Dataset::from_files("synthetic", &base_path, &query_path, Some((&gt_path, DistanceMetric::Euclidean)), Some(50))
+            .unwrap();
+    assert!(matches!(evaluate(&truncated, &config), Err(DBError::SerializationError(_))));
+
+    for path in [base_path, query_path, gt_path] {
+        std::fs::remove_file(&path).unwrap();
+    }
+}
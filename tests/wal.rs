@@ -0,0 +1,187 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use vectordb::segment::segment::Segment;
+use vectordb::utils::errors::DBError;
+use vectordb::utils::payload::{Payload, PayloadValue};
+use vectordb::utils::types::{DistanceMetric, Vector};
+use vectordb::vector::hnsw::HNSWIndex;
+
+fn vecf(v: &[f32]) -> Vector {
+    v.to_vec()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vectordb_{}_{}.wal", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_wal_replays_inserts_deletes_and_payloads() {
+    let path = temp_path("replay");
+    let mut ids = Vec::new();
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 3);
+        let mut segment = Segment::create(&path, hnsw).unwrap();
+        for i in 0..50 {
+            let mut payload = Payload::default();
+            payload.set("idx", PayloadValue::Int(i));
+            payload.set("tags", PayloadValue::ListStr(vec![format!("t{}", i % 3)]));
+            ids.push(segment.insert(vecf(&[i as f32, 0.0, 1.0]), Some(payload)).unwrap());
+        }
+        ids.push(segment.insert(vecf(&[100.0, 0.0, 1.0]), None).unwrap());
+        segment.delete(ids[3]).unwrap();
+        segment.delete(ids[7]).unwrap();
+        // Segment is dropped without any explicit shutdown, as in a crash.
+    }
+
+    let mut reopened = Segment::open(&path).unwrap();
+    assert_eq!(reopened.hnsw().dim(), 3);
+    assert_eq!(reopened.hnsw().metric(), DistanceMetric::Euclidean);
+    assert!(reopened.is_deleted(ids[3]));
+    assert!(reopened.is_deleted(ids[7]));
+    assert!(reopened.get_vector(ids[3]).is_none());
+    assert_eq!(reopened.get_vector(ids[10]).as_deref(), Some(&[10.0, 0.0, 1.0][..]));
+    assert_eq!(reopened.get_payload(ids[10]).unwrap().get("idx"), Some(&PayloadValue::Int(10)));
+    assert!(reopened.get_payload(ids[50]).is_none());
+
+    let results = reopened.search(&vecf(&[20.0, 0.0, 1.0]), 1).unwrap();
+    assert_eq!(results[0].id, ids[20]);
+
+    // New IDs continue after the replayed ones, and new writes are logged too.
+    let new_id = reopened.insert(vecf(&[-5.0, 0.0, 1.0]), None).unwrap();
+    assert_eq!(new_id, ids[50] + 1);
+    reopened.purge().unwrap();
+    drop(reopened);
+
+    let again = Segment::open(&path).unwrap();
+    assert!(again.get_vector(new_id).is_some());
+    assert!(again.get_vector(ids[3]).is_none());
+    assert!(!again.is_deleted(ids[3]), "purge should have been replayed");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_create_refuses_existing_file() {
+    let path = temp_path("exists");
+    let hnsw = HNSWIndex::new(DistanceMetric::Cosine, 8, 32, 8, 2);
+    drop(Segment::create(&path, hnsw).unwrap());
+
+    let hnsw = HNSWIndex::new(DistanceMetric::Cosine, 8, 32, 8, 2);
+    assert!(matches!(Segment::create(&path, hnsw), Err(DBError::IOError(_))));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_invalid_insert_is_not_logged() {
+    let path = temp_path("invalid");
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Dot, 8, 32, 8, 2);
+        let mut segment = Segment::create(&path, hnsw).unwrap();
+        segment.insert(vecf(&[1.0, 2.0]), None).unwrap();
+        assert!(segment.insert(vecf(&[1.0, 2.0, 3.0]), None).is_err());
+    }
+
+    let segment = Segment::open(&path).unwrap();
+    assert_eq!(segment.hnsw().len(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_torn_tail_is_dropped() {
+    let path = temp_path("torn");
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2);
+        let mut segment = Segment::create(&path, hnsw).unwrap();
+        segment.insert(vecf(&[1.0, 1.0]), None).unwrap();
+        segment.insert(vecf(&[2.0, 2.0]), None).unwrap();
+    }
+
+    // Simulate a crash in the middle of writing a frame: a length prefix with no body.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[64, 0, 0, 0, 1, 2]).unwrap();
+    drop(file);
+
+    let mut segment = Segment::open(&path).unwrap();
+    assert_eq!(segment.hnsw().len(), 2);
+    let id = segment.insert(vecf(&[3.0, 3.0]), None).unwrap();
+    drop(segment);
+
+    let segment = Segment::open(&path).unwrap();
+    assert!(segment.get_vector(id).is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_truncated_insert_record_is_reported() {
+    let path = temp_path("short_insert");
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2);
+        let mut segment = Segment::create(&path, hnsw).unwrap();
+        segment.insert(vecf(&[1.0, 1.0]), None).unwrap();
+    }
+
+    // A well-framed insert whose body stops after the payload flag: tag, point ID, vector, no payload.
+    let mut body = vec![0u8];
+    body.extend_from_slice(&2u64.to_le_bytes());
+    body.extend_from_slice(&2u64.to_le_bytes());
+    body.extend_from_slice(&2.0f32.to_le_bytes());
+    body.extend_from_slice(&2.0f32.to_le_bytes());
+    body.push(0);
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&(body.len() as u32).to_le_bytes()).unwrap();
+    file.write_all(&crc32fast::hash(&body).to_le_bytes()).unwrap();
+    file.write_all(&body).unwrap();
+    drop(file);
+
+    assert!(matches!(Segment::open(&path), Err(DBError::WALCorrupt(_))));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_checksum_mismatch_is_reported() {
+    let path = temp_path("corrupt");
+    {
+        let hnsw = HNSWIndex::new(DistanceMetric::Euclidean, 8, 32, 8, 2);
+        let mut segment = Segment::create(&path, hnsw).unwrap();
+        segment.insert(vecf(&[1.0, 1.0]), None).unwrap();
+        segment.insert(vecf(&[2.0, 2.0]), None).unwrap();
+    }
+
+    // Flip a byte inside the last record's vector data.
+    let mut bytes = std::fs::read(&path).unwrap();
+    let last = bytes.len() - 2;
+    bytes[last] ^= 0xFF;
+    std::fs::write(&path, &bytes).unwrap();
+
+    assert!(matches!(Segment::open(&path), Err(DBError::WALCorrupt(_))));
+
+    // A damaged header is rejected as well.
+    bytes[0] = b'X';
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(Segment::open(&path), Err(DBError::WALCorrupt(_))));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wal_preserves_additional_metrics() {
+    for metric in [DistanceMetric::Manhattan, DistanceMetric::Chebyshev, DistanceMetric::Hamming, DistanceMetric::Jaccard] {
+        let path = temp_path(&format!("metric_{:?}", metric));
+        {
+            let hnsw = HNSWIndex::new(metric, 8, 32, 8, 3);
+            let mut segment = Segment::create(&path, hnsw).unwrap();
+            segment.insert(vecf(&[1.0, 0.0, 1.0]), None).unwrap();
+        }
+        let segment = Segment::open(&path).unwrap();
+        assert_eq!(segment.hnsw().metric(), metric);
+        std::fs::remove_file(&path).unwrap();
+    }
+}